@@ -0,0 +1,98 @@
+use std::io;
+use std::io::{Read, Write};
+use std::mem::{size_of, size_of_val};
+
+use crate::atomic_long::AtomicConfiguration;
+use crate::error::Result;
+use crate::protocol::{
+    read_i64, write_bool, write_i32, write_i64, write_null, write_string_type_code, write_u8,
+};
+use crate::{ReadableReq, WriteableReq};
+
+/// Atomic Long Create 9000
+pub(crate) struct AtomicLongCreateReq<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) initial_value: i64,
+    pub(crate) config: Option<&'a AtomicConfiguration>,
+}
+
+impl WriteableReq for AtomicLongCreateReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_string_type_code(writer, self.name)?;
+        write_i64(writer, self.initial_value)?;
+        write_bool(writer, self.config.is_some())?;
+        if let Some(config) = self.config {
+            write_i32(writer, config.atomic_sequence_reserve_size)?;
+            write_u8(writer, config.cache_mode.clone() as u8)?;
+            write_i32(writer, config.backups)?;
+            write_opt_str(writer, config.group_name.as_deref())?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        let config_size = match self.config {
+            Some(config) => {
+                size_of::<i32>() // sequence reserve size
+                    + size_of::<u8>() // cache mode
+                    + size_of::<i32>() // backups
+                    + opt_str_size(config.group_name.as_deref())
+            }
+            None => 0,
+        };
+        self.name.len() + 5 // string itself, type code, len
+            + size_of::<i64>() // initial value
+            + size_of::<bool>() // has configuration
+            + config_size
+    }
+}
+
+/// Atomic Long Remove 9001, Exists 9002 and the value operations 9003-9006.
+/// All of them start with the name and the group name, followed by the long arguments
+pub(crate) struct AtomicLongReq<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) group_name: Option<&'a str>,
+    pub(crate) args: &'a [i64],
+}
+
+impl WriteableReq for AtomicLongReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_string_type_code(writer, self.name)?;
+        write_opt_str(writer, self.group_name)?;
+        for arg in self.args {
+            write_i64(writer, *arg)?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.name.len() + 5 // string itself, type code, len
+            + opt_str_size(self.group_name)
+            + size_of_val(self.args) // long args
+    }
+}
+
+pub(crate) struct AtomicLongValueResp {
+    pub(crate) value: i64,
+}
+
+impl ReadableReq for AtomicLongValueResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let value = read_i64(reader)?;
+        Ok(AtomicLongValueResp { value })
+    }
+}
+
+fn write_opt_str(writer: &mut dyn Write, value: Option<&str>) -> io::Result<()> {
+    match value {
+        Some(value) => write_string_type_code(writer, value),
+        None => write_null(writer),
+    }
+}
+
+fn opt_str_size(value: Option<&str>) -> usize {
+    match value {
+        Some(value) => value.len() + 5, // string itself, type code, len
+        None => 1,                      // null type code
+    }
+}
@@ -0,0 +1,125 @@
+use std::io;
+use std::io::{Read, Write};
+use std::mem::size_of;
+
+use crate::error::{Error, Result};
+use crate::protocol::binary_object::{BinaryField, BinaryType};
+use crate::protocol::{prealloc_len, read_bool, read_i32, write_i32};
+#[cfg(any(test, feature = "fake-server"))]
+use crate::protocol::{write_bool, write_null, write_string_type_code};
+use crate::{ReadableReq, ReadableType, WriteableReq};
+
+/// Get Binary Type 3002
+pub(crate) struct BinaryTypeGetReq {
+    pub(crate) type_id: i32,
+}
+
+impl WriteableReq for BinaryTypeGetReq {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i32(writer, self.type_id)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i32>() // type id
+    }
+}
+
+pub(crate) struct BinaryTypeGetResp {
+    pub(crate) binary_type: Option<BinaryType>,
+}
+
+impl ReadableReq for BinaryTypeGetResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let binary_type = match read_bool(reader)? {
+            true => Some(read_binary_type(reader)?),
+            false => None,
+        };
+        Ok(BinaryTypeGetResp { binary_type })
+    }
+}
+
+fn read_binary_type(reader: &mut impl Read) -> Result<BinaryType> {
+    let type_id = read_i32(reader)?;
+    let type_name = String::read(reader)?.ok_or_else(|| Error::from("Type name is NULL"))?;
+    let affinity_key_field = String::read(reader)?;
+
+    let count = read_i32(reader)?;
+    let mut fields = Vec::with_capacity(prealloc_len(count));
+    for _ in 0..count {
+        fields.push(BinaryField {
+            name: String::read(reader)?.ok_or_else(|| Error::from("Field name is NULL"))?,
+            type_id: read_i32(reader)?,
+            field_id: read_i32(reader)?,
+        });
+    }
+
+    let is_enum = read_bool(reader)?;
+    let mut enum_values = Vec::new();
+    if is_enum {
+        let count = read_i32(reader)?;
+        enum_values.reserve(prealloc_len(count));
+        for _ in 0..count {
+            let name = String::read(reader)?.ok_or_else(|| Error::from("Enum name is NULL"))?;
+            enum_values.push((name, read_i32(reader)?));
+        }
+    }
+
+    let count = read_i32(reader)?;
+    let mut schemas = Vec::with_capacity(prealloc_len(count));
+    for _ in 0..count {
+        let schema_id = read_i32(reader)?;
+        let field_count = read_i32(reader)?;
+        let mut field_ids = Vec::with_capacity(prealloc_len(field_count));
+        for _ in 0..field_count {
+            field_ids.push(read_i32(reader)?);
+        }
+        schemas.push((schema_id, field_ids));
+    }
+
+    Ok(BinaryType {
+        type_id,
+        type_name,
+        affinity_key_field,
+        fields,
+        is_enum,
+        enum_values,
+        schemas,
+    })
+}
+
+/// Writes the binary type the way `read_binary_type` reads it
+#[cfg(any(test, feature = "fake-server"))]
+pub(crate) fn write_binary_type(
+    writer: &mut dyn Write,
+    binary_type: &BinaryType,
+) -> io::Result<()> {
+    write_i32(writer, binary_type.type_id)?;
+    write_string_type_code(writer, &binary_type.type_name)?;
+    match binary_type.affinity_key_field {
+        Some(ref name) => write_string_type_code(writer, name)?,
+        None => write_null(writer)?,
+    }
+    write_i32(writer, binary_type.fields.len() as i32)?;
+    for field in binary_type.fields.iter() {
+        write_string_type_code(writer, &field.name)?;
+        write_i32(writer, field.type_id)?;
+        write_i32(writer, field.field_id)?;
+    }
+    write_bool(writer, binary_type.is_enum)?;
+    if binary_type.is_enum {
+        write_i32(writer, binary_type.enum_values.len() as i32)?;
+        for (name, ordinal) in binary_type.enum_values.iter() {
+            write_string_type_code(writer, name)?;
+            write_i32(writer, *ordinal)?;
+        }
+    }
+    write_i32(writer, binary_type.schemas.len() as i32)?;
+    for (schema_id, field_ids) in binary_type.schemas.iter() {
+        write_i32(writer, *schema_id)?;
+        write_i32(writer, field_ids.len() as i32)?;
+        for field_id in field_ids {
+            write_i32(writer, *field_id)?;
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,10 @@
-use std::io::{Read, Write};
+use std::io::{Cursor, Read, Write};
 
 use crate::cache::CacheConfiguration;
 use crate::error::{Error, Result};
-use crate::protocol::cache_config::{get_cache_configuration_bytes, read_cache_configuration};
+use crate::protocol::cache_config::{
+    get_cache_configuration_bytes, read_cache_configuration, read_opt_expiry_policy,
+};
 use crate::protocol::{
     read_i32, write_bool, write_i32, write_i64, write_null, write_string_type_code, write_u8,
 };
@@ -105,7 +107,7 @@ pub(crate) struct CacheCreateWithNameReq<'a> {
 }
 
 impl CacheCreateWithNameReq<'_> {
-    pub(crate) fn from(name: &str) -> CacheCreateWithNameReq {
+    pub(crate) fn from(name: &str) -> CacheCreateWithNameReq<'_> {
         CacheCreateWithNameReq { name }
     }
 }
@@ -126,7 +128,7 @@ pub(crate) struct CacheGetOrCreateWithNameReq<'a> {
 }
 
 impl CacheGetOrCreateWithNameReq<'_> {
-    pub(crate) fn from(name: &str) -> CacheGetOrCreateWithNameReq {
+    pub(crate) fn from(name: &str) -> CacheGetOrCreateWithNameReq<'_> {
         CacheGetOrCreateWithNameReq { name }
     }
 }
@@ -177,7 +179,7 @@ pub(crate) struct CacheGetConfigReq<'a> {
 }
 
 impl CacheGetConfigReq<'_> {
-    pub(crate) fn from(name: &str) -> CacheGetConfigReq {
+    pub(crate) fn from(name: &str) -> CacheGetConfigReq<'_> {
         CacheGetConfigReq { name }
     }
 }
@@ -201,7 +203,14 @@ pub(crate) struct CacheGetConfigResp {
 impl ReadableReq for CacheGetConfigResp {
     fn read(reader: &mut impl Read) -> Result<Self> {
         let _ = read_i32(reader)?;
-        let config = read_cache_configuration(reader)?;
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        let mut body = Cursor::new(body);
+        let mut config = read_cache_configuration(&mut body)?;
+        // sent by servers since v1.6.0
+        if (body.position() as usize) < body.get_ref().len() {
+            config.expiry_policy = read_opt_expiry_policy(&mut body)?;
+        }
         Ok(CacheGetConfigResp { config })
     }
 }
@@ -212,7 +221,7 @@ pub(crate) struct CacheDestroyReq<'a> {
 }
 
 impl CacheDestroyReq<'_> {
-    pub(crate) fn from(name: &str) -> CacheDestroyReq {
+    pub(crate) fn from(name: &str) -> CacheDestroyReq<'_> {
         CacheDestroyReq { name }
     }
 }
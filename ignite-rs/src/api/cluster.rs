@@ -0,0 +1,338 @@
+use std::convert::TryFrom;
+use std::io;
+use std::io::{Read, Write};
+use std::mem::size_of;
+
+use crate::cluster::{ClusterNode, ClusterState, NodeEndpoints, NodeFilter, NodeVersion};
+use crate::error::{Error, Result};
+#[cfg(any(test, feature = "fake-server"))]
+use crate::protocol::complex_obj::write_value;
+use crate::protocol::complex_obj::{read_value, IgniteValue};
+use crate::protocol::{
+    prealloc_len, read_bool, read_i32, read_i64, read_u8, read_uuid, write_bool, write_i16,
+    write_i32, write_i64, write_string_type_code, write_u8, write_uuid, TypeCode,
+};
+#[cfg(any(test, feature = "fake-server"))]
+use crate::protocol::{read_i16, CollectionKind};
+#[cfg(any(test, feature = "fake-server"))]
+use crate::WritableType;
+use crate::{ReadableReq, ReadableType, Uuid, WriteableReq};
+
+/// Cluster Get State 5000
+pub(crate) struct ClusterGetStateReq {}
+
+impl WriteableReq for ClusterGetStateReq {
+    fn write(&self, _: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        0
+    }
+}
+
+pub(crate) struct ClusterGetStateResp {
+    pub(crate) state: ClusterState,
+}
+
+impl ReadableReq for ClusterGetStateResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let state = ClusterState::try_from(read_u8(reader)?)?;
+        Ok(ClusterGetStateResp { state })
+    }
+}
+
+/// Cluster Change State 5001
+pub(crate) struct ClusterChangeStateReq {
+    pub(crate) state: ClusterState,
+}
+
+impl WriteableReq for ClusterChangeStateReq {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u8(writer, self.state.into())
+    }
+
+    fn size(&self) -> usize {
+        size_of::<u8>() // state
+    }
+}
+
+/// Cluster Change WAL State 5002
+pub(crate) struct ClusterChangeWalStateReq<'a> {
+    pub(crate) cache_name: &'a str,
+    pub(crate) enabled: bool,
+}
+
+impl WriteableReq for ClusterChangeWalStateReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_string_type_code(writer, self.cache_name)?;
+        write_bool(writer, self.enabled)
+    }
+
+    fn size(&self) -> usize {
+        self.cache_name.len() + 5 // string itself, type code, len
+            + size_of::<u8>() // enabled flag
+    }
+}
+
+/// Cluster Get WAL State 5003
+pub(crate) struct ClusterGetWalStateReq<'a> {
+    pub(crate) cache_name: &'a str,
+}
+
+impl WriteableReq for ClusterGetWalStateReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_string_type_code(writer, self.cache_name)
+    }
+
+    fn size(&self) -> usize {
+        self.cache_name.len() + 5 // string itself, type code, len
+    }
+}
+
+// https://ignite.apache.org/docs/latest/binary-client-protocol/cluster-api#op_cluster_group_get_node_ids
+const FILTER_ATTRIBUTE: i16 = 1;
+const FILTER_SERVER_NODES: i16 = 2;
+/// Makes the server return the node ids whatever the current topology version is
+const UNKNOWN_TOP_VER: i64 = -1;
+
+/// Cluster Group Get Node Ids 5100
+pub(crate) struct ClusterGroupGetNodeIdsReq<'a> {
+    pub(crate) filters: &'a [NodeFilter],
+}
+
+impl WriteableReq for ClusterGroupGetNodeIdsReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i64(writer, UNKNOWN_TOP_VER)?;
+        write_bool(writer, !self.filters.is_empty())?;
+        if self.filters.is_empty() {
+            return Ok(());
+        }
+        write_i32(writer, self.filters.len() as i32)?;
+        for filter in self.filters {
+            match filter {
+                NodeFilter::Attribute(name, value) => {
+                    write_i16(writer, FILTER_ATTRIBUTE)?;
+                    write_string_type_code(writer, name)?;
+                    write_string_type_code(writer, value)?;
+                }
+                NodeFilter::ServerNodes(server) => {
+                    write_i16(writer, FILTER_SERVER_NODES)?;
+                    write_bool(writer, *server)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        let filters_size: usize = self
+            .filters
+            .iter()
+            .map(|filter| match filter {
+                NodeFilter::Attribute(name, value) => {
+                    size_of::<i16>() + name.len() + 5 + value.len() + 5 // code, name, value
+                }
+                NodeFilter::ServerNodes(_) => size_of::<i16>() + size_of::<u8>(), // code, flag
+            })
+            .sum();
+        size_of::<i64>() // topology version
+            + size_of::<u8>() // has filters flag
+            + match self.filters.is_empty() {
+                true => 0,
+                false => size_of::<i32>() + filters_size, // filter count, filters
+            }
+    }
+}
+
+pub(crate) struct ClusterGroupGetNodeIdsResp {
+    pub(crate) ids: Vec<Uuid>,
+}
+
+impl ReadableReq for ClusterGroupGetNodeIdsResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        if !read_bool(reader)? {
+            return Err(Error::from("Topology is not expected to be unchanged"));
+        }
+        let _top_ver = read_i64(reader)?;
+        let count = read_i32(reader)?;
+        let mut ids = Vec::with_capacity(prealloc_len(count));
+        for _ in 0..count {
+            ids.push(read_uuid(reader)?);
+        }
+        Ok(ClusterGroupGetNodeIdsResp { ids })
+    }
+}
+
+/// Cluster Group Get Node Info 5101
+pub(crate) struct ClusterGroupGetNodeInfoReq<'a> {
+    pub(crate) ids: &'a [Uuid],
+}
+
+impl WriteableReq for ClusterGroupGetNodeInfoReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i32(writer, self.ids.len() as i32)?;
+        for id in self.ids {
+            write_uuid(writer, *id)?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i32>() + self.ids.len() * 16 // count, ids
+    }
+}
+
+pub(crate) struct ClusterGroupGetNodeInfoResp {
+    pub(crate) nodes: Vec<ClusterNode>,
+}
+
+impl ReadableReq for ClusterGroupGetNodeInfoResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let count = read_i32(reader)?;
+        let mut nodes = Vec::with_capacity(prealloc_len(count));
+        for _ in 0..count {
+            nodes.push(read_cluster_node(reader)?);
+        }
+        Ok(ClusterGroupGetNodeInfoResp { nodes })
+    }
+}
+
+/// Cluster Group Get Node Endpoints 5102
+pub(crate) struct ClusterGroupGetNodeEndpointsReq {}
+
+impl WriteableReq for ClusterGroupGetNodeEndpointsReq {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        // the difference between the empty topology and the current one, i.e. all the nodes
+        write_i64(writer, UNKNOWN_TOP_VER)?;
+        write_i64(writer, UNKNOWN_TOP_VER)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i64>() // start topology version
+            + size_of::<i64>() // end topology version
+    }
+}
+
+pub(crate) struct ClusterGroupGetNodeEndpointsResp {
+    pub(crate) endpoints: Vec<NodeEndpoints>,
+}
+
+impl ReadableReq for ClusterGroupGetNodeEndpointsResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let _top_ver = read_i64(reader)?;
+        let count = read_i32(reader)?;
+        let mut endpoints = Vec::with_capacity(prealloc_len(count));
+        for _ in 0..count {
+            let id = read_uuid(reader)?;
+            let port = read_i32(reader)?;
+            let addr_count = read_i32(reader)?;
+            let mut addresses = Vec::with_capacity(prealloc_len(addr_count));
+            for _ in 0..addr_count {
+                addresses.push(
+                    String::read(reader)?.ok_or_else(|| Error::from("NULL is not expected"))?,
+                );
+            }
+            endpoints.push(NodeEndpoints {
+                id,
+                port,
+                addresses,
+            });
+        }
+        // ids of the removed nodes are skipped, there are none since the empty topology
+        let removed = read_i32(reader)?;
+        for _ in 0..removed {
+            read_uuid(reader)?;
+        }
+        Ok(ClusterGroupGetNodeEndpointsResp { endpoints })
+    }
+}
+
+fn read_cluster_node(reader: &mut impl Read) -> Result<ClusterNode> {
+    let id = Uuid::read(reader)?.ok_or_else(|| Error::from("Node id is NULL"))?;
+    let attr_count = read_i32(reader)?;
+    let mut attributes = Vec::with_capacity(prealloc_len(attr_count));
+    for _ in 0..attr_count {
+        let name = String::read(reader)?.ok_or_else(|| Error::from("NULL is not expected"))?;
+        attributes.push((name, read_data_object(reader)?));
+    }
+    Ok(ClusterNode {
+        id,
+        attributes,
+        addresses: Vec::<String>::read(reader)?.unwrap_or_default(),
+        host_names: Vec::<String>::read(reader)?.unwrap_or_default(),
+        order: read_i64(reader)?,
+        is_local: read_bool(reader)?,
+        is_daemon: read_bool(reader)?,
+        is_client: read_bool(reader)?,
+        consistent_id: read_data_object(reader)?,
+        version: NodeVersion {
+            major: read_u8(reader)?,
+            minor: read_u8(reader)?,
+            maintenance: read_u8(reader)?,
+            stage: String::read(reader)?.unwrap_or_default(),
+            revision_timestamp: read_i64(reader)?,
+            revision_hash: Vec::<u8>::read(reader)?.unwrap_or_default(),
+        },
+    })
+}
+
+fn read_data_object(reader: &mut impl Read) -> Result<IgniteValue> {
+    read_value(TypeCode::try_from(read_u8(reader)?)?, reader)
+}
+
+/// Writes the node the way `read_cluster_node` reads it
+#[cfg(any(test, feature = "fake-server"))]
+pub(crate) fn write_cluster_node(writer: &mut dyn Write, node: &ClusterNode) -> io::Result<()> {
+    node.id.write(writer)?;
+    write_i32(writer, node.attributes.len() as i32)?;
+    for (name, value) in &node.attributes {
+        write_string_type_code(writer, name)?;
+        write_value(writer, value)?;
+    }
+    for strings in [&node.addresses, &node.host_names] {
+        let items = strings.iter().cloned().map(IgniteValue::String).collect();
+        write_value(
+            writer,
+            &IgniteValue::Collection(CollectionKind::ArrayList, items),
+        )?;
+    }
+    write_i64(writer, node.order)?;
+    write_bool(writer, node.is_local)?;
+    write_bool(writer, node.is_daemon)?;
+    write_bool(writer, node.is_client)?;
+    write_value(writer, &node.consistent_id)?;
+    write_u8(writer, node.version.major)?;
+    write_u8(writer, node.version.minor)?;
+    write_u8(writer, node.version.maintenance)?;
+    write_string_type_code(writer, &node.version.stage)?;
+    write_i64(writer, node.version.revision_timestamp)?;
+    node.version.revision_hash.write(writer)
+}
+
+/// Reads the filters written by `ClusterGroupGetNodeIdsReq`
+#[cfg(any(test, feature = "fake-server"))]
+pub(crate) fn read_node_filters(reader: &mut impl Read) -> Result<Vec<NodeFilter>> {
+    let mut filters = Vec::new();
+    if !read_bool(reader)? {
+        return Ok(filters);
+    }
+    let count = read_i32(reader)?;
+    for _ in 0..count {
+        let filter = match read_i16(reader)? {
+            FILTER_ATTRIBUTE => {
+                let name = String::read(reader)?.unwrap_or_default();
+                let value = String::read(reader)?.unwrap_or_default();
+                NodeFilter::Attribute(name, value)
+            }
+            FILTER_SERVER_NODES => NodeFilter::ServerNodes(read_bool(reader)?),
+            code => {
+                return Err(Error::from(
+                    format!("Unknown node filter {}", code).as_str(),
+                ))
+            }
+        };
+        filters.push(filter);
+    }
+    Ok(filters)
+}
@@ -0,0 +1,37 @@
+use std::io;
+use std::io::Write;
+use std::mem::size_of;
+
+use crate::protocol::{write_i32, write_i64, write_string_type_code, write_u8};
+use crate::{WritableType, WriteableReq};
+
+// https://ignite.apache.org/docs/latest/binary-client-protocol/compute-api#op_compute_task_execute
+pub(crate) const FLAG_NO_FAILOVER: u8 = 0x01;
+pub(crate) const FLAG_NO_RESULT_CACHE: u8 = 0x02;
+
+/// Compute Task Execute 6000
+pub(crate) struct ComputeTaskExecuteReq<'a, A: WritableType> {
+    pub(crate) flags: u8,
+    /// Milliseconds, 0 for no timeout
+    pub(crate) timeout: i64,
+    pub(crate) task_name: &'a str,
+    pub(crate) arg: &'a A,
+}
+
+impl<A: WritableType> WriteableReq for ComputeTaskExecuteReq<'_, A> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i32(writer, 0)?; // node count, 0 for all the server nodes
+        write_u8(writer, self.flags)?;
+        write_i64(writer, self.timeout)?;
+        write_string_type_code(writer, self.task_name)?;
+        self.arg.write(writer)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i32>() // node count
+            + size_of::<u8>() // flags
+            + size_of::<i64>() // timeout
+            + self.task_name.len() + 5 // string itself, type code, len
+            + self.arg.size()
+    }
+}
@@ -0,0 +1,81 @@
+use std::convert::TryFrom;
+use std::io;
+use std::io::{Read, Write};
+use std::mem::size_of;
+
+use crate::continuous_query::{CacheEntryEvent, CacheEntryEventType};
+use crate::error::Result;
+use crate::protocol::{
+    read_i32, read_i64, read_u8, write_bool, write_i32, write_i64, write_null, write_u8,
+};
+use crate::{ReadableReq, ReadableType, WriteableReq};
+
+// https://ignite.apache.org/docs/latest/binary-client-protocol/sql-and-scan-queries#op_query_continuous
+const FLAGS: u8 = 0;
+const INITIAL_QUERY_NONE: u8 = 0;
+
+/// Query Continuous 2006. The filter, the transformer and the initial query are not supported
+pub(crate) struct QueryContinuousReq {
+    pub(crate) cache_id: i32,
+    pub(crate) page_size: i32,
+    /// Milliseconds, 0 to send the events only when the page is full
+    pub(crate) time_interval: i64,
+    pub(crate) include_expired: bool,
+}
+
+impl WriteableReq for QueryContinuousReq {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i32(writer, self.cache_id)?;
+        write_u8(writer, FLAGS)?;
+        write_i32(writer, self.page_size)?;
+        write_i64(writer, self.time_interval)?;
+        write_bool(writer, self.include_expired)?;
+        write_null(writer)?; // filter, the platform byte is sent only for a filter object
+        write_null(writer)?; // transformer, same as the filter
+        write_u8(writer, INITIAL_QUERY_NONE)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i32>() // cache id
+            + size_of::<u8>() // flags
+            + size_of::<i32>() // page size
+            + size_of::<i64>() // time interval
+            + size_of::<bool>() // include expired
+            + size_of::<u8>() // null filter
+            + size_of::<u8>() // null transformer
+            + size_of::<u8>() // initial query type
+    }
+}
+
+pub(crate) struct QueryContinuousResp {
+    /// Id of the query, the event notifications refer to it
+    pub(crate) cursor_id: i64,
+}
+
+impl ReadableReq for QueryContinuousResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let cursor_id = read_i64(reader)?;
+        Ok(QueryContinuousResp { cursor_id })
+    }
+}
+
+/// Reads the body of the Query Continuous Event Notification 2007
+pub(crate) fn read_events<K: ReadableType, V: ReadableType>(
+    reader: &mut impl Read,
+) -> Result<Vec<CacheEntryEvent<K, V>>> {
+    let count = read_i32(reader)?;
+    let mut events = Vec::new();
+    for _ in 0..count {
+        let key = K::read(reader)?;
+        let old_value = V::read(reader)?;
+        let value = V::read(reader)?;
+        let event_type = CacheEntryEventType::try_from(read_u8(reader)?)?;
+        events.push(CacheEntryEvent {
+            key,
+            old_value,
+            value,
+            event_type,
+        });
+    }
+    Ok(events)
+}
@@ -0,0 +1,91 @@
+use std::io;
+use std::io::{Read, Write};
+use std::mem::size_of;
+
+use crate::error::Result;
+use crate::protocol::{read_i64, write_i32, write_i64, write_null, write_u8};
+use crate::{ReadableReq, WriteableReq};
+
+// flags of the streamer requests, see ClientDataStreamerFlags of the server
+pub(crate) const FLAG_ALLOW_OVERWRITE: u8 = 0x01;
+pub(crate) const FLAG_SKIP_STORE: u8 = 0x02;
+pub(crate) const FLAG_FLUSH: u8 = 0x08;
+pub(crate) const FLAG_CLOSE: u8 = 0x10;
+
+/// Serialized key-value pairs, a null value removes the key
+pub(crate) struct StreamerEntries {
+    pub(crate) count: i32,
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl StreamerEntries {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i32(writer, self.count)?;
+        writer.write_all(&self.bytes)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i32>() + self.bytes.len()
+    }
+}
+
+/// Data Streamer Start 8000. The receiver is not supported
+pub(crate) struct DataStreamerStartReq<'a> {
+    pub(crate) cache_id: i32,
+    pub(crate) flags: u8,
+    pub(crate) per_node_buffer_size: i32,
+    pub(crate) per_thread_buffer_size: i32,
+    pub(crate) entries: &'a StreamerEntries,
+}
+
+impl WriteableReq for DataStreamerStartReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i32(writer, self.cache_id)?;
+        write_u8(writer, self.flags)?;
+        write_i32(writer, self.per_node_buffer_size)?;
+        write_i32(writer, self.per_thread_buffer_size)?;
+        write_null(writer)?; // receiver, the platform byte is sent only for a receiver object
+        self.entries.write(writer)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i32>() // cache id
+            + size_of::<u8>() // flags
+            + size_of::<i32>() // per node buffer size
+            + size_of::<i32>() // per thread buffer size
+            + size_of::<u8>() // null receiver
+            + self.entries.size()
+    }
+}
+
+pub(crate) struct DataStreamerStartResp {
+    pub(crate) streamer_id: i64,
+}
+
+impl ReadableReq for DataStreamerStartResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let streamer_id = read_i64(reader)?;
+        Ok(DataStreamerStartResp { streamer_id })
+    }
+}
+
+/// Data Streamer Add Data 8001
+pub(crate) struct DataStreamerAddDataReq<'a> {
+    pub(crate) streamer_id: i64,
+    pub(crate) flags: u8,
+    pub(crate) entries: &'a StreamerEntries,
+}
+
+impl WriteableReq for DataStreamerAddDataReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i64(writer, self.streamer_id)?;
+        write_u8(writer, self.flags)?;
+        self.entries.write(writer)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i64>() // streamer id
+            + size_of::<u8>() // flags
+            + self.entries.size()
+    }
+}
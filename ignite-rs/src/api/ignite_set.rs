@@ -0,0 +1,221 @@
+use std::io;
+use std::io::{Read, Write};
+use std::mem::size_of;
+
+use crate::error::{Error, Result};
+use crate::ignite_set::CollectionConfiguration;
+use crate::protocol::{
+    read_bool, read_i32, read_i64, write_bool, write_i32, write_i64, write_null,
+    write_string_type_code, write_u8,
+};
+use crate::{ReadableReq, ReadableType, WritableType, WriteableReq};
+
+/// Resource Close 0. Releases a cursor or an iterator kept by the server
+pub(crate) struct ResourceCloseReq {
+    pub(crate) resource_id: i64,
+}
+
+impl WriteableReq for ResourceCloseReq {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i64(writer, self.resource_id)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i64>() // resource id
+    }
+}
+
+/// Set Get Or Create 9010. The set is only looked up if there is no configuration
+pub(crate) struct SetGetOrCreateReq<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) config: Option<&'a CollectionConfiguration>,
+}
+
+impl WriteableReq for SetGetOrCreateReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_string_type_code(writer, self.name)?;
+        write_bool(writer, self.config.is_some())?;
+        if let Some(config) = self.config {
+            write_u8(writer, config.atomicity_mode.clone() as u8)?;
+            write_u8(writer, config.cache_mode.clone() as u8)?;
+            write_i32(writer, config.backups)?;
+            match &config.group_name {
+                Some(group_name) => write_string_type_code(writer, group_name)?,
+                None => write_null(writer)?,
+            }
+            write_bool(writer, config.collocated)?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        let config_size = match self.config {
+            Some(config) => {
+                size_of::<u8>() // atomicity mode
+                    + size_of::<u8>() // cache mode
+                    + size_of::<i32>() // backups
+                    + config.group_name.as_ref().map_or(1, |name| name.len() + 5) // string or null
+                    + size_of::<bool>() // collocated
+            }
+            None => 0,
+        };
+        self.name.len() + 5 // string itself, type code, len
+            + size_of::<bool>() // create
+            + config_size
+    }
+}
+
+/// Identity of an existing set, none if the set does not exist
+pub(crate) struct SetGetOrCreateResp {
+    pub(crate) set: Option<(bool, i32)>,
+}
+
+impl ReadableReq for SetGetOrCreateResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        if !read_bool(reader)? {
+            return Ok(SetGetOrCreateResp { set: None });
+        }
+        let collocated = read_bool(reader)?;
+        let cache_id = read_i32(reader)?;
+        Ok(SetGetOrCreateResp {
+            set: Some((collocated, cache_id)),
+        })
+    }
+}
+
+/// Name, cache id and collocation of a set. The whole request of
+/// Set Close 9011, Exists 9012, Size 9020 and Clear 9021, and the start of the others
+pub(crate) struct SetIdentity<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) cache_id: i32,
+    pub(crate) collocated: bool,
+}
+
+impl WriteableReq for SetIdentity<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_string_type_code(writer, self.name)?;
+        write_i32(writer, self.cache_id)?;
+        write_bool(writer, self.collocated)
+    }
+
+    fn size(&self) -> usize {
+        self.name.len() + 5 // string itself, type code, len
+            + size_of::<i32>() // cache id
+            + size_of::<bool>() // collocated
+    }
+}
+
+/// Set Value Add 9013, Remove 9015 and Contains 9017
+pub(crate) struct SetValueReq<'a, T: WritableType> {
+    pub(crate) set: SetIdentity<'a>,
+    pub(crate) value: &'a T,
+}
+
+impl<T: WritableType> WriteableReq for SetValueReq<'_, T> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.set.write(writer)?;
+        write_bool(writer, true)?; // server keeps the values binary
+        self.value.write(writer)
+    }
+
+    fn size(&self) -> usize {
+        self.set.size()
+            + size_of::<bool>() // keep binary
+            + self.value.size()
+    }
+}
+
+/// Set Value Add All 9014, Remove All 9016, Contains All 9018 and Retain All 9019
+pub(crate) struct SetValuesReq<'a, T: WritableType> {
+    pub(crate) set: SetIdentity<'a>,
+    pub(crate) values: &'a [T],
+}
+
+impl<T: WritableType> WriteableReq for SetValuesReq<'_, T> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.set.write(writer)?;
+        write_bool(writer, true)?; // server keeps the values binary
+        write_i32(writer, self.values.len() as i32)?;
+        for value in self.values {
+            value.write(writer)?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.set.size()
+            + size_of::<bool>() // keep binary
+            + size_of::<i32>() // count
+            + self.values.iter().map(|value| value.size()).sum::<usize>()
+    }
+}
+
+/// Set Iterator Start 9022
+pub(crate) struct SetIteratorStartReq<'a> {
+    pub(crate) set: SetIdentity<'a>,
+    pub(crate) page_size: i32,
+}
+
+impl WriteableReq for SetIteratorStartReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.set.write(writer)?;
+        write_i32(writer, self.page_size)
+    }
+
+    fn size(&self) -> usize {
+        self.set.size() + size_of::<i32>() // page size
+    }
+}
+
+/// Set Iterator Get Page 9023
+pub(crate) struct SetIteratorGetPageReq {
+    pub(crate) resource_id: i64,
+    pub(crate) page_size: i32,
+}
+
+impl WriteableReq for SetIteratorGetPageReq {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i64(writer, self.resource_id)?;
+        write_i32(writer, self.page_size)
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i64>() // resource id
+            + size_of::<i32>() // page size
+    }
+}
+
+/// Page of the set values. The iterator is kept by the server only if there are more pages
+pub(crate) struct SetIteratorPageResp<T: ReadableType> {
+    pub(crate) values: Vec<T>,
+    pub(crate) has_next: bool,
+}
+
+impl<T: ReadableType> ReadableReq for SetIteratorPageResp<T> {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let count = read_i32(reader)?;
+        let mut values = Vec::new();
+        for _ in 0..count {
+            values.push(T::read(reader)?.ok_or_else(|| Error::from("NULL set value"))?);
+        }
+        let has_next = read_bool(reader)?;
+        Ok(SetIteratorPageResp { values, has_next })
+    }
+}
+
+/// First page of the set values followed by the iterator id if there are more pages
+pub(crate) struct SetIteratorStartResp<T: ReadableType> {
+    pub(crate) page: SetIteratorPageResp<T>,
+    pub(crate) resource_id: Option<i64>,
+}
+
+impl<T: ReadableType> ReadableReq for SetIteratorStartResp<T> {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let page = SetIteratorPageResp::read(reader)?;
+        let resource_id = match page.has_next {
+            true => Some(read_i64(reader)?),
+            false => None,
+        };
+        Ok(SetIteratorStartResp { page, resource_id })
+    }
+}
@@ -1,5 +1,6 @@
-use crate::cache::CachePeekMode;
+use crate::cache::{CachePeekMode, ExpiryPolicy};
 use crate::error::Result;
+use crate::protocol::cache_config::write_expiry_policy;
 use crate::protocol::{
     read_bool, read_i32, read_i64, write_bool, write_i32, write_i64, write_i8, write_null,
     write_string, write_u8, TypeCode,
@@ -10,78 +11,110 @@ use std::io;
 use std::io::{Read, Write};
 use std::mem::size_of;
 
-// https://apacheignite.readme.io/docs/binary-client-protocol-key-value-operations#op_cache_get
-const MAGIC_BYTE: u8 = 0;
+// https://ignite.apache.org/docs/latest/binary-client-protocol/key-value-queries#op_cache_get
+pub(crate) const FLAG_KEEP_BINARY: u8 = 0x01;
+pub(crate) const FLAG_WITH_EXPIRY_POLICY: u8 = 0x04;
 const CACHE_ID_MAGIC_BYTE_SIZE: usize = 5;
 
+/// Cache id and flags starting the key-value and scan requests
+#[derive(Clone, Copy)]
+pub(crate) struct CacheReqHeader {
+    pub(crate) cache_id: i32,
+    /// The server doesn't deserialize the objects
+    pub(crate) keep_binary: bool,
+    pub(crate) expiry_policy: Option<ExpiryPolicy>,
+}
+
+impl CacheReqHeader {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_i32(writer, self.cache_id)?;
+        let mut flags = 0;
+        if self.keep_binary {
+            flags |= FLAG_KEEP_BINARY;
+        }
+        if self.expiry_policy.is_some() {
+            flags |= FLAG_WITH_EXPIRY_POLICY;
+        }
+        write_u8(writer, flags)?;
+        match self.expiry_policy {
+            Some(ref policy) => write_expiry_policy(writer, policy),
+            None => Ok(()),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self.expiry_policy {
+            Some(_) => CACHE_ID_MAGIC_BYTE_SIZE + 3 * size_of::<i64>(), // create, update, access
+            None => CACHE_ID_MAGIC_BYTE_SIZE,
+        }
+    }
+}
+
 pub(crate) enum CacheReq<'a, K: WritableType, V: WritableType> {
-    Get(i32, &'a K),
-    GetAll(i32, &'a [K]),
-    Put(i32, &'a K, &'a V),
-    PutAll(i32, &'a [(K, V)]),
-    ContainsKey(i32, &'a K),
-    ContainsKeys(i32, &'a [K]),
-    GetAndPut(i32, &'a K, &'a V),
-    GetAndReplace(i32, &'a K, &'a V),
-    GetAndRemove(i32, &'a K),
-    PutIfAbsent(i32, &'a K, &'a V),
-    GetAndPutIfAbsent(i32, &'a K, &'a V),
-    Replace(i32, &'a K, &'a V),
-    ReplaceIfEquals(i32, &'a K, &'a V, &'a V),
-    Clear(i32),
-    ClearKey(i32, &'a K),
-    ClearKeys(i32, &'a [K]),
-    RemoveKey(i32, &'a K),
-    RemoveIfEquals(i32, &'a K, &'a V),
-    GetSize(i32, Vec<CachePeekMode>),
-    RemoveKeys(i32, &'a [K]),
-    RemoveAll(i32),
-    QueryScan(i32, i32),                    // cache ID, page size,
-    QueryScanSql(i32, i32, String, String), // cache ID, page size, table/type, sql
-    QueryScanSqlFields(i32, i32, String),   // cache ID, page size, sql
+    Get(CacheReqHeader, &'a K),
+    GetAll(CacheReqHeader, &'a [K]),
+    Put(CacheReqHeader, &'a K, &'a V),
+    PutAll(CacheReqHeader, &'a [(K, V)]),
+    ContainsKey(CacheReqHeader, &'a K),
+    ContainsKeys(CacheReqHeader, &'a [K]),
+    GetAndPut(CacheReqHeader, &'a K, &'a V),
+    GetAndReplace(CacheReqHeader, &'a K, &'a V),
+    GetAndRemove(CacheReqHeader, &'a K),
+    PutIfAbsent(CacheReqHeader, &'a K, &'a V),
+    GetAndPutIfAbsent(CacheReqHeader, &'a K, &'a V),
+    Replace(CacheReqHeader, &'a K, &'a V),
+    ReplaceIfEquals(CacheReqHeader, &'a K, &'a V, &'a V),
+    Clear(CacheReqHeader),
+    ClearKey(CacheReqHeader, &'a K),
+    ClearKeys(CacheReqHeader, &'a [K]),
+    RemoveKey(CacheReqHeader, &'a K),
+    RemoveIfEquals(CacheReqHeader, &'a K, &'a V),
+    GetSize(CacheReqHeader, Vec<CachePeekMode>),
+    RemoveKeys(CacheReqHeader, &'a [K]),
+    RemoveAll(CacheReqHeader),
+    QueryScan(CacheReqHeader, i32), // page size
+    QueryScanSql(i32, i32, String, String, &'a [&'a dyn WritableType]), // cache ID, page size, table/type, sql, args
+    #[allow(dead_code)]
+    QueryScanSqlFields(i32, i32, String),        // cache ID, page size, sql
 }
 
 impl<'a, K: WritableType, V: WritableType> WriteableReq for CacheReq<'a, K, V> {
     fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
         match self {
-            CacheReq::Get(id, key)
-            | CacheReq::ContainsKey(id, key)
-            | CacheReq::GetAndRemove(id, key)
-            | CacheReq::ClearKey(id, key)
-            | CacheReq::RemoveKey(id, key) => {
-                write_i32(writer, *id)?;
-                write_u8(writer, MAGIC_BYTE)?;
+            CacheReq::Get(header, key)
+            | CacheReq::ContainsKey(header, key)
+            | CacheReq::GetAndRemove(header, key)
+            | CacheReq::ClearKey(header, key)
+            | CacheReq::RemoveKey(header, key) => {
+                header.write(writer)?;
                 key.write(writer)?;
                 Ok(())
             }
-            CacheReq::GetAll(id, keys)
-            | CacheReq::ContainsKeys(id, keys)
-            | CacheReq::ClearKeys(id, keys)
-            | CacheReq::RemoveKeys(id, keys) => {
-                write_i32(writer, *id)?;
-                write_u8(writer, MAGIC_BYTE)?;
+            CacheReq::GetAll(header, keys)
+            | CacheReq::ContainsKeys(header, keys)
+            | CacheReq::ClearKeys(header, keys)
+            | CacheReq::RemoveKeys(header, keys) => {
+                header.write(writer)?;
                 write_i32(writer, keys.len() as i32)?;
                 for k in *keys {
                     k.write(writer)?;
                 }
                 Ok(())
             }
-            CacheReq::Put(id, key, value)
-            | CacheReq::GetAndPut(id, key, value)
-            | CacheReq::GetAndReplace(id, key, value)
-            | CacheReq::PutIfAbsent(id, key, value)
-            | CacheReq::GetAndPutIfAbsent(id, key, value)
-            | CacheReq::Replace(id, key, value)
-            | CacheReq::RemoveIfEquals(id, key, value) => {
-                write_i32(writer, *id)?;
-                write_u8(writer, MAGIC_BYTE)?;
+            CacheReq::Put(header, key, value)
+            | CacheReq::GetAndPut(header, key, value)
+            | CacheReq::GetAndReplace(header, key, value)
+            | CacheReq::PutIfAbsent(header, key, value)
+            | CacheReq::GetAndPutIfAbsent(header, key, value)
+            | CacheReq::Replace(header, key, value)
+            | CacheReq::RemoveIfEquals(header, key, value) => {
+                header.write(writer)?;
                 key.write(writer)?;
                 value.write(writer)?;
                 Ok(())
             }
-            CacheReq::PutAll(id, pairs) => {
-                write_i32(writer, *id)?;
-                write_u8(writer, MAGIC_BYTE)?;
+            CacheReq::PutAll(header, pairs) => {
+                header.write(writer)?;
                 write_i32(writer, pairs.len() as i32)?;
                 for pair in *pairs {
                     pair.0.write(writer)?;
@@ -89,22 +122,19 @@ impl<'a, K: WritableType, V: WritableType> WriteableReq for CacheReq<'a, K, V> {
                 }
                 Ok(())
             }
-            CacheReq::ReplaceIfEquals(id, key, old, new) => {
-                write_i32(writer, *id)?;
-                write_u8(writer, MAGIC_BYTE)?;
+            CacheReq::ReplaceIfEquals(header, key, old, new) => {
+                header.write(writer)?;
                 key.write(writer)?;
                 old.write(writer)?;
                 new.write(writer)?;
                 Ok(())
             }
-            CacheReq::Clear(id) | CacheReq::RemoveAll(id) => {
-                write_i32(writer, *id)?;
-                write_u8(writer, MAGIC_BYTE)?;
+            CacheReq::Clear(header) | CacheReq::RemoveAll(header) => {
+                header.write(writer)?;
                 Ok(())
             }
-            CacheReq::GetSize(id, modes) => {
-                write_i32(writer, *id)?;
-                write_u8(writer, MAGIC_BYTE)?;
+            CacheReq::GetSize(header, modes) => {
+                header.write(writer)?;
                 write_i32(writer, modes.len() as i32)?;
                 for mode in modes {
                     write_u8(writer, mode.clone() as u8)?;
@@ -112,9 +142,14 @@ impl<'a, K: WritableType, V: WritableType> WriteableReq for CacheReq<'a, K, V> {
                 Ok(())
             }
             // https://ignite.apache.org/docs/latest/binary-client-protocol/sql-and-scan-queries#op_query_scan
-            CacheReq::QueryScan(id, pg_sz) => {
-                write_i32(writer, *id)?;
-                write_u8(writer, 1u8)?; // 1 to keep the value in binary form
+            CacheReq::QueryScan(header, pg_sz) => {
+                // the values are always kept binary, the server can't deserialize
+                // the types registered by thin clients only
+                let header = CacheReqHeader {
+                    keep_binary: true,
+                    ..*header
+                };
+                header.write(writer)?;
                 write_null(writer)?; // Not possible to pass filter object unless Java or .NET
                 write_i32(writer, *pg_sz)?;
                 write_i32(writer, -1)?; // negative to query entire cache
@@ -122,19 +157,17 @@ impl<'a, K: WritableType, V: WritableType> WriteableReq for CacheReq<'a, K, V> {
                 Ok(())
             }
             // https://ignite.apache.org/docs/latest/binary-client-protocol/sql-and-scan-queries#op_query_sql
-            CacheReq::QueryScanSql(id, pg_sz, table, sql) => {
+            CacheReq::QueryScanSql(id, pg_sz, table, sql, args) => {
                 write_i32(writer, *id)?;
                 write_u8(writer, 0)?; // Use 0. This field is deprecated and will be removed in the future.
                 write_u8(writer, TypeCode::String as u8)?;
                 write_string(writer, table.as_str())?;
                 write_u8(writer, TypeCode::String as u8)?;
                 write_string(writer, sql.as_str())?;
-                write_i32(writer, 0)?; // Argument count.
-                                       /*
-                                       TODO: Data Object
-                                       Query argument.
-                                       Repeat for as many times as the query argument count that is passed in the previous parameter.
-                                        */
+                write_i32(writer, args.len() as i32)?; // Argument count.
+                for arg in *args {
+                    arg.write(writer)?; // Query argument.
+                }
                 write_bool(writer, false)?; // Distributed joins
                 write_bool(writer, false)?; // Local query.
                 write_bool(writer, false)?; // Replicated only - Whether query contains only replicated tables or not.
@@ -173,33 +206,33 @@ impl<'a, K: WritableType, V: WritableType> WriteableReq for CacheReq<'a, K, V> {
 
     fn size(&self) -> usize {
         match self {
-            CacheReq::Get(_, key)
-            | CacheReq::ContainsKey(_, key)
-            | CacheReq::GetAndRemove(_, key)
-            | CacheReq::ClearKey(_, key)
-            | CacheReq::RemoveKey(_, key) => CACHE_ID_MAGIC_BYTE_SIZE + key.size(),
-            CacheReq::GetAll(_, keys)
-            | CacheReq::ContainsKeys(_, keys)
-            | CacheReq::ClearKeys(_, keys)
-            | CacheReq::RemoveKeys(_, keys) => {
-                let mut size = CACHE_ID_MAGIC_BYTE_SIZE;
+            CacheReq::Get(header, key)
+            | CacheReq::ContainsKey(header, key)
+            | CacheReq::GetAndRemove(header, key)
+            | CacheReq::ClearKey(header, key)
+            | CacheReq::RemoveKey(header, key) => header.size() + key.size(),
+            CacheReq::GetAll(header, keys)
+            | CacheReq::ContainsKeys(header, keys)
+            | CacheReq::ClearKeys(header, keys)
+            | CacheReq::RemoveKeys(header, keys) => {
+                let mut size = header.size();
                 size += 4; // len
                 for k in *keys {
                     size += k.size();
                 }
                 size
             }
-            CacheReq::Put(_, key, value)
-            | CacheReq::GetAndPut(_, key, value)
-            | CacheReq::GetAndReplace(_, key, value)
-            | CacheReq::PutIfAbsent(_, key, value)
-            | CacheReq::GetAndPutIfAbsent(_, key, value)
-            | CacheReq::Replace(_, key, value)
-            | CacheReq::RemoveIfEquals(_, key, value) => {
-                CACHE_ID_MAGIC_BYTE_SIZE + key.size() + value.size()
+            CacheReq::Put(header, key, value)
+            | CacheReq::GetAndPut(header, key, value)
+            | CacheReq::GetAndReplace(header, key, value)
+            | CacheReq::PutIfAbsent(header, key, value)
+            | CacheReq::GetAndPutIfAbsent(header, key, value)
+            | CacheReq::Replace(header, key, value)
+            | CacheReq::RemoveIfEquals(header, key, value) => {
+                header.size() + key.size() + value.size()
             }
-            CacheReq::PutAll(_, pairs) => {
-                let mut size = CACHE_ID_MAGIC_BYTE_SIZE;
+            CacheReq::PutAll(header, pairs) => {
+                let mut size = header.size();
                 size += 4; //len
                 for pair in *pairs {
                     size += pair.0.size();
@@ -207,30 +240,31 @@ impl<'a, K: WritableType, V: WritableType> WriteableReq for CacheReq<'a, K, V> {
                 }
                 size
             }
-            CacheReq::ReplaceIfEquals(_, key, old, new) => {
-                CACHE_ID_MAGIC_BYTE_SIZE + key.size() + old.size() + new.size()
+            CacheReq::ReplaceIfEquals(header, key, old, new) => {
+                header.size() + key.size() + old.size() + new.size()
             }
-            CacheReq::Clear(_) | CacheReq::RemoveAll(_) => CACHE_ID_MAGIC_BYTE_SIZE,
-            CacheReq::GetSize(_, modes) => {
-                let mut size = CACHE_ID_MAGIC_BYTE_SIZE;
+            CacheReq::Clear(header) | CacheReq::RemoveAll(header) => header.size(),
+            CacheReq::GetSize(header, modes) => {
+                let mut size = header.size();
                 size += 4; //len
                 for _ in modes {
                     size += 1;
                 }
                 size
             }
-            CacheReq::QueryScan(_, _) => {
-                CACHE_ID_MAGIC_BYTE_SIZE
+            CacheReq::QueryScan(header, _) => {
+                header.size()
                 + size_of::<u8>() // Filter object: Null
                 + size_of::<i32>() // Cursor page size
                 + size_of::<i32>() // Partition count
                 + size_of::<u8>() // local only flag
             }
-            CacheReq::QueryScanSql(_, _, table, sql) => {
+            CacheReq::QueryScanSql(_, _, table, sql, args) => {
                 CACHE_ID_MAGIC_BYTE_SIZE
                     + size_of::<i32>() + table.len() + size_of::<u8>()
                     + size_of::<i32>() + sql.len() + size_of::<u8>()
                     + size_of::<i32>() // Query argument count.
+                    + args.iter().map(|arg| arg.size()).sum::<usize>()
                     + size_of::<u8>() // Distributed joins flag
                     + size_of::<u8>() // Local query flag
                     + size_of::<u8>() // Replicated only flag
@@ -326,3 +360,32 @@ impl ReadableReq for CacheBoolResp {
         Ok(CacheBoolResp { flag })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_binary_flag() {
+        let header = CacheReqHeader {
+            cache_id: 1,
+            keep_binary: false,
+            expiry_policy: None,
+        };
+        let flags = |req: CacheReq<i32, i32>| {
+            let mut bytes = Vec::new();
+            req.write(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), req.size());
+            bytes[4]
+        };
+        assert_eq!(flags(CacheReq::Get(header, &1)), 0);
+        assert_eq!(flags(CacheReq::QueryScan(header, 10)), FLAG_KEEP_BINARY);
+
+        let header = CacheReqHeader {
+            keep_binary: true,
+            ..header
+        };
+        assert_eq!(flags(CacheReq::Get(header, &1)), FLAG_KEEP_BINARY);
+        assert_eq!(flags(CacheReq::QueryScan(header, 10)), FLAG_KEEP_BINARY);
+    }
+}
@@ -1,7 +1,20 @@
+use crate::error::Error;
+use std::convert::TryFrom;
+
+pub(crate) mod atomic_long;
+pub(crate) mod binary_type;
 pub(crate) mod cache_config;
+pub(crate) mod cluster;
+pub(crate) mod compute;
+pub(crate) mod continuous_query;
+pub(crate) mod data_streamer;
+pub(crate) mod ignite_set;
 pub(crate) mod key_value;
+pub(crate) mod service;
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) enum OpCode {
+    ResourceClose = 0,
     Handshake = 1,
     //cache configuration
     CacheGetNames = 1050,
@@ -36,13 +49,141 @@ pub(crate) enum OpCode {
     // sql & scan queries - https://ignite.apache.org/docs/latest/binary-client-protocol/sql-and-scan-queries
     QueryScan = 2000,
     QuerySql = 2002,
+    #[allow(dead_code)]
     QuerySqlFields = 2004,
+    QueryContinuous = 2006,
+    QueryContinuousEventNotification = 2007,
+    // binary types - https://ignite.apache.org/docs/latest/binary-client-protocol/binary-type-metadata
+    GetBinaryType = 3002,
     TxStart = 4000,
     TxEnd = 4001,
+    // cluster - https://ignite.apache.org/docs/latest/binary-client-protocol/cluster-api
+    ClusterGetState = 5000,
+    ClusterChangeState = 5001,
+    ClusterChangeWalState = 5002,
+    ClusterGetWalState = 5003,
+    ClusterGroupGetNodeIds = 5100,
+    ClusterGroupGetNodeInfo = 5101,
+    ClusterGroupGetNodeEndpoints = 5102,
+    // compute - https://ignite.apache.org/docs/latest/binary-client-protocol/compute-api
+    ComputeTaskExecute = 6000,
+    ComputeTaskFinished = 6001,
+    // services - https://ignite.apache.org/docs/latest/binary-client-protocol/service-api
+    ServiceInvoke = 7000,
+    ServiceGetDescriptors = 7001,
+    ServiceGetDescriptor = 7002,
+    // data streamer, not in the protocol docs yet, see ClientMessageParser of the server
+    DataStreamerStart = 8000,
+    DataStreamerAddData = 8001,
+    // data structures, see ClientMessageParser of the server
+    AtomicLongCreate = 9000,
+    AtomicLongRemove = 9001,
+    AtomicLongExists = 9002,
+    AtomicLongValueGet = 9003,
+    AtomicLongValueAddAndGet = 9004,
+    AtomicLongValueGetAndSet = 9005,
+    AtomicLongValueCompareAndSet = 9006,
+    SetGetOrCreate = 9010,
+    SetClose = 9011,
+    SetExists = 9012,
+    SetValueAdd = 9013,
+    SetValueAddAll = 9014,
+    SetValueRemove = 9015,
+    SetValueRemoveAll = 9016,
+    SetValueContains = 9017,
+    SetValueContainsAll = 9018,
+    SetValueRetainAll = 9019,
+    SetSize = 9020,
+    SetClear = 9021,
+    SetIteratorStart = 9022,
+    SetIteratorGetPage = 9023,
 }
 
-impl Into<i16> for OpCode {
-    fn into(self) -> i16 {
-        self as i16
+impl From<OpCode> for i16 {
+    fn from(code: OpCode) -> i16 {
+        code as i16
+    }
+}
+
+impl TryFrom<i16> for OpCode {
+    type Error = Error;
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(OpCode::ResourceClose),
+            1 => Ok(OpCode::Handshake),
+            1050 => Ok(OpCode::CacheGetNames),
+            1051 => Ok(OpCode::CacheCreateWithName),
+            1052 => Ok(OpCode::CacheGetOrCreateWithName),
+            1053 => Ok(OpCode::CacheCreateWithConfiguration),
+            1054 => Ok(OpCode::CacheGetOrCreateWithConfiguration),
+            1055 => Ok(OpCode::CacheGetConfiguration),
+            1056 => Ok(OpCode::CacheDestroy),
+            1000 => Ok(OpCode::CacheGet),
+            1001 => Ok(OpCode::CachePut),
+            1002 => Ok(OpCode::CachePutIfAbsent),
+            1003 => Ok(OpCode::CacheGetAll),
+            1004 => Ok(OpCode::CachePutAll),
+            1005 => Ok(OpCode::CacheGetAndPut),
+            1006 => Ok(OpCode::CacheGetAndReplace),
+            1007 => Ok(OpCode::CacheGetAndRemove),
+            1008 => Ok(OpCode::CacheGetAndPutIfAbsent),
+            1009 => Ok(OpCode::CacheReplace),
+            1010 => Ok(OpCode::CacheReplaceIfEquals),
+            1011 => Ok(OpCode::CacheContainsKey),
+            1012 => Ok(OpCode::CacheContainsKeys),
+            1013 => Ok(OpCode::CacheClear),
+            1014 => Ok(OpCode::CacheClearKey),
+            1015 => Ok(OpCode::CacheClearKeys),
+            1016 => Ok(OpCode::CacheRemoveKey),
+            1017 => Ok(OpCode::CacheRemoveIfEquals),
+            1018 => Ok(OpCode::CacheRemoveKeys),
+            1019 => Ok(OpCode::CacheRemoveAll),
+            1020 => Ok(OpCode::CacheGetSize),
+            2000 => Ok(OpCode::QueryScan),
+            2002 => Ok(OpCode::QuerySql),
+            2004 => Ok(OpCode::QuerySqlFields),
+            2006 => Ok(OpCode::QueryContinuous),
+            2007 => Ok(OpCode::QueryContinuousEventNotification),
+            3002 => Ok(OpCode::GetBinaryType),
+            4000 => Ok(OpCode::TxStart),
+            4001 => Ok(OpCode::TxEnd),
+            5000 => Ok(OpCode::ClusterGetState),
+            5001 => Ok(OpCode::ClusterChangeState),
+            5002 => Ok(OpCode::ClusterChangeWalState),
+            5003 => Ok(OpCode::ClusterGetWalState),
+            5100 => Ok(OpCode::ClusterGroupGetNodeIds),
+            5101 => Ok(OpCode::ClusterGroupGetNodeInfo),
+            5102 => Ok(OpCode::ClusterGroupGetNodeEndpoints),
+            6000 => Ok(OpCode::ComputeTaskExecute),
+            6001 => Ok(OpCode::ComputeTaskFinished),
+            7000 => Ok(OpCode::ServiceInvoke),
+            7001 => Ok(OpCode::ServiceGetDescriptors),
+            7002 => Ok(OpCode::ServiceGetDescriptor),
+            8000 => Ok(OpCode::DataStreamerStart),
+            8001 => Ok(OpCode::DataStreamerAddData),
+            9000 => Ok(OpCode::AtomicLongCreate),
+            9001 => Ok(OpCode::AtomicLongRemove),
+            9002 => Ok(OpCode::AtomicLongExists),
+            9003 => Ok(OpCode::AtomicLongValueGet),
+            9004 => Ok(OpCode::AtomicLongValueAddAndGet),
+            9005 => Ok(OpCode::AtomicLongValueGetAndSet),
+            9006 => Ok(OpCode::AtomicLongValueCompareAndSet),
+            9010 => Ok(OpCode::SetGetOrCreate),
+            9011 => Ok(OpCode::SetClose),
+            9012 => Ok(OpCode::SetExists),
+            9013 => Ok(OpCode::SetValueAdd),
+            9014 => Ok(OpCode::SetValueAddAll),
+            9015 => Ok(OpCode::SetValueRemove),
+            9016 => Ok(OpCode::SetValueRemoveAll),
+            9017 => Ok(OpCode::SetValueContains),
+            9018 => Ok(OpCode::SetValueContainsAll),
+            9019 => Ok(OpCode::SetValueRetainAll),
+            9020 => Ok(OpCode::SetSize),
+            9021 => Ok(OpCode::SetClear),
+            9022 => Ok(OpCode::SetIteratorStart),
+            9023 => Ok(OpCode::SetIteratorGetPage),
+            _ => Err(Error::from(format!("Unknown op code {}", value).as_str())),
+        }
     }
 }
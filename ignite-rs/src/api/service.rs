@@ -0,0 +1,138 @@
+use std::io;
+use std::io::{Read, Write};
+use std::mem::size_of;
+
+use crate::error::{Error, Result};
+use crate::protocol::{
+    prealloc_len, read_i32, read_u8, write_i32, write_i64, write_string_type_code, write_u8,
+};
+#[cfg(any(test, feature = "fake-server"))]
+use crate::protocol::{write_null, write_uuid, TypeCode};
+use crate::service::{ServiceDescriptor, ServicePlatform};
+use crate::{ReadableReq, ReadableType, Uuid, WritableType, WriteableReq};
+use std::convert::TryFrom;
+
+/// Service Invoke 7000
+pub(crate) struct ServiceInvokeReq<'a> {
+    pub(crate) name: &'a str,
+    /// Milliseconds, 0 for no timeout
+    pub(crate) timeout: i64,
+    pub(crate) method: &'a str,
+    pub(crate) args: &'a [&'a dyn WritableType],
+}
+
+impl WriteableReq for ServiceInvokeReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_string_type_code(writer, self.name)?;
+        write_u8(writer, 0)?; // flags: no keep binary, no parameter types
+        write_i64(writer, self.timeout)?;
+        write_i32(writer, 0)?; // node count, 0 for any node the service is deployed on
+        write_string_type_code(writer, self.method)?;
+        write_i32(writer, self.args.len() as i32)?;
+        for arg in self.args {
+            arg.write(writer)?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        let args_size: usize = self.args.iter().map(|arg| arg.size()).sum();
+        self.name.len() + 5 // string itself, type code, len
+            + size_of::<u8>() // flags
+            + size_of::<i64>() // timeout
+            + size_of::<i32>() // node count
+            + self.method.len() + 5 // string itself, type code, len
+            + size_of::<i32>() // arg count
+            + args_size
+    }
+}
+
+/// Service Get Descriptors 7001
+pub(crate) struct ServiceGetDescriptorsReq {}
+
+impl WriteableReq for ServiceGetDescriptorsReq {
+    fn write(&self, _: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        0
+    }
+}
+
+pub(crate) struct ServiceGetDescriptorsResp {
+    pub(crate) descriptors: Vec<ServiceDescriptor>,
+}
+
+impl ReadableReq for ServiceGetDescriptorsResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let count = read_i32(reader)?;
+        let mut descriptors = Vec::with_capacity(prealloc_len(count));
+        for _ in 0..count {
+            descriptors.push(read_descriptor(reader)?);
+        }
+        Ok(ServiceGetDescriptorsResp { descriptors })
+    }
+}
+
+/// Service Get Descriptor 7002
+pub(crate) struct ServiceGetDescriptorReq<'a> {
+    pub(crate) name: &'a str,
+}
+
+impl WriteableReq for ServiceGetDescriptorReq<'_> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_string_type_code(writer, self.name)
+    }
+
+    fn size(&self) -> usize {
+        self.name.len() + 5 // string itself, type code, len
+    }
+}
+
+pub(crate) struct ServiceGetDescriptorResp {
+    pub(crate) descriptor: ServiceDescriptor,
+}
+
+impl ReadableReq for ServiceGetDescriptorResp {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let descriptor = read_descriptor(reader)?;
+        Ok(ServiceGetDescriptorResp { descriptor })
+    }
+}
+
+fn read_descriptor(reader: &mut impl Read) -> Result<ServiceDescriptor> {
+    Ok(ServiceDescriptor {
+        name: String::read(reader)?.ok_or_else(|| Error::from("Service name is NULL"))?,
+        service_class: String::read(reader)?.unwrap_or_default(),
+        total_count: read_i32(reader)?,
+        max_per_node_count: read_i32(reader)?,
+        cache_name: String::read(reader)?,
+        origin_node_id: Uuid::read(reader)?,
+        platform: ServicePlatform::try_from(read_u8(reader)?)?,
+    })
+}
+
+/// Writes the descriptor the way `read_descriptor` reads it
+#[cfg(any(test, feature = "fake-server"))]
+pub(crate) fn write_descriptor(
+    writer: &mut dyn Write,
+    descriptor: &ServiceDescriptor,
+) -> io::Result<()> {
+    write_string_type_code(writer, &descriptor.name)?;
+    write_string_type_code(writer, &descriptor.service_class)?;
+    write_i32(writer, descriptor.total_count)?;
+    write_i32(writer, descriptor.max_per_node_count)?;
+    match descriptor.cache_name {
+        Some(ref name) => write_string_type_code(writer, name)?,
+        None => write_null(writer)?,
+    }
+    match descriptor.origin_node_id {
+        Some(id) => {
+            write_u8(writer, TypeCode::Uuid as u8)?;
+            write_uuid(writer, id)?;
+        }
+        None => write_null(writer)?,
+    }
+    write_u8(writer, descriptor.platform as u8)
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use crate::api::atomic_long::{AtomicLongReq, AtomicLongValueResp};
+use crate::api::key_value::CacheBoolResp;
+use crate::api::OpCode;
+use crate::cache::CacheMode;
+use crate::connection::Connection;
+use crate::error::Result;
+
+/// Settings of the cache keeping the atomic data structures.
+/// Applied when the structure is created, the structures of one group share the cache
+#[derive(Clone, Debug)]
+pub struct AtomicConfiguration {
+    /// Number of the sequence values reserved by a node at once
+    pub atomic_sequence_reserve_size: i32,
+    pub cache_mode: CacheMode,
+    /// Number of backups in the partitioned mode
+    pub backups: i32,
+    /// Cache group of the structures, none for the default group
+    pub group_name: Option<String>,
+}
+
+impl Default for AtomicConfiguration {
+    fn default() -> Self {
+        // the defaults of Java's AtomicConfiguration
+        AtomicConfiguration {
+            atomic_sequence_reserve_size: 1000,
+            cache_mode: CacheMode::Partitioned,
+            backups: 1,
+            group_name: None,
+        }
+    }
+}
+
+/// Cluster-wide long value, created by `Client::atomic_long`.
+/// All the operations are atomic and fail once the value is removed
+#[derive(Clone)]
+pub struct AtomicLong {
+    conn: Arc<Connection>,
+    name: String,
+    group_name: Option<String>,
+}
+
+impl AtomicLong {
+    pub(crate) fn new(conn: Arc<Connection>, name: &str, group_name: Option<String>) -> AtomicLong {
+        AtomicLong {
+            conn,
+            name: name.to_string(),
+            group_name,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the current value
+    pub fn get(&self) -> Result<i64> {
+        self.value_op(OpCode::AtomicLongValueGet, &[])
+    }
+
+    /// Adds the delta and returns the new value
+    pub fn add_and_get(&self, delta: i64) -> Result<i64> {
+        self.value_op(OpCode::AtomicLongValueAddAndGet, &[delta])
+    }
+
+    /// Increments the value and returns the new one
+    pub fn increment_and_get(&self) -> Result<i64> {
+        self.add_and_get(1)
+    }
+
+    /// Decrements the value and returns the new one
+    pub fn decrement_and_get(&self) -> Result<i64> {
+        self.add_and_get(-1)
+    }
+
+    /// Sets the value and returns the previous one
+    pub fn get_and_set(&self, value: i64) -> Result<i64> {
+        self.value_op(OpCode::AtomicLongValueGetAndSet, &[value])
+    }
+
+    /// Sets the value if the current one equals the expected value.
+    /// Returns true if the value was set
+    pub fn compare_and_set(&self, expected: i64, value: i64) -> Result<bool> {
+        self.bool_op(OpCode::AtomicLongValueCompareAndSet, &[expected, value])
+    }
+
+    /// Returns false if the value was removed
+    pub fn exists(&self) -> Result<bool> {
+        self.bool_op(OpCode::AtomicLongExists, &[])
+    }
+
+    /// Removes the value from the cluster
+    pub fn remove(&self) -> Result<()> {
+        self.conn.send(OpCode::AtomicLongRemove, self.req(&[]))
+    }
+
+    fn value_op(&self, op_code: OpCode, args: &[i64]) -> Result<i64> {
+        let resp: AtomicLongValueResp = self.conn.send_and_read(op_code, self.req(args))?;
+        Ok(resp.value)
+    }
+
+    fn bool_op(&self, op_code: OpCode, args: &[i64]) -> Result<bool> {
+        let resp: CacheBoolResp = self.conn.send_and_read(op_code, self.req(args))?;
+        Ok(resp.flag)
+    }
+
+    fn req<'a>(&'a self, args: &'a [i64]) -> AtomicLongReq<'a> {
+        AtomicLongReq {
+            name: self.name.as_str(),
+            group_name: self.group_name.as_deref(),
+            args,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, StatusCode};
+    use crate::fake_server::FakeServer;
+
+    #[test]
+    fn test_atomic_long() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let ids = client.atomic_long("ids", 10, None).unwrap();
+        assert_eq!(ids.name(), "ids");
+        assert_eq!(ids.get().unwrap(), 10);
+        assert_eq!(ids.increment_and_get().unwrap(), 11);
+        assert_eq!(ids.add_and_get(5).unwrap(), 16);
+        assert_eq!(ids.decrement_and_get().unwrap(), 15);
+        assert_eq!(ids.get_and_set(100).unwrap(), 15);
+        assert!(!ids.compare_and_set(15, 0).unwrap());
+        assert!(ids.compare_and_set(100, 0).unwrap());
+        assert_eq!(ids.get().unwrap(), 0);
+
+        // existing value is returned as it is
+        let same = client.atomic_long("ids", 42, None).unwrap();
+        assert_eq!(same.get().unwrap(), 0);
+
+        assert!(ids.exists().unwrap());
+        same.remove().unwrap();
+        assert!(!ids.exists().unwrap());
+        match ids.get() {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::ResourceDoesNotExist),
+            _ => panic!("removed value is read"),
+        }
+    }
+
+    #[test]
+    fn test_group() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let config = AtomicConfiguration {
+            cache_mode: CacheMode::Replicated,
+            group_name: Some("counters".to_string()),
+            ..AtomicConfiguration::default()
+        };
+        let grouped = client.atomic_long("seq", 1, Some(&config)).unwrap();
+        let default = client.atomic_long("seq", 2, None).unwrap();
+        assert_eq!(grouped.get().unwrap(), 1);
+        assert_eq!(default.get().unwrap(), 2);
+        grouped.remove().unwrap();
+        assert!(default.exists().unwrap());
+    }
+}
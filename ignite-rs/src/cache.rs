@@ -2,7 +2,8 @@ use std::convert::TryFrom;
 use std::io::Read;
 
 use crate::api::key_value::{
-    CacheBoolResp, CacheDataObjectResp, CachePairsResp, CacheReq, CacheSizeResp, QueryScanResp,
+    CacheBoolResp, CacheDataObjectResp, CachePairsResp, CacheReq, CacheReqHeader, CacheSizeResp,
+    QueryScanResp,
 };
 use crate::cache::AtomicityMode::{Atomic, Transactional};
 use crate::cache::CacheMode::{Local, Partitioned, Replicated};
@@ -14,12 +15,19 @@ use crate::cache::RebalanceMode::Async;
 use crate::cache::WriteSynchronizationMode::{FullAsync, FullSync, PrimarySync};
 use crate::error::{Error, Result};
 
+use crate::api::continuous_query::QueryContinuousReq;
 use crate::api::OpCode;
 use crate::connection::Connection;
+use crate::continuous_query::ContinuousQuery;
+use crate::data_streamer::{DataStreamer, DataStreamerConfiguration};
+use crate::protocol::binary_object::BinaryObject;
 use crate::protocol::{read_bool, read_i32, read_i64};
+use crate::utils::duration_to_millis;
 use crate::{ReadableType, WritableType};
+use std::cell::Cell;
 use std::marker::PhantomData;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub enum AtomicityMode {
@@ -131,9 +139,9 @@ pub enum CachePeekMode {
     Backup = 3,
 }
 
-impl Into<u8> for CachePeekMode {
-    fn into(self) -> u8 {
-        self as u8
+impl From<CachePeekMode> for u8 {
+    fn from(mode: CachePeekMode) -> u8 {
+        mode as u8
     }
 }
 
@@ -157,6 +165,50 @@ impl TryFrom<u8> for IndexType {
     }
 }
 
+/// Time to live an entry gets from a cache operation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExpiryDuration {
+    /// The expiration time of the entry is not changed.
+    /// For the created entries the expiry policy of the cache is used
+    Unchanged,
+    /// The entry never expires
+    Eternal,
+    /// The entry expires after the duration, rounded down to milliseconds
+    /// and capped at `i64::MAX` of them. Zero expires the entry right away
+    After(Duration),
+}
+
+impl From<ExpiryDuration> for i64 {
+    fn from(duration: ExpiryDuration) -> i64 {
+        match duration {
+            ExpiryDuration::Unchanged => -2,
+            ExpiryDuration::Eternal => -1,
+            ExpiryDuration::After(duration) => duration_to_millis(duration),
+        }
+    }
+}
+
+impl TryFrom<i64> for ExpiryDuration {
+    type Error = Error;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            -2 => Ok(ExpiryDuration::Unchanged),
+            -1 => Ok(ExpiryDuration::Eternal),
+            ms if ms >= 0 => Ok(ExpiryDuration::After(Duration::from_millis(ms as u64))),
+            _ => Err(Error::from("Cannot read ExpiryDuration")),
+        }
+    }
+}
+
+/// Times to live of the entries created, updated and read by the cache operations
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExpiryPolicy {
+    pub create: ExpiryDuration,
+    pub update: ExpiryDuration,
+    pub access: ExpiryDuration,
+}
+
 #[derive(Clone, Debug)]
 pub struct CacheConfiguration {
     pub atomicity_mode: AtomicityMode,
@@ -189,6 +241,8 @@ pub struct CacheConfiguration {
     pub write_synchronization_mode: WriteSynchronizationMode,
     pub cache_key_configurations: Option<Vec<CacheKeyConfiguration>>,
     pub query_entities: Option<Vec<QueryEntity>>,
+    /// Applied to the operations that don't set a policy of their own
+    pub expiry_policy: Option<ExpiryPolicy>,
 }
 
 impl CacheConfiguration {
@@ -231,6 +285,7 @@ impl CacheConfiguration {
             write_synchronization_mode: WriteSynchronizationMode::PrimarySync,
             cache_key_configurations: None,
             query_entities: None,
+            expiry_policy: None,
         }
     }
 }
@@ -280,6 +335,8 @@ pub struct Cache<K: WritableType + ReadableType, V: WritableType + ReadableType>
     id: i32,
     pub cfg: CacheConfiguration,
     conn: Arc<Connection>,
+    keep_binary: bool,
+    expiry_policy: Option<ExpiryPolicy>,
     k_phantom: PhantomData<K>,
     v_phantom: PhantomData<V>,
 }
@@ -290,6 +347,47 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
             id,
             cfg,
             conn,
+            keep_binary: false,
+            expiry_policy: None,
+            k_phantom: PhantomData,
+            v_phantom: PhantomData,
+        }
+    }
+
+    /// Returns a view of this cache whose key-value operations apply the expiry policy
+    /// instead of the one configured for the cache. Both share the connection and the entries.
+    /// The queries and the data streamer are not affected by the policy
+    pub fn with_expiry_policy(
+        &self,
+        create: ExpiryDuration,
+        update: ExpiryDuration,
+        access: ExpiryDuration,
+    ) -> Cache<K, V> {
+        Cache {
+            id: self.id,
+            cfg: self.cfg.clone(),
+            conn: self.conn.clone(),
+            keep_binary: self.keep_binary,
+            expiry_policy: Some(ExpiryPolicy {
+                create,
+                update,
+                access,
+            }),
+            k_phantom: PhantomData,
+            v_phantom: PhantomData,
+        }
+    }
+
+    /// Returns a view of this cache reading and writing the keys and values
+    /// in their serialized form. The server doesn't deserialize them either,
+    /// so the objects of the types unknown to both sides can be copied between caches
+    pub fn with_keep_binary(&self) -> Cache<BinaryObject, BinaryObject> {
+        Cache {
+            id: self.id,
+            cfg: self.cfg.clone(),
+            conn: self.conn.clone(),
+            keep_binary: true,
+            expiry_policy: self.expiry_policy,
             k_phantom: PhantomData,
             v_phantom: PhantomData,
         }
@@ -300,7 +398,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::QueryScan,
-                CacheReq::QueryScan::<K, V>(self.id, page_size),
+                CacheReq::QueryScan::<K, V>(self.header(), page_size),
             )
             .map(|resp: QueryScanResp<K, V>| resp.val)
     }
@@ -311,6 +409,17 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         page_size: i32,
         type_name: &str,
         sql: &str,
+    ) -> Result<Vec<(Option<K>, Option<V>)>> {
+        self.query_scan_sql_args(page_size, type_name, sql, &[])
+    }
+
+    /// Same as query_scan_sql, but binds the arguments to '?' placeholders of the query
+    pub fn query_scan_sql_args(
+        &self,
+        page_size: i32,
+        type_name: &str,
+        sql: &str,
+        args: &[&dyn WritableType],
     ) -> Result<Vec<(Option<K>, Option<V>)>> {
         self.conn
             .send_and_read(
@@ -320,6 +429,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
                     page_size,
                     type_name.to_string(),
                     sql.to_string(),
+                    args,
                 ),
             )
             .map(|resp: QueryScanResp<K, V>| resp.val)
@@ -330,44 +440,75 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         page_size: i32,
         cb: &mut dyn Fn(&mut dyn Read, i32) -> Result<()>,
     ) -> Result<bool> {
-        let req = CacheReq::QueryScan::<K, V>(self.id, page_size);
-        let more: Arc<Mutex<Option<bool>>> = Arc::new(Mutex::new(None));
+        let req = CacheReq::QueryScan::<K, V>(self.header(), page_size);
+        let more: Cell<Option<bool>> = Cell::new(None);
         self.conn
             .send_and_read_dyn(OpCode::QueryScan, req, &mut |mut buf| {
                 let _cursor_id = read_i64(&mut buf)?;
                 let count = read_i32(&mut buf)?;
                 cb(&mut buf, count)?;
-                more.lock().unwrap().replace(read_bool(&mut buf)?);
+                more.set(Some(read_bool(&mut buf)?));
                 Ok(())
             })?;
-        let more = more
-            .lock()
-            .unwrap()
-            .ok_or(Error::from("Callback not invoked!"))?;
+        let more = more.get().ok_or(Error::from("Callback not invoked!"))?;
         Ok(more)
     }
 
+    /// Subscribes to the changes of the cache.
+    /// https://ignite.apache.org/docs/latest/binary-client-protocol/sql-and-scan-queries#op_query_continuous
+    ///
+    /// The server sends the events in batches of `page_size`, or earlier when `time_interval`
+    /// passes since the first event of the batch; zero interval waits for the full batch.
+    /// The query uses a connection of its own, this cache's connection is not blocked by it
+    pub fn query_continuous(
+        &self,
+        page_size: i32,
+        time_interval: Duration,
+        include_expired: bool,
+    ) -> Result<ContinuousQuery<K, V>> {
+        ContinuousQuery::start(
+            &self.conn,
+            QueryContinuousReq {
+                cache_id: self.id,
+                page_size,
+                time_interval: duration_to_millis(time_interval),
+                include_expired,
+            },
+        )
+    }
+
+    /// Creates a streamer loading the entries into the cache in the background.
+    /// Much faster than `put_all` for large amounts of data
+    pub fn data_streamer(&self, config: &DataStreamerConfiguration) -> DataStreamer<K, V> {
+        DataStreamer::new(self.conn.clone(), self.id, config)
+    }
+
     pub fn get(&self, key: &K) -> Result<Option<V>> {
         self.conn
-            .send_and_read(OpCode::CacheGet, CacheReq::Get::<K, V>(self.id, key))
+            .send_and_read(OpCode::CacheGet, CacheReq::Get::<K, V>(self.header(), key))
             .map(|resp: CacheDataObjectResp<V>| resp.val)
     }
 
     pub fn get_all(&self, keys: &[K]) -> Result<Vec<(Option<K>, Option<V>)>> {
         self.conn
-            .send_and_read(OpCode::CacheGetAll, CacheReq::GetAll::<K, V>(self.id, keys))
+            .send_and_read(
+                OpCode::CacheGetAll,
+                CacheReq::GetAll::<K, V>(self.header(), keys),
+            )
             .map(|resp: CachePairsResp<K, V>| resp.val)
     }
 
     pub fn put(&self, key: &K, value: &V) -> Result<()> {
-        self.conn
-            .send(OpCode::CachePut, CacheReq::Put::<K, V>(self.id, key, value))
+        self.conn.send(
+            OpCode::CachePut,
+            CacheReq::Put::<K, V>(self.header(), key, value),
+        )
     }
 
     pub fn put_all(&self, pairs: &[(K, V)]) -> Result<()> {
         self.conn.send(
             OpCode::CachePutAll,
-            CacheReq::PutAll::<K, V>(self.id, pairs),
+            CacheReq::PutAll::<K, V>(self.header(), pairs),
         )
     }
 
@@ -375,7 +516,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheContainsKey,
-                CacheReq::ContainsKey::<K, V>(self.id, key),
+                CacheReq::ContainsKey::<K, V>(self.header(), key),
             )
             .map(|resp: CacheBoolResp| resp.flag)
     }
@@ -384,7 +525,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheContainsKeys,
-                CacheReq::ContainsKeys::<K, V>(self.id, keys),
+                CacheReq::ContainsKeys::<K, V>(self.header(), keys),
             )
             .map(|resp: CacheBoolResp| resp.flag)
     }
@@ -393,7 +534,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheGetAndPut,
-                CacheReq::GetAndPut::<K, V>(self.id, key, value),
+                CacheReq::GetAndPut::<K, V>(self.header(), key, value),
             )
             .map(|resp: CacheDataObjectResp<V>| resp.val)
     }
@@ -402,7 +543,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheGetAndReplace,
-                CacheReq::GetAndReplace::<K, V>(self.id, key, value),
+                CacheReq::GetAndReplace::<K, V>(self.header(), key, value),
             )
             .map(|resp: CacheDataObjectResp<V>| resp.val)
     }
@@ -411,7 +552,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheGetAndRemove,
-                CacheReq::GetAndRemove::<K, V>(self.id, key),
+                CacheReq::GetAndRemove::<K, V>(self.header(), key),
             )
             .map(|resp: CacheDataObjectResp<V>| resp.val)
     }
@@ -420,7 +561,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CachePutIfAbsent,
-                CacheReq::PutIfAbsent::<K, V>(self.id, key, value),
+                CacheReq::PutIfAbsent::<K, V>(self.header(), key, value),
             )
             .map(|resp: CacheBoolResp| resp.flag)
     }
@@ -429,7 +570,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheGetAndPutIfAbsent,
-                CacheReq::GetAndPutIfAbsent::<K, V>(self.id, key, value),
+                CacheReq::GetAndPutIfAbsent::<K, V>(self.header(), key, value),
             )
             .map(|resp: CacheDataObjectResp<V>| resp.val)
     }
@@ -438,7 +579,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheReplace,
-                CacheReq::Replace::<K, V>(self.id, key, value),
+                CacheReq::Replace::<K, V>(self.header(), key, value),
             )
             .map(|resp: CacheBoolResp| resp.flag)
     }
@@ -447,27 +588,27 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheReplaceIfEquals,
-                CacheReq::ReplaceIfEquals::<K, V>(self.id, key, old, new),
+                CacheReq::ReplaceIfEquals::<K, V>(self.header(), key, old, new),
             )
             .map(|resp: CacheBoolResp| resp.flag)
     }
 
     pub fn clear(&self) -> Result<()> {
         self.conn
-            .send(OpCode::CacheClear, CacheReq::Clear::<K, V>(self.id))
+            .send(OpCode::CacheClear, CacheReq::Clear::<K, V>(self.header()))
     }
 
     pub fn clear_key(&self, key: &K) -> Result<()> {
         self.conn.send(
             OpCode::CacheClearKey,
-            CacheReq::ClearKey::<K, V>(self.id, key),
+            CacheReq::ClearKey::<K, V>(self.header(), key),
         )
     }
 
     pub fn clear_keys(&self, keys: &[K]) -> Result<()> {
         self.conn.send(
             OpCode::CacheClearKeys,
-            CacheReq::ClearKeys::<K, V>(self.id, keys),
+            CacheReq::ClearKeys::<K, V>(self.header(), keys),
         )
     }
 
@@ -475,7 +616,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheRemoveKey,
-                CacheReq::RemoveKey::<K, V>(self.id, key),
+                CacheReq::RemoveKey::<K, V>(self.header(), key),
             )
             .map(|resp: CacheBoolResp| resp.flag)
     }
@@ -484,7 +625,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheRemoveIfEquals,
-                CacheReq::RemoveIfEquals::<K, V>(self.id, key, value),
+                CacheReq::RemoveIfEquals::<K, V>(self.header(), key, value),
             )
             .map(|resp: CacheBoolResp| resp.flag)
     }
@@ -494,7 +635,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheGetSize,
-                CacheReq::GetSize::<K, V>(self.id, modes),
+                CacheReq::GetSize::<K, V>(self.header(), modes),
             )
             .map(|resp: CacheSizeResp| resp.size)
     }
@@ -504,7 +645,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheGetSize,
-                CacheReq::GetSize::<K, V>(self.id, modes),
+                CacheReq::GetSize::<K, V>(self.header(), modes),
             )
             .map(|resp: CacheSizeResp| resp.size)
     }
@@ -513,7 +654,7 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
         self.conn
             .send_and_read(
                 OpCode::CacheGetSize,
-                CacheReq::GetSize::<K, V>(self.id, modes),
+                CacheReq::GetSize::<K, V>(self.header(), modes),
             )
             .map(|resp: CacheSizeResp| resp.size)
     }
@@ -521,12 +662,175 @@ impl<K: WritableType + ReadableType, V: WritableType + ReadableType> Cache<K, V>
     pub fn remove_keys(&self, keys: &[K]) -> Result<()> {
         self.conn.send(
             OpCode::CacheRemoveKeys,
-            CacheReq::RemoveKeys::<K, V>(self.id, keys),
+            CacheReq::RemoveKeys::<K, V>(self.header(), keys),
         )
     }
 
     pub fn remove_all(&self) -> Result<()> {
-        self.conn
-            .send(OpCode::CacheRemoveAll, CacheReq::RemoveAll::<K, V>(self.id))
+        self.conn.send(
+            OpCode::CacheRemoveAll,
+            CacheReq::RemoveAll::<K, V>(self.header()),
+        )
+    }
+
+    fn header(&self) -> CacheReqHeader {
+        CacheReqHeader {
+            cache_id: self.id,
+            keep_binary: self.keep_binary,
+            expiry_policy: self.expiry_policy,
+        }
+    }
+}
+
+/// Key-value operations of a cache. Implemented by `Cache` and by the in-memory
+/// `InMemoryCache`, so the code using a cache could be tested without a cluster
+pub trait KeyValueCache<K, V> {
+    /// Returns first page of the scan query results
+    fn query_scan(&self, page_size: i32) -> Result<Vec<(Option<K>, Option<V>)>>;
+    /// Returns the value associated with the key
+    fn get(&self, key: &K) -> Result<Option<V>>;
+    /// Returns the pairs of the keys present in the cache
+    fn get_all(&self, keys: &[K]) -> Result<Vec<(Option<K>, Option<V>)>>;
+    /// Associates the value with the key
+    fn put(&self, key: &K, value: &V) -> Result<()>;
+    /// Puts all the pairs
+    fn put_all(&self, pairs: &[(K, V)]) -> Result<()>;
+    /// Checks if the key is present
+    fn contains_key(&self, key: &K) -> Result<bool>;
+    /// Checks if all the keys are present
+    fn contains_keys(&self, keys: &[K]) -> Result<bool>;
+    /// Puts the value and returns the previous one
+    fn get_and_put(&self, key: &K, value: &V) -> Result<Option<V>>;
+    /// Replaces the value if the key is present. Returns the previous value
+    fn get_and_replace(&self, key: &K, value: &V) -> Result<Option<V>>;
+    /// Removes the key and returns its value
+    fn get_and_remove(&self, key: &K) -> Result<Option<V>>;
+    /// Puts the value if the key is absent. Returns true if the value was put
+    fn put_if_absent(&self, key: &K, value: &V) -> Result<bool>;
+    /// Puts the value if the key is absent. Returns the present value
+    fn get_and_put_if_absent(&self, key: &K, value: &V) -> Result<Option<V>>;
+    /// Replaces the value if the key is present. Returns true if the value was replaced
+    fn replace(&self, key: &K, value: &V) -> Result<bool>;
+    /// Replaces the value if the current one equals the old value
+    fn replace_if_equals(&self, key: &K, old: &V, new: &V) -> Result<bool>;
+    /// Removes all the entries
+    fn clear(&self) -> Result<()>;
+    /// Removes the key
+    fn clear_key(&self, key: &K) -> Result<()>;
+    /// Removes the keys
+    fn clear_keys(&self, keys: &[K]) -> Result<()>;
+    /// Removes the key. Returns true if it was present
+    fn remove_key(&self, key: &K) -> Result<bool>;
+    /// Removes the key if its value equals the provided one
+    fn remove_if_equals(&self, key: &K, value: &V) -> Result<bool>;
+    /// Returns the number of entries
+    fn get_size(&self) -> Result<i64>;
+    /// Returns the number of entries in the given peek mode
+    fn get_size_peek_mode(&self, mode: CachePeekMode) -> Result<i64>;
+    /// Returns the number of entries in the given peek modes
+    fn get_size_peek_modes(&self, modes: Vec<CachePeekMode>) -> Result<i64>;
+    /// Removes the keys
+    fn remove_keys(&self, keys: &[K]) -> Result<()>;
+    /// Removes all the entries
+    fn remove_all(&self) -> Result<()>;
+}
+
+impl<K: WritableType + ReadableType, V: WritableType + ReadableType> KeyValueCache<K, V>
+    for Cache<K, V>
+{
+    fn query_scan(&self, page_size: i32) -> Result<Vec<(Option<K>, Option<V>)>> {
+        Cache::query_scan(self, page_size)
+    }
+
+    fn get(&self, key: &K) -> Result<Option<V>> {
+        Cache::get(self, key)
+    }
+
+    fn get_all(&self, keys: &[K]) -> Result<Vec<(Option<K>, Option<V>)>> {
+        Cache::get_all(self, keys)
+    }
+
+    fn put(&self, key: &K, value: &V) -> Result<()> {
+        Cache::put(self, key, value)
+    }
+
+    fn put_all(&self, pairs: &[(K, V)]) -> Result<()> {
+        Cache::put_all(self, pairs)
+    }
+
+    fn contains_key(&self, key: &K) -> Result<bool> {
+        Cache::contains_key(self, key)
+    }
+
+    fn contains_keys(&self, keys: &[K]) -> Result<bool> {
+        Cache::contains_keys(self, keys)
+    }
+
+    fn get_and_put(&self, key: &K, value: &V) -> Result<Option<V>> {
+        Cache::get_and_put(self, key, value)
+    }
+
+    fn get_and_replace(&self, key: &K, value: &V) -> Result<Option<V>> {
+        Cache::get_and_replace(self, key, value)
+    }
+
+    fn get_and_remove(&self, key: &K) -> Result<Option<V>> {
+        Cache::get_and_remove(self, key)
+    }
+
+    fn put_if_absent(&self, key: &K, value: &V) -> Result<bool> {
+        Cache::put_if_absent(self, key, value)
+    }
+
+    fn get_and_put_if_absent(&self, key: &K, value: &V) -> Result<Option<V>> {
+        Cache::get_and_put_if_absent(self, key, value)
+    }
+
+    fn replace(&self, key: &K, value: &V) -> Result<bool> {
+        Cache::replace(self, key, value)
+    }
+
+    fn replace_if_equals(&self, key: &K, old: &V, new: &V) -> Result<bool> {
+        Cache::replace_if_equals(self, key, old, new)
+    }
+
+    fn clear(&self) -> Result<()> {
+        Cache::clear(self)
+    }
+
+    fn clear_key(&self, key: &K) -> Result<()> {
+        Cache::clear_key(self, key)
+    }
+
+    fn clear_keys(&self, keys: &[K]) -> Result<()> {
+        Cache::clear_keys(self, keys)
+    }
+
+    fn remove_key(&self, key: &K) -> Result<bool> {
+        Cache::remove_key(self, key)
+    }
+
+    fn remove_if_equals(&self, key: &K, value: &V) -> Result<bool> {
+        Cache::remove_if_equals(self, key, value)
+    }
+
+    fn get_size(&self) -> Result<i64> {
+        Cache::get_size(self)
+    }
+
+    fn get_size_peek_mode(&self, mode: CachePeekMode) -> Result<i64> {
+        Cache::get_size_peek_mode(self, mode)
+    }
+
+    fn get_size_peek_modes(&self, modes: Vec<CachePeekMode>) -> Result<i64> {
+        Cache::get_size_peek_modes(self, modes)
+    }
+
+    fn remove_keys(&self, keys: &[K]) -> Result<()> {
+        Cache::remove_keys(self, keys)
+    }
+
+    fn remove_all(&self) -> Result<()> {
+        Cache::remove_all(self)
     }
 }
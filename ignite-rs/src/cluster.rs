@@ -0,0 +1,319 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::api::cluster::{
+    ClusterGroupGetNodeIdsReq, ClusterGroupGetNodeIdsResp, ClusterGroupGetNodeInfoReq,
+    ClusterGroupGetNodeInfoResp,
+};
+use crate::api::OpCode;
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::protocol::complex_obj::IgniteValue;
+use crate::Uuid;
+
+/// State of the cluster.
+/// Persistent clusters start inactive and have to be activated before caches can be used
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClusterState {
+    /// Caches cannot be read or modified
+    Inactive = 0,
+    /// Fully operational
+    Active = 1,
+    /// Caches can be read but not modified
+    ActiveReadOnly = 2,
+}
+
+impl From<ClusterState> for u8 {
+    fn from(state: ClusterState) -> u8 {
+        state as u8
+    }
+}
+
+impl TryFrom<u8> for ClusterState {
+    type Error = Error;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ClusterState::Inactive),
+            1 => Ok(ClusterState::Active),
+            2 => Ok(ClusterState::ActiveReadOnly),
+            _ => Err(Error::from("Cannot read ClusterState")),
+        }
+    }
+}
+
+/// Node filter of a cluster group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NodeFilter {
+    /// Nodes having the attribute with the value
+    Attribute(String, String),
+    /// Server nodes if true, client nodes otherwise
+    ServerNodes(bool),
+}
+
+impl NodeFilter {
+    #[cfg(any(test, feature = "fake-server"))]
+    pub(crate) fn matches(&self, node: &ClusterNode) -> bool {
+        match self {
+            NodeFilter::Attribute(name, value) => {
+                node.attribute(name) == Some(&IgniteValue::String(value.clone()))
+            }
+            NodeFilter::ServerNodes(server) => node.is_client != *server,
+        }
+    }
+}
+
+/// Group of the cluster nodes matching all the filters.
+/// Returned by `Client::cluster_group` for the whole cluster and narrowed by the `for_*` methods
+#[derive(Clone)]
+pub struct ClusterGroup {
+    conn: Arc<Connection>,
+    filters: Vec<NodeFilter>,
+}
+
+impl ClusterGroup {
+    pub(crate) fn new(conn: Arc<Connection>) -> ClusterGroup {
+        ClusterGroup {
+            conn,
+            filters: Vec::new(),
+        }
+    }
+
+    fn with_filter(&self, filter: NodeFilter) -> ClusterGroup {
+        let mut filters = self.filters.clone();
+        filters.push(filter);
+        ClusterGroup {
+            conn: self.conn.clone(),
+            filters,
+        }
+    }
+
+    /// Nodes of this group having the attribute with the value
+    pub fn for_attribute(&self, name: &str, value: &str) -> ClusterGroup {
+        self.with_filter(NodeFilter::Attribute(name.into(), value.into()))
+    }
+
+    /// Server nodes of this group
+    pub fn for_servers(&self) -> ClusterGroup {
+        self.with_filter(NodeFilter::ServerNodes(true))
+    }
+
+    /// Client nodes of this group
+    pub fn for_clients(&self) -> ClusterGroup {
+        self.with_filter(NodeFilter::ServerNodes(false))
+    }
+
+    /// https://ignite.apache.org/docs/latest/binary-client-protocol/cluster-api#op_cluster_group_get_node_ids
+    pub fn node_ids(&self) -> Result<Vec<Uuid>> {
+        let resp: ClusterGroupGetNodeIdsResp = self.conn.send_and_read(
+            OpCode::ClusterGroupGetNodeIds,
+            ClusterGroupGetNodeIdsReq {
+                filters: &self.filters,
+            },
+        )?;
+        Ok(resp.ids)
+    }
+
+    /// https://ignite.apache.org/docs/latest/binary-client-protocol/cluster-api#op_cluster_group_get_node_info
+    pub fn nodes(&self) -> Result<Vec<ClusterNode>> {
+        let ids = self.node_ids()?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let resp: ClusterGroupGetNodeInfoResp = self.conn.send_and_read(
+            OpCode::ClusterGroupGetNodeInfo,
+            ClusterGroupGetNodeInfoReq { ids: &ids },
+        )?;
+        Ok(resp.nodes)
+    }
+}
+
+/// Node of the cluster
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterNode {
+    pub id: Uuid,
+    /// Attributes the thin client protocol can carry. Java-only attributes are left out by the server
+    pub attributes: Vec<(String, IgniteValue)>,
+    pub addresses: Vec<String>,
+    pub host_names: Vec<String>,
+    /// Order in which the node joined the topology
+    pub order: i64,
+    /// True for the node the client is connected to
+    pub is_local: bool,
+    pub is_daemon: bool,
+    pub is_client: bool,
+    /// Unlike `id`, stays the same after a restart of a persistent node
+    pub consistent_id: IgniteValue,
+    pub version: NodeVersion,
+}
+
+impl ClusterNode {
+    /// Value of the attribute, if the node has it
+    pub fn attribute(&self, name: &str) -> Option<&IgniteValue> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Ignite version of a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub maintenance: u8,
+    pub stage: String,
+    pub revision_timestamp: i64,
+    pub revision_hash: Vec<u8>,
+}
+
+/// Addresses the thin client connector of a server node listens on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeEndpoints {
+    pub id: Uuid,
+    pub port: i32,
+    pub addresses: Vec<String>,
+}
+
+impl NodeEndpoints {
+    /// Addresses with the port, ready to be used in `ClientConfig::new`
+    pub fn socket_addrs(&self) -> Vec<String> {
+        self.addresses
+            .iter()
+            .map(|addr| match addr.contains(':') {
+                true => format!("[{}]:{}", addr, self.port),
+                false => format!("{}:{}", addr, self.port),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StatusCode;
+    use crate::fake_server::FakeServer;
+    use crate::Ignite;
+
+    #[test]
+    fn test_change_cluster_state() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, i32>("cache").unwrap();
+        cache.put(&1, &1).unwrap();
+        assert_eq!(client.get_cluster_state().unwrap(), ClusterState::Active);
+
+        client
+            .change_cluster_state(ClusterState::ActiveReadOnly)
+            .unwrap();
+        assert_eq!(
+            client.get_cluster_state().unwrap(),
+            ClusterState::ActiveReadOnly
+        );
+        assert_eq!(cache.get(&1).unwrap(), Some(1));
+        match cache.put(&1, &2) {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::InvalidNodeState),
+            _ => panic!("read-only cluster is modified"),
+        }
+
+        client.change_cluster_state(ClusterState::Inactive).unwrap();
+        assert!(cache.get(&1).is_err());
+
+        client.change_cluster_state(ClusterState::Active).unwrap();
+        cache.put(&1, &2).unwrap();
+        assert_eq!(cache.get(&1).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_wal_state() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        client.get_or_create_cache::<i32, i32>("cache").unwrap();
+        assert!(client.is_wal_enabled("cache").unwrap());
+
+        assert!(client.set_wal_enabled("cache", false).unwrap());
+        assert!(!client.set_wal_enabled("cache", false).unwrap());
+        assert!(!client.is_wal_enabled("cache").unwrap());
+        assert!(client.set_wal_enabled("cache", true).unwrap());
+        assert!(client.is_wal_enabled("cache").unwrap());
+
+        match client.is_wal_enabled("missing") {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::CacheDoesNotExist),
+            _ => panic!("WAL state of a missing cache"),
+        }
+    }
+
+    fn node(id: u128, is_client: bool, role: &str) -> ClusterNode {
+        ClusterNode {
+            id: Uuid::from(id),
+            attributes: vec![("role".to_string(), IgniteValue::String(role.to_string()))],
+            addresses: vec!["10.0.0.1".to_string(), "::1".to_string()],
+            host_names: Vec::new(),
+            order: id as i64,
+            is_local: false,
+            is_daemon: false,
+            is_client,
+            consistent_id: IgniteValue::Null,
+            version: NodeVersion {
+                major: 2,
+                minor: 16,
+                maintenance: 0,
+                stage: String::new(),
+                revision_timestamp: 1,
+                revision_hash: vec![1, 2],
+            },
+        }
+    }
+
+    #[test]
+    fn test_cluster_group() {
+        let server = FakeServer::start().unwrap();
+        server.add_node(node(2, false, "storage"));
+        server.add_node(node(3, true, "loader"));
+        let mut client = server.connect().unwrap();
+        let cluster = client.cluster_group();
+
+        let ids = |group: ClusterGroup| -> Vec<u128> {
+            let ids = group.node_ids().unwrap();
+            ids.into_iter().map(u128::from).collect()
+        };
+        assert_eq!(ids(cluster.clone()), vec![1, 2, 3]);
+        assert_eq!(ids(cluster.for_servers()), vec![1, 2]);
+        assert_eq!(ids(cluster.for_clients()), vec![3]);
+        assert_eq!(ids(cluster.for_attribute("role", "storage")), vec![2]);
+        let none = cluster.for_clients().for_attribute("role", "storage");
+        assert!(ids(none.clone()).is_empty());
+        assert!(none.nodes().unwrap().is_empty());
+
+        let nodes = cluster.nodes().unwrap();
+        assert_eq!(nodes.len(), 3);
+        assert!(nodes[0].is_local);
+        assert_eq!(
+            nodes[0].consistent_id,
+            IgniteValue::String(server.addr().to_string())
+        );
+        assert_eq!(nodes[1], node(2, false, "storage"));
+        assert_eq!(
+            nodes[2].attribute("role"),
+            Some(&IgniteValue::String("loader".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_node_endpoints() {
+        let server = FakeServer::start().unwrap();
+        server.add_node(node(2, false, "storage"));
+        server.add_node(node(3, true, "loader"));
+        let mut client = server.connect().unwrap();
+
+        let endpoints = client.get_node_endpoints().unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].socket_addrs(), vec![server.addr().to_string()]);
+        let port = server.addr().port();
+        assert_eq!(
+            endpoints[1].socket_addrs(),
+            vec![format!("10.0.0.1:{}", port), format!("[::1]:{}", port)]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::compute::{ComputeTaskExecuteReq, FLAG_NO_FAILOVER, FLAG_NO_RESULT_CACHE};
+use crate::api::OpCode;
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::utils::duration_to_millis;
+use crate::{ReadableType, WritableType};
+
+/// Executes compute tasks deployed on the server nodes.
+/// The settings are changed by the `with_*` methods, each returning a new instance
+#[derive(Clone)]
+pub struct Compute {
+    conn: Arc<Connection>,
+    flags: u8,
+    timeout: Option<Duration>,
+}
+
+impl Compute {
+    pub(crate) fn new(conn: Arc<Connection>) -> Compute {
+        Compute {
+            conn,
+            flags: 0,
+            timeout: None,
+        }
+    }
+
+    /// Jobs of the task are not failed over to other nodes if their node leaves the cluster
+    pub fn with_no_failover(&self) -> Compute {
+        self.with_flag(FLAG_NO_FAILOVER)
+    }
+
+    /// Results of the finished jobs are not kept until the whole task completes
+    pub fn with_no_result_cache(&self) -> Compute {
+        self.with_flag(FLAG_NO_RESULT_CACHE)
+    }
+
+    /// The task fails if it is not finished in time. There is no timeout by default
+    pub fn with_timeout(&self, timeout: Duration) -> Compute {
+        Compute {
+            timeout: Some(timeout),
+            ..self.clone()
+        }
+    }
+
+    fn with_flag(&self, flag: u8) -> Compute {
+        Compute {
+            flags: self.flags | flag,
+            ..self.clone()
+        }
+    }
+
+    /// Executes the Java task with the class name and returns its result.
+    /// https://ignite.apache.org/docs/latest/binary-client-protocol/compute-api#op_compute_task_execute
+    ///
+    /// Blocks until the task is finished. The task runs on a connection of its own,
+    /// so the client stays usable by other threads meanwhile
+    pub fn execute<A: WritableType, R: ReadableType>(
+        &self,
+        task_name: &str,
+        arg: &A,
+    ) -> Result<Option<R>> {
+        let timeout = self.timeout.map_or(0, duration_to_millis);
+        let conn = self.conn.open_dedicated()?;
+        let mut body = conn.send_and_wait_notification(
+            OpCode::ComputeTaskExecute,
+            ComputeTaskExecuteReq {
+                flags: self.flags,
+                timeout,
+                task_name,
+                arg,
+            },
+            OpCode::ComputeTaskFinished,
+        )?;
+        R::read(&mut body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, StatusCode};
+    use crate::fake_server::FakeServer;
+    use crate::protocol::inspect::inspect_session;
+    use crate::recording::read_session;
+    use crate::{new_client, Ignite};
+
+    fn start_server() -> FakeServer {
+        let server = FakeServer::start().unwrap();
+        server.add_task("Sum", |arg: Option<Vec<i64>>| {
+            Ok(arg.unwrap_or_default().iter().sum::<i64>())
+        });
+        server.add_task("Fail", |_: Option<String>| -> Result<String> {
+            Err(Error::from("Task failed"))
+        });
+        server
+    }
+
+    #[test]
+    fn test_execute() {
+        let server = start_server();
+        let mut client = server.connect().unwrap();
+        let compute = client
+            .compute()
+            .with_no_failover()
+            .with_no_result_cache()
+            .with_timeout(Duration::from_secs(10));
+        let sum: Option<i64> = compute.execute("Sum", &vec![1i64, 2, 3]).unwrap();
+        assert_eq!(sum, Some(6));
+        let sum: Option<i64> = compute.execute("Sum", &None::<Vec<i64>>).unwrap();
+        assert_eq!(sum, Some(0));
+        // the notification is consumed, the connection keeps working
+        assert!(client.get_cache_names().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_task_failure() {
+        let server = start_server();
+        let mut client = server.connect().unwrap();
+        match client
+            .compute()
+            .execute::<_, String>("Fail", &"arg".to_string())
+        {
+            Err(Error::Server { code, message }) => {
+                assert_eq!(code, StatusCode::Failed);
+                assert!(message.contains("Task failed"));
+            }
+            _ => panic!("task failure is not reported"),
+        }
+        assert!(client
+            .compute()
+            .execute::<_, String>("Unknown", &1)
+            .is_err());
+        let sum: Option<i64> = client.compute().execute("Sum", &vec![5i64]).unwrap();
+        assert_eq!(sum, Some(5));
+    }
+
+    #[test]
+    fn test_shared_connection_not_used() {
+        let server = start_server();
+        let path = std::env::temp_dir().join(format!(
+            "ignite-rs-compute-shared-{}.session",
+            std::process::id()
+        ));
+        let mut conf = server.client_config();
+        conf.session_record_path = Some(path.clone());
+        let mut client = new_client(conf).unwrap();
+        let _: Option<i64> = client.compute().execute("Sum", &vec![1i64]).unwrap();
+        drop(client);
+
+        let frames = read_session(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // the task went over a connection of its own
+        let nodes = inspect_session(&frames).unwrap();
+        let labels: Vec<&str> = nodes.iter().map(|node| node.label.as_str()).collect();
+        assert_eq!(labels, vec!["handshake request", "handshake response"]);
+    }
+
+    #[test]
+    fn test_inspect_notification() {
+        let server = start_server();
+        let path =
+            std::env::temp_dir().join(format!("ignite-rs-compute-{}.session", std::process::id()));
+        let mut conf = server.client_config();
+        conf.session_record_path = Some(path.clone());
+        let conn = Connection::new(&conf).unwrap();
+        conn.send_and_wait_notification(
+            OpCode::ComputeTaskExecute,
+            ComputeTaskExecuteReq {
+                flags: 0,
+                timeout: 0,
+                task_name: "Sum",
+                arg: &vec![1i64],
+            },
+            OpCode::ComputeTaskFinished,
+        )
+        .unwrap();
+        drop(conn);
+        let frames = read_session(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let nodes = inspect_session(&frames).unwrap();
+        let labels: Vec<&str> = nodes.iter().map(|node| node.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "handshake request",
+                "handshake response",
+                "request",
+                "response",
+                "notification"
+            ]
+        );
+        assert_eq!(nodes[3].children[3].label, "task id = 1");
+        let notification: Vec<&str> = nodes[4]
+            .children
+            .iter()
+            .map(|node| node.label.as_str())
+            .collect();
+        assert_eq!(
+            notification[1..],
+            [
+                "resource id = 1",
+                "flags = 0x0004 [NOTIFICATION]",
+                "op code = 6001 (ComputeTaskFinished)",
+                "Long (4) = 1"
+            ]
+        );
+    }
+}
@@ -1,18 +1,27 @@
 use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::net::{Shutdown, TcpStream};
 
 use crate::api::OpCode;
-use crate::error::{Error, Result};
-use crate::handshake::handshake;
+use crate::error::{Error, Result, StatusCode};
+use crate::handshake::{handshake, ProtocolContext, MIN_VERSION, VERSION};
 use crate::protocol::Flag::{Failure, Success};
-use crate::protocol::{read_i32, read_i64, write_i16, write_i32, write_i64, Flag};
+use crate::protocol::{
+    read_bytes, read_i64, read_len, write_i16, write_i32, write_i64, Flag, RespHeader,
+};
+use crate::recording::RecordingStream;
+use crate::WriteableReq;
 use crate::{ClientConfig, ReadableReq};
-use crate::{ReadableType, WriteableReq};
 use bufstream::BufStream;
 #[cfg(feature = "ssl")]
 use rustls;
+use std::collections::HashMap;
+use std::fs::File;
 use std::io;
+use std::io::{Cursor, ErrorKind};
 use std::option::Option::Some;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
 #[allow(unused_imports)]
 use std::sync::{Arc, Mutex};
 #[cfg(feature = "ssl")]
@@ -20,25 +29,60 @@ use webpki;
 
 const DFLT_READ_BUF_SIZE: usize = 1024;
 const DFLT_WRITE_BUF_SIZE: usize = 1024;
+const DFLT_MAX_MESSAGE_SIZE: usize = 256 * 1024 * 1024;
 const REQ_HEADER_SIZE_BYTES: i32 = 10;
 
+/// Blocking connection to a node. A request that fails in the middle of the frame marks
+/// the connection as broken, as its stream can't be read further. Broken connections
+/// are not reconnected: every following request fails at once with an error
 pub struct Connection {
     #[cfg(not(feature = "ssl"))]
-    stream: Mutex<BufStream<TcpStream>>,
+    stream: Mutex<BufStream<RecordingStream<TcpStream>>>,
     #[cfg(feature = "ssl")]
-    stream: Mutex<BufStream<rustls::StreamOwned<rustls::ClientSession, TcpStream>>>,
+    stream:
+        Mutex<BufStream<RecordingStream<rustls::StreamOwned<rustls::ClientSession, TcpStream>>>>,
+    broken: AtomicBool,
+    max_message_size: usize,
+    /// Clone of the socket to shut it down while the stream is locked
+    socket: TcpStream,
+    /// Receivers of the notifications by the resource id
+    listeners: Mutex<HashMap<i64, Sender<Vec<u8>>>>,
+    conf: ClientConfig,
+    protocol: ProtocolContext,
 }
 
 impl Connection {
+    /// Connects with the client's protocol version. A server rejecting it reports the version
+    /// it supports, the handshake is repeated with that one if the client can speak it
     pub(crate) fn new(conf: &ClientConfig) -> Result<Connection> {
+        match Connection::connect(conf, VERSION) {
+            // the server closes the connection after the rejection, so another one is opened
+            Err(Error::Handshake { server_version, .. })
+                if (MIN_VERSION..VERSION).contains(&server_version) =>
+            {
+                Connection::connect(conf, server_version)
+            }
+            result => result,
+        }
+    }
+
+    fn connect(conf: &ClientConfig, version: (i16, i16, i16)) -> Result<Connection> {
         match TcpStream::connect(&conf.addr) {
             Ok(stream) => {
                 // apply tcp configs
                 Connection::configure_tcp(&stream, conf)?;
+                let socket = stream.try_clone()?;
 
                 // wrap in tls stream if this feature enabled
                 #[cfg(feature = "ssl")]
-                let mut stream = Connection::wrap_tls_stream(&conf.tls_conf, stream)?;
+                let stream = Connection::wrap_tls_stream(&conf.tls_conf, stream)?;
+
+                // record frames if requested
+                let sink: Option<Box<dyn Write + Send>> = match conf.session_record_path {
+                    Some(ref path) => Some(Box::new(io::BufWriter::new(File::create(path)?))),
+                    None => None,
+                };
+                let stream = RecordingStream::new(stream, sink);
 
                 // wrap in buffered stream
                 let mut buffered_stream = BufStream::with_capacities(
@@ -48,9 +92,15 @@ impl Connection {
                 );
 
                 // try initial handshake
-                match handshake(&mut buffered_stream, conf) {
-                    Ok(_) => Ok(Connection {
+                match handshake(&mut buffered_stream, conf, version) {
+                    Ok(protocol) => Ok(Connection {
                         stream: Mutex::new(buffered_stream),
+                        broken: AtomicBool::new(false),
+                        max_message_size: conf.max_message_size.unwrap_or(DFLT_MAX_MESSAGE_SIZE),
+                        socket,
+                        listeners: Mutex::new(HashMap::new()),
+                        conf: conf.clone(),
+                        protocol,
                     }),
                     Err(err) => Err(err),
                 }
@@ -61,8 +111,8 @@ impl Connection {
 
     /// Send message and read response header. Acquires lock
     pub(crate) fn send(&self, op_code: OpCode, data: impl WriteableReq) -> Result<()> {
-        let sock_lock = &mut *self.stream.lock()?; //acquire lock on socket
-        Connection::send_safe(sock_lock, op_code, data)
+        self.exchange(op_code, data)?;
+        Ok(())
     }
 
     /// Send message, read response header and return a response. Acquires lock
@@ -71,8 +121,8 @@ impl Connection {
         op_code: OpCode,
         data: impl WriteableReq,
     ) -> Result<T> {
-        let sock_lock = &mut *self.stream.lock()?; //acquire lock on socket
-        Connection::send_and_read_safe(sock_lock, op_code, data)
+        let mut frame = self.exchange(op_code, data)?;
+        T::read(&mut frame) //unpack the input bytes into an actual type
     }
 
     /// Send message, let the caller read the result. Acquires lock
@@ -82,17 +132,144 @@ impl Connection {
         req: impl WriteableReq,
         cb: &mut dyn Fn(&mut dyn Read) -> Result<()>,
     ) -> Result<()> {
-        let buf = &mut *self.stream.lock()?; //acquire lock on socket
-        Connection::send_safe(buf, op_code, req)?; //send request and read the response
-        cb(&mut Box::new(buf))?;
+        let mut frame = self.exchange(op_code, req)?;
+        cb(&mut frame)?;
         Ok(())
     }
 
-    fn send_safe<RW: Read + Write>(
+    /// Sends a request and returns the body of the successful response.
+    /// The lock is held only while the frame is transferred, so a failure
+    /// in the response decoding can't leave unread bytes in the stream.
+    /// An I/O error in the middle of the frame does, so such connection is not used anymore
+    fn exchange(&self, op_code: OpCode, payload: impl WriteableReq) -> Result<Cursor<Vec<u8>>> {
+        self.protocol.check_supported(op_code)?;
+        let frame = {
+            let sock_lock = &mut *self.stream.lock()?; //acquire lock on socket
+            if self.broken.load(Ordering::Acquire) {
+                return Err(Error::from("Connection is broken by a previous I/O error"));
+            }
+            let frame = Connection::transfer(sock_lock, op_code, payload, self.max_message_size)
+                .and_then(|frame| self.route_notifications(sock_lock, frame));
+            if frame.is_err() {
+                self.broken.store(true, Ordering::Release);
+            }
+            frame?
+        };
+        Connection::read_resp(frame, self.protocol.has_flags())
+    }
+
+    /// Sends a request, reads the resource id from its response and waits for the notification
+    /// the server sends about that resource. Returns the body of the notification.
+    /// The lock is held until the notification arrives, so it is meant for dedicated connections
+    pub(crate) fn send_and_wait_notification(
+        &self,
+        op_code: OpCode,
+        payload: impl WriteableReq,
+        notification: OpCode,
+    ) -> Result<Cursor<Vec<u8>>> {
+        self.protocol.check_supported(op_code)?;
+        let sock_lock = &mut *self.stream.lock()?;
+        if self.broken.load(Ordering::Acquire) {
+            return Err(Error::from("Connection is broken by a previous I/O error"));
+        }
+        let resp = Connection::transfer(sock_lock, op_code, payload, self.max_message_size)
+            .and_then(|frame| self.route_notifications(sock_lock, frame));
+        if resp.is_err() {
+            self.broken.store(true, Ordering::Release);
+        }
+        let resource_id = read_i64(&mut Connection::read_resp(
+            resp?,
+            self.protocol.has_flags(),
+        )?)?;
+
+        loop {
+            let frame = Connection::read_frame(sock_lock, self.max_message_size);
+            if frame.is_err() {
+                self.broken.store(true, Ordering::Release);
+            }
+            let frame = frame?;
+            let header = peek_header(&frame, self.protocol.has_flags())?;
+            match header.notification {
+                None => return Err(Error::from("Response is not expected")),
+                Some(op_code) if (header.id, op_code) == (resource_id, notification as i16) => {
+                    return read_notification(frame)
+                }
+                Some(_) => self.dispatch(header.id, frame),
+            }
+        }
+    }
+
+    /// Opens another connection with the same configuration, except that it is not recorded.
+    /// The version negotiated by this connection is offered right away
+    pub(crate) fn open_dedicated(&self) -> Result<Connection> {
+        let mut conf = self.conf.clone();
+        conf.session_record_path = None;
+        Connection::connect(&conf, self.protocol.version)
+    }
+
+    /// Registers a receiver of the notifications about the resource
+    pub(crate) fn add_listener(&self, resource_id: i64) -> Result<Receiver<Vec<u8>>> {
+        let (sender, receiver) = mpsc::channel();
+        self.listeners.lock()?.insert(resource_id, sender);
+        Ok(receiver)
+    }
+
+    /// Reads notifications and passes them to their listeners until the connection is closed.
+    /// Intended for dedicated connections: the lock is held all the time.
+    /// The listeners are dropped at the end, so their receivers see the disconnection
+    pub(crate) fn serve_notifications(&self) -> Result<()> {
+        let sock_lock = &mut *self.stream.lock()?;
+        let result = loop {
+            let frame = match Connection::read_frame(sock_lock, self.max_message_size) {
+                Ok(frame) => frame,
+                Err(err) => break Err(Error::from(err)),
+            };
+            match peek_header(&frame, self.protocol.has_flags()) {
+                Ok(header) if header.notification.is_some() => self.dispatch(header.id, frame),
+                Ok(_) => break Err(Error::from("Response is not expected")),
+                Err(err) => break Err(Error::from(err)),
+            }
+        };
+        self.broken.store(true, Ordering::Release);
+        self.listeners.lock()?.clear();
+        result
+    }
+
+    /// Shuts the socket down. Blocked reads fail and the connection is not usable anymore
+    pub(crate) fn close(&self) {
+        let _ = self.socket.shutdown(Shutdown::Both);
+    }
+
+    /// Passes the frames that are notifications to their listeners and reads the next frame,
+    /// until a response frame is read. Returns the response frame
+    fn route_notifications(&self, con: &mut impl Read, mut frame: Vec<u8>) -> io::Result<Vec<u8>> {
+        loop {
+            let header = peek_header(&frame, self.protocol.has_flags())?;
+            if header.notification.is_none() {
+                return Ok(frame);
+            }
+            self.dispatch(header.id, frame);
+            frame = Connection::read_frame(con, self.max_message_size)?;
+        }
+    }
+
+    /// Sends the notification to its listener. Notifications nobody listens to are dropped
+    fn dispatch(&self, resource_id: i64, frame: Vec<u8>) {
+        let mut listeners = self.listeners.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(listener) = listeners.get(&resource_id) {
+            if listener.send(frame).is_err() {
+                listeners.remove(&resource_id);
+            }
+        }
+    }
+
+    /// Writes the request and reads the whole response frame
+    fn transfer<RW: Read + Write>(
         con: &mut RW,
         op_code: OpCode,
         payload: impl WriteableReq,
-    ) -> Result<()> {
+        max_message_size: usize,
+    ) -> io::Result<Vec<u8>> {
         // write common message header
         Connection::write_req_header(con, payload.size(), op_code as i16)?;
 
@@ -102,20 +279,34 @@ impl Connection {
         // flush write buffer
         con.flush()?;
 
-        //read response
-        match Connection::read_resp_header(con)? {
-            Flag::Success => Ok(()),
-            Flag::Failure { err_msg } => Err(Error::from(err_msg.as_str())),
+        Connection::read_frame(con, max_message_size)
+    }
+
+    /// Reads a whole frame, the length prefix is not included
+    fn read_frame(con: &mut impl Read, max_message_size: usize) -> io::Result<Vec<u8>> {
+        let len = read_len(con)?;
+        if len > max_message_size {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Response size {} exceeds the max message size {}",
+                    len, max_message_size
+                ),
+            ));
         }
+        read_bytes(con, len)
     }
 
-    fn send_and_read_safe<T: ReadableReq, RW: Read + Write>(
-        buf: &mut RW,
-        op_code: OpCode,
-        data: impl WriteableReq,
-    ) -> Result<T> {
-        Connection::send_safe(buf, op_code, data)?; //send request and read the response
-        T::read(buf) //unpack the input bytes into an actual type
+    /// Checks the response header and returns the rest of the frame
+    fn read_resp(frame: Vec<u8>, has_flags: bool) -> Result<Cursor<Vec<u8>>> {
+        let mut reader = Cursor::new(frame);
+        match Connection::read_resp_header(&mut reader, has_flags)? {
+            Flag::Success => Ok(reader),
+            Flag::Failure { status, err_msg } => Err(Error::Server {
+                code: StatusCode::from(status),
+                message: err_msg,
+            }),
+        }
     }
 
     /// Returns binary repr of standard request header
@@ -130,20 +321,9 @@ impl Connection {
         Ok(())
     }
 
-    /// Reads standard response header
-    fn read_resp_header(reader: &mut impl Read) -> Result<Flag> {
-        let _length = read_i32(reader)?;
-        let _req_id = read_i64(reader)?;
-        let status = read_i32(reader)?;
-        match status {
-            0 => Ok(Success),
-            _ => {
-                let err_msg = String::read(reader)?;
-                Ok(Failure {
-                    err_msg: err_msg.unwrap(),
-                })
-            }
-        }
+    /// Reads standard response header. The length is expected to be already read
+    fn read_resp_header(reader: &mut impl Read, has_flags: bool) -> Result<Flag> {
+        RespHeader::read(reader, has_flags)?.read_status(reader)
     }
 
     #[cfg(feature = "ssl")]
@@ -173,12 +353,265 @@ impl Connection {
     }
 }
 
+/// Reads the header of a frame, the length prefix is not included.
+/// Notifications are told from responses by their flags, there are none without the flags
+fn peek_header(frame: &[u8], has_flags: bool) -> io::Result<RespHeader> {
+    RespHeader::read(&mut Cursor::new(frame), has_flags)
+}
+
+/// Checks the notification header and returns the rest of the frame.
+/// Notifications about failures are returned as server errors
+pub(crate) fn read_notification(frame: Vec<u8>) -> Result<Cursor<Vec<u8>>> {
+    let mut reader = Cursor::new(frame);
+    let header = RespHeader::read(&mut reader, true)?;
+    if header.notification.is_none() {
+        return Err(Error::from("Notification flag is not set"));
+    }
+    match header.read_status(&mut reader)? {
+        Success => Ok(reader),
+        Failure { status, err_msg } => Err(Error::Server {
+            code: StatusCode::from(status),
+            message: err_msg,
+        }),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::cache_config::CacheGetNamesReq;
+    use crate::fake_server::FakeServer;
+    use crate::handshake::{handshake, VERSION};
     use crate::protocol::complex_obj::{ComplexObject, ComplexObjectSchema, IgniteValue};
+    use crate::protocol::read_i32;
+    use crate::ReadableType;
     use crate::{new_client, Ignite};
 
+    fn send_safe(stream: &mut MockStream) -> Result<Cursor<Vec<u8>>> {
+        let frame = Connection::transfer(
+            stream,
+            OpCode::CacheGetNames,
+            CacheGetNamesReq {},
+            DFLT_MAX_MESSAGE_SIZE,
+        )?;
+        Connection::read_resp(frame, true)
+    }
+
+    /// Replays the response bytes and records the request bytes
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl MockStream {
+        fn new(input: &[u8]) -> MockStream {
+            MockStream {
+                input: Cursor::new(input.to_vec()),
+                output: vec![],
+            }
+        }
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_server_error() {
+        let mut stream = MockStream::new(&hex_literal::hex!(
+            "18 00 00 00" // length
+            "00 00 00 00 00 00 00 00" // request id
+            "01 00" // flags: error
+            "E8 03 00 00" // status: cache does not exist
+            "09 05 00 00 00 63 61 63 68 65" // "cache"
+        ));
+        let err = send_safe(&mut stream).unwrap_err();
+        match err {
+            Error::Server { code, message } => {
+                assert_eq!(code, StatusCode::CacheDoesNotExist);
+                assert_eq!(message, "cache");
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+        assert_eq!(StatusCode::from(42), StatusCode::Unknown(42));
+    }
+
+    #[test]
+    fn test_status_header() {
+        // before 1.4.0 the status follows the request id
+        let resp = hex_literal::hex!(
+            "00 00 00 00 00 00 00 00" // request id
+            "E8 03 00 00" // status: cache does not exist
+            "09 05 00 00 00 63 61 63 68 65" // "cache"
+        );
+        match Connection::read_resp(resp.to_vec(), false) {
+            Err(Error::Server { code, message }) => {
+                assert_eq!(code, StatusCode::CacheDoesNotExist);
+                assert_eq!(message, "cache");
+            }
+            _ => panic!("response error is not reported"),
+        }
+
+        let resp = hex_literal::hex!(
+            "00 00 00 00 00 00 00 00" // request id
+            "00 00 00 00" // status: success
+            "04 00 00 00" // payload
+        );
+        let mut body = Connection::read_resp(resp.to_vec(), false).unwrap();
+        assert_eq!(read_i32(&mut body).unwrap(), 4);
+        assert!(peek_header(&resp, false).unwrap().notification.is_none());
+    }
+
+    #[test]
+    fn test_max_message_size() {
+        let mut stream = MockStream::new(&hex_literal::hex!(
+            "00 00 00 10" // length: 256 MiB
+            "00 00 00 00 00 00 00 00" // request id
+        ));
+        let err = Connection::transfer(
+            &mut stream,
+            OpCode::CacheGetNames,
+            CacheGetNamesReq {},
+            DFLT_MAX_MESSAGE_SIZE - 1,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // the frame is shorter than its length
+        let mut stream = MockStream::new(&hex_literal::hex!("00 00 00 10 00 00"));
+        assert!(send_safe(&mut stream).is_err());
+    }
+
+    #[test]
+    fn test_frame_is_consumed() {
+        let mut stream = MockStream::new(&hex_literal::hex!(
+            "0F 00 00 00" // length
+            "00 00 00 00 00 00 00 00" // request id
+            "00 00" // flags: success
+            "FF FF FF FF 00" // garbage
+            "16 00 00 00" // length
+            "00 00 00 00 00 00 00 00" // request id
+            "02 00" // flags: topology changed
+            "05 00 00 00 00 00 00 00 00 00 00 00" // topology version
+        ));
+        let mut frame = send_safe(&mut stream).unwrap();
+        assert!(String::read(&mut frame).is_err());
+        let frame = send_safe(&mut stream).unwrap();
+        assert_eq!(frame.position(), frame.get_ref().len() as u64);
+    }
+
+    #[test]
+    fn test_notification_frame() {
+        let resp = hex_literal::hex!(
+            "07 00 00 00 00 00 00 00" // request id
+            "01 00" // flags: error
+            "EA 03 00 00" // status: cache exists
+        );
+        assert!(peek_header(&resp, true).unwrap().notification.is_none());
+        assert!(read_notification(resp.to_vec()).is_err());
+
+        // the bytes after the id of a response are not mistaken for the notification flag
+        let resp = hex_literal::hex!(
+            "07 00 00 00 00 00 00 00" // request id
+            "01 00" // flags: error
+            "FC FF FF FF" // status: -4
+            "09 00 00 00 00" // message
+        );
+        assert!(peek_header(&resp, true).unwrap().notification.is_none());
+        match Connection::read_resp(resp.to_vec(), true) {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::Unknown(-4)),
+            _ => panic!("response error is not reported"),
+        }
+
+        let notification = hex_literal::hex!(
+            "07 00 00 00 00 00 00 00" // resource id
+            "06 00" // flags: notification, topology changed
+            "03 00 00 00 00 00 00 00 01 00 00 00" // topology version
+            "D7 07" // op code
+            "00 00 00 00" // event count
+        );
+        let header = peek_header(&notification, true).unwrap();
+        assert_eq!(header.id, 7);
+        assert_eq!(
+            header.notification,
+            Some(OpCode::QueryContinuousEventNotification as i16)
+        );
+        let mut body = read_notification(notification.to_vec()).unwrap();
+        assert_eq!(read_i32(&mut body).unwrap(), 0);
+
+        let failure = hex_literal::hex!(
+            "07 00 00 00 00 00 00 00" // resource id
+            "05 00" // flags: notification, error
+            "D7 07" // op code
+            "01 00 00 00" // status: failed
+            "09 04 00 00 00 66 61 69 6C" // "fail"
+        );
+        match read_notification(failure.to_vec()) {
+            Err(Error::Server { code, message }) => {
+                assert_eq!(code, StatusCode::Failed);
+                assert_eq!(message, "fail");
+            }
+            _ => panic!("notification error is not reported"),
+        }
+    }
+
+    #[test]
+    fn test_broken_connection() {
+        let server = FakeServer::start().unwrap();
+        let conn = Connection::new(&server.client_config()).unwrap();
+        conn.send(OpCode::CacheGetNames, CacheGetNamesReq {})
+            .unwrap();
+
+        // the request fails on the closed socket, the ones after it don't touch the socket
+        conn.close();
+        assert!(conn
+            .send(OpCode::CacheGetNames, CacheGetNamesReq {})
+            .is_err());
+        for _ in 0..2 {
+            let err = conn
+                .send(OpCode::CacheGetNames, CacheGetNamesReq {})
+                .unwrap_err();
+            assert!(err.to_string().contains("broken"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_handshake_error() {
+        let mut stream = MockStream::new(&hex_literal::hex!(
+            "14 00 00 00" // length
+            "00" // failure
+            "01 00 02 00 00 00" // server version
+            "09 04 00 00 00 61 75 74 68" // "auth"
+            "D0 07 00 00" // status: authentication failed
+        ));
+        let err =
+            handshake(&mut stream, &ClientConfig::new("localhost:10800"), VERSION).unwrap_err();
+        match err {
+            Error::Handshake {
+                server_version,
+                code,
+                message,
+            } => {
+                assert_eq!(server_version, (1, 2, 0));
+                assert_eq!(code, StatusCode::AuthFailed);
+                assert_eq!(message, "auth");
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
     #[ignore]
     #[test]
     fn test_read() {
@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::api::continuous_query::{read_events, QueryContinuousReq, QueryContinuousResp};
+use crate::api::OpCode;
+use crate::connection::{read_notification, Connection};
+use crate::error::{Error, Result};
+use crate::ReadableType;
+
+/// Kind of the cache entry change
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CacheEntryEventType {
+    Created = 0,
+    Updated = 1,
+    Removed = 2,
+    /// Sent only to the queries including the expired entries
+    Expired = 3,
+}
+
+impl From<CacheEntryEventType> for u8 {
+    fn from(event_type: CacheEntryEventType) -> u8 {
+        event_type as u8
+    }
+}
+
+impl TryFrom<u8> for CacheEntryEventType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CacheEntryEventType::Created),
+            1 => Ok(CacheEntryEventType::Updated),
+            2 => Ok(CacheEntryEventType::Removed),
+            3 => Ok(CacheEntryEventType::Expired),
+            _ => Err(Error::from("Cannot read CacheEntryEventType")),
+        }
+    }
+}
+
+/// Change of a cache entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEntryEvent<K, V> {
+    pub key: Option<K>,
+    /// Value before the change, none for the created entries
+    pub old_value: Option<V>,
+    /// Value after the change, none for the removed and expired entries
+    pub value: Option<V>,
+    pub event_type: CacheEntryEventType,
+}
+
+/// Subscription to the changes of a cache, created by `Cache::query_continuous`.
+///
+/// The server pushes the events over a connection of their own, a background thread
+/// reads them into a channel and the `recv*` methods take them out one by one.
+/// The query is stopped and its connection is closed when this is dropped
+pub struct ContinuousQuery<K: ReadableType, V: ReadableType> {
+    conn: Arc<Connection>,
+    notifications: Receiver<Vec<u8>>,
+    pending: VecDeque<CacheEntryEvent<K, V>>,
+    reader: Option<JoinHandle<Result<()>>>,
+}
+
+impl<K: ReadableType, V: ReadableType> ContinuousQuery<K, V> {
+    /// Opens a connection dedicated to the query and starts reading the events
+    pub(crate) fn start(conn: &Connection, req: QueryContinuousReq) -> Result<Self> {
+        let conn = Arc::new(conn.open_dedicated()?);
+        let resp: QueryContinuousResp = conn.send_and_read(OpCode::QueryContinuous, req)?;
+        let notifications = conn.add_listener(resp.cursor_id)?;
+        let reader_conn = conn.clone();
+        let reader = thread::spawn(move || reader_conn.serve_notifications());
+        Ok(ContinuousQuery {
+            conn,
+            notifications,
+            pending: VecDeque::new(),
+            reader: Some(reader),
+        })
+    }
+
+    /// Blocks until the next event is received.
+    /// Fails if the connection of the query is lost
+    pub fn recv(&mut self) -> Result<CacheEntryEvent<K, V>> {
+        let event = self.next_event(|notifications| match notifications.recv() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(_) => Err(query_closed()),
+        })?;
+        event.ok_or_else(query_closed)
+    }
+
+    /// Returns the next event if it is already received
+    pub fn try_recv(&mut self) -> Result<Option<CacheEntryEvent<K, V>>> {
+        self.next_event(|notifications| match notifications.try_recv() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(query_closed()),
+        })
+    }
+
+    /// Waits for the next event up to the timeout. Returns none if no event was received
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<CacheEntryEvent<K, V>>> {
+        let deadline = Instant::now() + timeout;
+        self.next_event(|notifications| {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match notifications.recv_timeout(timeout) {
+                Ok(frame) => Ok(Some(frame)),
+                Err(RecvTimeoutError::Timeout) => Ok(None),
+                Err(RecvTimeoutError::Disconnected) => Err(query_closed()),
+            }
+        })
+    }
+
+    /// Returns a pending event or decodes the next notification taken by `take`.
+    /// A notification may carry no events, so the notifications are taken until there is one
+    fn next_event(
+        &mut self,
+        take: impl Fn(&Receiver<Vec<u8>>) -> Result<Option<Vec<u8>>>,
+    ) -> Result<Option<CacheEntryEvent<K, V>>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            match take(&self.notifications)? {
+                Some(frame) => self
+                    .pending
+                    .extend(read_events(&mut read_notification(frame)?)?),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl<K: ReadableType, V: ReadableType> Drop for ContinuousQuery<K, V> {
+    fn drop(&mut self) {
+        // the server stops the query when its connection is closed
+        self.conn.close();
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+fn query_closed() -> Error {
+    Error::from("Continuous query connection is closed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_server::FakeServer;
+    use crate::Ignite;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn event(
+        event_type: CacheEntryEventType,
+        key: i32,
+        old_value: Option<&str>,
+        value: Option<&str>,
+    ) -> CacheEntryEvent<i32, String> {
+        CacheEntryEvent {
+            key: Some(key),
+            old_value: old_value.map(String::from),
+            value: value.map(String::from),
+            event_type,
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, String>("cache").unwrap();
+        let other = client.get_or_create_cache::<i32, String>("other").unwrap();
+        let mut query = cache.query_continuous(1, Duration::ZERO, false).unwrap();
+        assert_eq!(query.try_recv().unwrap(), None);
+
+        cache.put(&1, &"one".to_string()).unwrap();
+        other.put(&1, &"ignored".to_string()).unwrap();
+        cache.put(&1, &"uno".to_string()).unwrap();
+        cache.remove_key(&1).unwrap();
+        assert_eq!(
+            query.recv().unwrap(),
+            event(CacheEntryEventType::Created, 1, None, Some("one"))
+        );
+        assert_eq!(
+            query.recv_timeout(TIMEOUT).unwrap(),
+            Some(event(
+                CacheEntryEventType::Updated,
+                1,
+                Some("one"),
+                Some("uno")
+            ))
+        );
+        assert_eq!(
+            query.recv_timeout(TIMEOUT).unwrap(),
+            Some(event(CacheEntryEventType::Removed, 1, Some("uno"), None))
+        );
+        assert_eq!(query.recv_timeout(Duration::from_millis(50)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_batches() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, String>("cache").unwrap();
+        let mut query = cache.query_continuous(2, Duration::ZERO, true).unwrap();
+
+        let pairs: Vec<(i32, String)> = (0..3).map(|i| (i, i.to_string())).collect();
+        cache.put_all(&pairs).unwrap();
+        cache.clear().unwrap();
+        let mut events = Vec::new();
+        for _ in 0..6 {
+            events.push(query.recv_timeout(TIMEOUT).unwrap().unwrap());
+        }
+        let created: Vec<_> = events[..3]
+            .iter()
+            .map(|e| (e.event_type, e.key, e.value.clone()))
+            .collect();
+        assert_eq!(
+            created,
+            (0..3)
+                .map(|i| (CacheEntryEventType::Created, Some(i), Some(i.to_string())))
+                .collect::<Vec<_>>()
+        );
+        assert!(events[3..]
+            .iter()
+            .all(|e| e.event_type == CacheEntryEventType::Removed && e.value.is_none()));
+    }
+
+    #[test]
+    fn test_drop() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, String>("cache").unwrap();
+        let destroyed = client.get_or_create_cache::<i32, String>("other").unwrap();
+        client.destroy_cache("other").unwrap();
+        assert!(destroyed
+            .query_continuous(1, Duration::ZERO, false)
+            .is_err());
+        let first = cache.query_continuous(1, Duration::ZERO, false).unwrap();
+        let mut second = cache.query_continuous(1, Duration::ZERO, false).unwrap();
+        drop(first);
+
+        // the closed query does not affect the cache connection or the other query
+        cache.put(&1, &"one".to_string()).unwrap();
+        assert_eq!(cache.get(&1).unwrap(), Some("one".to_string()));
+        assert_eq!(
+            second.recv_timeout(TIMEOUT).unwrap(),
+            Some(event(CacheEntryEventType::Created, 1, None, Some("one")))
+        );
+    }
+}
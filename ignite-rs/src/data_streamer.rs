@@ -0,0 +1,419 @@
+use std::io;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+
+use crate::api::data_streamer::{
+    DataStreamerAddDataReq, DataStreamerStartReq, DataStreamerStartResp, StreamerEntries,
+    FLAG_ALLOW_OVERWRITE, FLAG_CLOSE, FLAG_FLUSH, FLAG_SKIP_STORE,
+};
+use crate::api::OpCode;
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::protocol::write_null;
+use crate::WritableType;
+
+/// Settings of a data streamer
+#[derive(Clone, Debug)]
+pub struct DataStreamerConfiguration {
+    /// Existing entries are replaced and removals are applied.
+    /// Otherwise the entries whose keys are present are skipped, which is faster
+    pub allow_overwrite: bool,
+    /// The cache store is not updated
+    pub skip_store: bool,
+    /// Number of entries sent in one request
+    pub batch_size: usize,
+    /// Number of batches waiting to be sent. Adding an entry blocks while the queue is full
+    pub max_pending_batches: usize,
+    /// Size of the server node buffers, the node sends the entries to their primary nodes
+    /// in batches of it
+    pub per_node_buffer_size: i32,
+    /// Size of the server thread buffer the entries are collected into
+    pub per_thread_buffer_size: i32,
+}
+
+impl Default for DataStreamerConfiguration {
+    fn default() -> Self {
+        DataStreamerConfiguration {
+            allow_overwrite: false,
+            skip_store: false,
+            batch_size: 512,
+            max_pending_batches: 4,
+            // the buffer sizes are the server defaults
+            per_node_buffer_size: 512,
+            per_thread_buffer_size: 4096,
+        }
+    }
+}
+
+/// Loads large amounts of entries into a cache, created by `Cache::data_streamer`.
+///
+/// The entries are buffered and sent in batches by a background thread, without waiting
+/// for each batch to be stored. The client is connected to a single node, so all the batches
+/// go to it and the node passes the entries on to the nodes owning their partitions.
+///
+/// Entries are not guaranteed to be in the cache until `flush` or `close` returns.
+/// The first error of the background thread is returned by the next call and again
+/// by `close`. Dropping the streamer closes it and ignores the errors,
+/// `close` should be called to check them
+pub struct DataStreamer<K: WritableType, V: WritableType> {
+    flags: u8,
+    batch_size: usize,
+    entries: StreamerEntries,
+    /// Whether a batch was sent, so the server has the streamer open
+    started: bool,
+    batches: Option<SyncSender<Batch>>,
+    sender: Option<JoinHandle<Result<()>>>,
+    /// Error the background thread failed with, kept for `close`
+    error: Option<Error>,
+    k_phantom: PhantomData<K>,
+    v_phantom: PhantomData<V>,
+}
+
+/// Entries sent in one request. The acknowledgement is sent when the request succeeds
+struct Batch {
+    entries: StreamerEntries,
+    flags: u8,
+    ack: Option<SyncSender<()>>,
+}
+
+impl<K: WritableType, V: WritableType> DataStreamer<K, V> {
+    pub(crate) fn new(
+        conn: Arc<Connection>,
+        cache_id: i32,
+        config: &DataStreamerConfiguration,
+    ) -> DataStreamer<K, V> {
+        let mut flags = 0;
+        if config.allow_overwrite {
+            flags |= FLAG_ALLOW_OVERWRITE;
+        }
+        if config.skip_store {
+            flags |= FLAG_SKIP_STORE;
+        }
+        let (batches, received) = mpsc::sync_channel(config.max_pending_batches);
+        let (per_node_buffer_size, per_thread_buffer_size) =
+            (config.per_node_buffer_size, config.per_thread_buffer_size);
+        let sender = thread::spawn(move || {
+            send_batches(
+                &conn,
+                cache_id,
+                per_node_buffer_size,
+                per_thread_buffer_size,
+                received,
+            )
+        });
+        DataStreamer {
+            flags,
+            batch_size: config.batch_size.max(1),
+            entries: new_entries(),
+            started: false,
+            batches: Some(batches),
+            sender: Some(sender),
+            error: None,
+            k_phantom: PhantomData,
+            v_phantom: PhantomData,
+        }
+    }
+
+    /// Adds the entry. The batch is sent when it is full
+    pub fn add(&mut self, key: &K, value: &V) -> Result<()> {
+        self.add_entry(|bytes| {
+            key.write(bytes)?;
+            value.write(bytes)
+        })
+    }
+
+    /// Adds all the pairs
+    pub fn add_all(&mut self, pairs: &[(K, V)]) -> Result<()> {
+        for (key, value) in pairs {
+            self.add(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the key. Applied only if overwriting is allowed
+    pub fn remove(&mut self, key: &K) -> Result<()> {
+        self.add_entry(|bytes| {
+            key.write(bytes)?;
+            write_null(bytes)
+        })
+    }
+
+    /// Sends the buffered entries and waits until all the entries added so far are stored
+    pub fn flush(&mut self) -> Result<()> {
+        if !self.started && self.entries.count == 0 {
+            return Ok(());
+        }
+        let (ack, acked) = mpsc::sync_channel(1);
+        self.send(FLAG_FLUSH, Some(ack))?;
+        match acked.recv() {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self.sender_error()),
+        }
+    }
+
+    /// Sends the buffered entries, waits until they are stored and closes the streamer
+    pub fn close(mut self) -> Result<()> {
+        self.finish()
+    }
+
+    /// Writes the entry into the batch. A partly written entry is removed,
+    /// so a failed write doesn't shift the entries after it
+    fn add_entry(&mut self, write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Result<()> {
+        let len = self.entries.bytes.len();
+        if let Err(err) = write(&mut self.entries.bytes) {
+            self.entries.bytes.truncate(len);
+            return Err(Error::from(err));
+        }
+        self.entries.count += 1;
+        if self.entries.count as usize >= self.batch_size {
+            self.send(0, None)?;
+        }
+        Ok(())
+    }
+
+    /// Passes the buffered entries to the background thread
+    fn send(&mut self, flags: u8, ack: Option<SyncSender<()>>) -> Result<()> {
+        let batch = Batch {
+            entries: mem::replace(&mut self.entries, new_entries()),
+            flags: self.flags | flags,
+            ack,
+        };
+        let sent = match &self.batches {
+            Some(batches) => batches.send(batch).is_ok(),
+            None => false,
+        };
+        if !sent {
+            return Err(self.sender_error());
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    /// Stops the background thread and returns a copy of the error it failed with
+    fn sender_error(&mut self) -> Error {
+        self.batches = None;
+        if let Some(sender) = self.sender.take() {
+            self.error = match sender.join() {
+                Ok(Err(err)) => Some(err),
+                Ok(Ok(())) => None,
+                Err(_) => Some(Error::from("Data streamer thread panicked")),
+            };
+        }
+        match &self.error {
+            Some(Error::Server { code, message }) => Error::Server {
+                code: *code,
+                message: message.clone(),
+            },
+            Some(err) => Error::from(err.to_string().as_str()),
+            None => Error::from("Data streamer is closed"),
+        }
+    }
+
+    /// Sends the last batch closing the streamer and waits for the background thread.
+    /// Returns the kept error if the thread has already failed
+    fn finish(&mut self) -> Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        if self.batches.is_none() {
+            return Ok(());
+        }
+        if self.started || self.entries.count > 0 {
+            self.send(FLAG_CLOSE, None)?;
+        }
+        // the thread stops when the channel is closed
+        self.batches = None;
+        match self.sender.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(Error::from("Data streamer thread panicked")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<K: WritableType, V: WritableType> Drop for DataStreamer<K, V> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+fn new_entries() -> StreamerEntries {
+    StreamerEntries {
+        count: 0,
+        bytes: Vec::new(),
+    }
+}
+
+/// Sends the batches until the channel is closed. The first batch starts the streamer
+fn send_batches(
+    conn: &Connection,
+    cache_id: i32,
+    per_node_buffer_size: i32,
+    per_thread_buffer_size: i32,
+    batches: Receiver<Batch>,
+) -> Result<()> {
+    let mut streamer_id = None;
+    for batch in batches {
+        match streamer_id {
+            None => {
+                let resp: DataStreamerStartResp = conn.send_and_read(
+                    OpCode::DataStreamerStart,
+                    DataStreamerStartReq {
+                        cache_id,
+                        flags: batch.flags,
+                        per_node_buffer_size,
+                        per_thread_buffer_size,
+                        entries: &batch.entries,
+                    },
+                )?;
+                streamer_id = Some(resp.streamer_id);
+            }
+            Some(streamer_id) => conn.send(
+                OpCode::DataStreamerAddData,
+                DataStreamerAddDataReq {
+                    streamer_id,
+                    flags: batch.flags,
+                    entries: &batch.entries,
+                },
+            )?,
+        }
+        if let Some(ack) = batch.ack {
+            let _ = ack.send(());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StatusCode;
+    use crate::fake_server::FakeServer;
+    use crate::protocol::complex_obj::{
+        ComplexObject, ComplexObjectSchema, IgniteField, IgniteType, IgniteValue,
+    };
+    use crate::Ignite;
+
+    #[test]
+    fn test_stream() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, String>("cache").unwrap();
+        let config = DataStreamerConfiguration {
+            batch_size: 10,
+            max_pending_batches: 1,
+            ..DataStreamerConfiguration::default()
+        };
+        let mut streamer = cache.data_streamer(&config);
+        streamer.flush().unwrap();
+        for i in 0..25 {
+            streamer.add(&i, &i.to_string()).unwrap();
+        }
+        streamer.flush().unwrap();
+        assert_eq!(cache.get_size().unwrap(), 25);
+        streamer
+            .add_all(&[(25, "25".to_string()), (26, "26".to_string())])
+            .unwrap();
+        streamer.close().unwrap();
+        assert_eq!(cache.get_size().unwrap(), 27);
+        assert_eq!(cache.get(&26).unwrap(), Some("26".to_string()));
+    }
+
+    #[test]
+    fn test_overwrite() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, String>("cache").unwrap();
+        cache.put(&1, &"old".to_string()).unwrap();
+
+        let mut streamer = cache.data_streamer(&DataStreamerConfiguration::default());
+        streamer.add(&1, &"new".to_string()).unwrap();
+        streamer.add(&2, &"two".to_string()).unwrap();
+        streamer.remove(&2).unwrap();
+        streamer.close().unwrap();
+        assert_eq!(cache.get(&1).unwrap(), Some("old".to_string()));
+        assert_eq!(cache.get(&2).unwrap(), Some("two".to_string()));
+
+        let config = DataStreamerConfiguration {
+            allow_overwrite: true,
+            skip_store: true,
+            ..DataStreamerConfiguration::default()
+        };
+        let mut streamer = cache.data_streamer(&config);
+        streamer.add(&1, &"new".to_string()).unwrap();
+        streamer.remove(&2).unwrap();
+        drop(streamer);
+        assert_eq!(cache.get(&1).unwrap(), Some("new".to_string()));
+        assert_eq!(cache.get(&2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_value_write_failure() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client
+            .get_or_create_cache::<i32, ComplexObject>("cache")
+            .unwrap();
+        let schema = Arc::new(ComplexObjectSchema {
+            type_name: "Point".to_string(),
+            fields: vec![IgniteField {
+                name: "X".to_string(),
+                r#type: IgniteType::Int,
+            }],
+        });
+        let point = |values| ComplexObject {
+            schema: schema.clone(),
+            values,
+        };
+
+        let mut streamer = cache.data_streamer(&DataStreamerConfiguration::default());
+        streamer.add(&1, &point(vec![IgniteValue::Int(1)])).unwrap();
+        // the value has fewer values than the schema fields
+        assert!(streamer.add(&2, &point(vec![])).is_err());
+        streamer.add(&3, &point(vec![IgniteValue::Int(3)])).unwrap();
+        streamer.close().unwrap();
+
+        assert_eq!(cache.get_size().unwrap(), 2);
+        assert_eq!(cache.get(&2).unwrap(), None);
+        assert_eq!(
+            cache.get(&3).unwrap().unwrap().values,
+            vec![IgniteValue::Int(3)]
+        );
+    }
+
+    #[test]
+    fn test_failure() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, String>("cache").unwrap();
+        client.destroy_cache("cache").unwrap();
+
+        let config = DataStreamerConfiguration {
+            batch_size: 1,
+            ..DataStreamerConfiguration::default()
+        };
+        let mut streamer = cache.data_streamer(&config);
+        streamer.add(&1, &"one".to_string()).unwrap();
+        match streamer.flush() {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::CacheDoesNotExist),
+            _ => panic!("streamer failure is not reported"),
+        }
+        assert!(streamer.add(&2, &"two".to_string()).is_err());
+        match streamer.close() {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::CacheDoesNotExist),
+            _ => panic!("streamer failure is not kept"),
+        }
+
+        // the failure of the last batch
+        let mut streamer = cache.data_streamer(&config);
+        streamer.add(&1, &"one".to_string()).unwrap();
+        assert!(streamer.close().is_err());
+        // nothing was sent
+        assert!(cache.data_streamer(&config).close().is_ok());
+    }
+}
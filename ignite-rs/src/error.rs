@@ -1,8 +1,6 @@
-use bufstream::BufStream;
 use snafu::Snafu;
 use std::convert;
 use std::io::Error as IoError;
-use std::net::TcpStream;
 use std::sync::{MutexGuard, PoisonError};
 #[cfg(feature = "ssl")]
 use webpki::InvalidDNSNameError;
@@ -15,6 +13,36 @@ pub enum Error {
     #[snafu(display("{}", desc))]
     Custom { desc: String },
 
+    /// Request failed on the server side
+    #[snafu(display("Server error {:?}: {}", code, message))]
+    Server { code: StatusCode, message: String },
+
+    /// Server rejected the handshake
+    #[snafu(display(
+        "Handshake error: v{}.{}.{} {:?}: {}",
+        server_version.0,
+        server_version.1,
+        server_version.2,
+        code,
+        message
+    ))]
+    Handshake {
+        /// Protocol version supported by the server.
+        server_version: (i16, i16, i16),
+        code: StatusCode,
+        message: String,
+    },
+
+    /// Malformed or unsupported data object in a complex object or in the inspected bytes.
+    /// The offset is counted from the start of the object or the input. Other reads
+    /// report `Custom` errors
+    #[snafu(display("Cannot decode type code {} at offset {}: {}", type_code, offset, desc))]
+    Decode {
+        type_code: u8,
+        offset: usize,
+        desc: String,
+    },
+
     /// IO Error
     #[snafu(display("IO Error: {}", source))]
     IoError { source: IoError },
@@ -29,6 +57,69 @@ pub enum Error {
     InvalidDNSName { desc: String },
 }
 
+/// Status codes of the failed responses.
+/// https://ignite.apache.org/docs/latest/binary-client-protocol/binary-client-protocol#error-codes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StatusCode {
+    Failed,
+    InvalidOpCode,
+    InvalidNodeState,
+    FunctionalityDisabled,
+    CacheDoesNotExist,
+    CacheExists,
+    TooManyCursors,
+    ResourceDoesNotExist,
+    SecurityViolation,
+    TxLimitExceeded,
+    TxNotFound,
+    TooManyComputeTasks,
+    AuthFailed,
+    /// Status code not known to this client
+    Unknown(i32),
+}
+
+impl From<i32> for StatusCode {
+    fn from(value: i32) -> Self {
+        match value {
+            1 => StatusCode::Failed,
+            2 => StatusCode::InvalidOpCode,
+            10 => StatusCode::InvalidNodeState,
+            100 => StatusCode::FunctionalityDisabled,
+            1000 => StatusCode::CacheDoesNotExist,
+            1001 => StatusCode::CacheExists,
+            1010 => StatusCode::TooManyCursors,
+            1011 => StatusCode::ResourceDoesNotExist,
+            1012 => StatusCode::SecurityViolation,
+            1020 => StatusCode::TxLimitExceeded,
+            1021 => StatusCode::TxNotFound,
+            1030 => StatusCode::TooManyComputeTasks,
+            2000 => StatusCode::AuthFailed,
+            _ => StatusCode::Unknown(value),
+        }
+    }
+}
+
+impl From<StatusCode> for i32 {
+    fn from(code: StatusCode) -> Self {
+        match code {
+            StatusCode::Failed => 1,
+            StatusCode::InvalidOpCode => 2,
+            StatusCode::InvalidNodeState => 10,
+            StatusCode::FunctionalityDisabled => 100,
+            StatusCode::CacheDoesNotExist => 1000,
+            StatusCode::CacheExists => 1001,
+            StatusCode::TooManyCursors => 1010,
+            StatusCode::ResourceDoesNotExist => 1011,
+            StatusCode::SecurityViolation => 1012,
+            StatusCode::TxLimitExceeded => 1020,
+            StatusCode::TxNotFound => 1021,
+            StatusCode::TooManyComputeTasks => 1030,
+            StatusCode::AuthFailed => 2000,
+            StatusCode::Unknown(value) => value,
+        }
+    }
+}
+
 impl convert::From<IoError> for Error {
     fn from(err: IoError) -> Self {
         Error::IoError { source: err }
@@ -43,8 +134,8 @@ impl convert::From<&str> for Error {
     }
 }
 
-impl From<PoisonError<MutexGuard<'_, BufStream<TcpStream>>>> for Error {
-    fn from(err: PoisonError<MutexGuard<'_, BufStream<TcpStream>>>) -> Self {
+impl<T> From<PoisonError<MutexGuard<'_, T>>> for Error {
+    fn from(err: PoisonError<MutexGuard<'_, T>>) -> Self {
         Error::MutexPoisoned {
             desc: err.to_string(),
         }
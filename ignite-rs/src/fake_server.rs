@@ -0,0 +1,1900 @@
+//! In-process stand-in for an Ignite node.
+//!
+//! Speaks the thin client protocol over TCP and keeps the caches in memory, so the client
+//! can be tested without a running cluster. Only the operations this client sends are
+//! supported: the handshake, cache configuration, key-value, scan and continuous query,
+//! data streamer, transaction, cluster state, cluster group, compute, service, atomic long
+//! and set ops.
+//! SQL queries are rejected. Streamed entries are stored as soon as they are received.
+//! Continuous queries are notified right after each change, in batches of their page size.
+//! Entries are expired when their cache is used next after their time to live has passed.
+//! Compute tasks and services are Rust closures deployed with `FakeServer::add_task`
+//! and `FakeServer::add_service`.
+//!
+//! The server pretends to be the only node of the cluster, more nodes can be added with
+//! `FakeServer::add_node`. `FakeServer::start_with_version` starts a node of an older
+//! protocol version, which rejects the newer handshakes and the operations it lacks.
+//!
+//! Keys and values are stored in their serialized form and are compared byte by byte.
+//! Transactions are not isolated: a rollback restores all the caches to the state they
+//! had when the transaction started.
+//!
+//! Available in tests and with the "fake-server" feature.
+//!
+//! ```no_run
+//! use ignite_rs::fake_server::FakeServer;
+//! use ignite_rs::Ignite;
+//!
+//! let server = FakeServer::start().unwrap();
+//! let mut client = server.connect().unwrap();
+//! let cache = client.get_or_create_cache::<i32, String>("cache").unwrap();
+//! cache.put(&1, &"one".to_string()).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+use std::io::{BufReader, BufWriter, Cursor, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Instant;
+
+use crate::api::binary_type::write_binary_type;
+use crate::api::cluster::{read_node_filters, write_cluster_node};
+use crate::api::data_streamer::{FLAG_ALLOW_OVERWRITE, FLAG_CLOSE};
+use crate::api::key_value::FLAG_WITH_EXPIRY_POLICY;
+use crate::api::service::write_descriptor;
+use crate::api::OpCode;
+use crate::cache::{CacheConfiguration, ExpiryDuration, ExpiryPolicy};
+use crate::cluster::{ClusterNode, ClusterState, NodeVersion};
+use crate::continuous_query::CacheEntryEventType;
+use crate::error::{Error, Result, StatusCode};
+use crate::handshake::{
+    feature_mask, ProtocolContext, CLIENT_CODE, CLIENT_FEATURES, VERSION, V_MAJOR,
+};
+use crate::protocol::binary_object::BinaryType;
+use crate::protocol::cache_config::{
+    read_cache_configuration_bytes, read_expiry_policy, write_cache_configuration,
+};
+use crate::protocol::complex_obj::{read_value, write_value, IgniteValue};
+use crate::protocol::{
+    read_bool, read_bytes, read_i16, read_i32, read_i64, read_len, read_u8, read_uuid, write_bool,
+    write_i16, write_i32, write_i64, write_null, write_string_type_code, write_u8, write_uuid,
+    TypeCode, RESPONSE_FLAG_ERROR, RESPONSE_FLAG_NOTIFICATION,
+};
+use crate::service::ServiceDescriptor;
+use crate::utils::string_to_java_hashcode;
+#[cfg(not(feature = "ssl"))]
+use crate::{new_client, Client, ClientConfig};
+use crate::{ReadableType, Uuid, WritableType};
+
+// https://ignite.apache.org/docs/latest/binary-client-protocol/key-value-queries#op_cache_get
+const FLAG_TRANSACTIONAL: u8 = 0x02;
+// https://ignite.apache.org/docs/latest/binary-client-protocol/service-api#op_service_invoke
+const FLAG_HAS_PARAMETER_TYPES: u8 = 0x02;
+
+/// Ignite node stand-in listening on a random local port.
+/// Stops accepting connections when dropped
+pub struct FakeServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl FakeServer {
+    /// Starts a server without any caches
+    pub fn start() -> io::Result<FakeServer> {
+        FakeServer::start_with_version(VERSION)
+    }
+
+    /// Starts a server supporting the protocol versions up to the given one, like an older node.
+    /// The features are negotiated since 1.7.0
+    pub fn start_with_version(version: (i16, i16, i16)) -> io::Result<FakeServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let mut state = State {
+            port: addr.port().into(),
+            version,
+            ..State::default()
+        };
+        state.nodes.push(local_node(addr));
+        let state = Arc::new(Mutex::new(state));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let accept_state = state.clone();
+        let accept_stopped = stopped.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let state = accept_state.clone();
+                    thread::spawn(move || serve(stream, &state));
+                }
+            }
+        });
+
+        Ok(FakeServer {
+            addr,
+            state,
+            stopped,
+        })
+    }
+
+    /// Address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Client configuration pointing to this server
+    #[cfg(not(feature = "ssl"))]
+    pub fn client_config(&self) -> ClientConfig {
+        ClientConfig::new(self.addr.to_string().as_str())
+    }
+
+    /// Client connected to this server with the default configuration
+    #[cfg(not(feature = "ssl"))]
+    pub fn connect(&self) -> Result<Client> {
+        new_client(self.client_config())
+    }
+
+    /// Creates a cache as if it was configured on the node.
+    /// Replaces the existing cache with the same name
+    pub fn add_cache(&self, config: CacheConfiguration) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.caches.insert(
+            string_to_java_hashcode(config.name.as_str()),
+            FakeCache::new(config),
+        );
+    }
+
+    /// Deploys a compute task executed by `Compute::execute` with the name.
+    /// An error returned by the task is sent to the client as the task failure
+    pub fn add_task<A, R, F>(&self, name: &str, task: F)
+    where
+        A: ReadableType,
+        R: WritableType,
+        F: Fn(Option<A>) -> Result<R> + Send + 'static,
+    {
+        let task: Task = Box::new(move |req| {
+            let result = task(A::read(req)?)?;
+            let mut body = Vec::with_capacity(result.size());
+            result.write(&mut body)?;
+            Ok(body)
+        });
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.tasks.insert(name.to_string(), task);
+    }
+
+    /// Deploys a service invoked by `Services::invoke`. The service is called with the method
+    /// name and the arguments, an error it returns is sent to the client as the call failure.
+    /// Replaces the deployed service with the same name
+    pub fn add_service<F>(&self, descriptor: ServiceDescriptor, service: F)
+    where
+        F: Fn(&str, Vec<IgniteValue>) -> Result<IgniteValue> + Send + 'static,
+    {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state
+            .services
+            .retain(|(desc, _)| desc.name != descriptor.name);
+        state.services.push((descriptor, Box::new(service)));
+    }
+
+    /// Registers the binary type returned by `Client::get_binary_type`.
+    /// Replaces the type with the same id
+    pub fn add_binary_type(&self, binary_type: BinaryType) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.binary_types.insert(binary_type.type_id, binary_type);
+    }
+
+    /// Adds a node to the cluster topology. The node is listed but does not serve anything
+    pub fn add_node(&self, node: ClusterNode) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.nodes.push(node);
+    }
+}
+
+/// The node this server pretends to be
+fn local_node(addr: SocketAddr) -> ClusterNode {
+    ClusterNode {
+        id: Uuid::from(1),
+        attributes: Vec::new(),
+        addresses: vec![addr.ip().to_string()],
+        host_names: vec!["localhost".to_string()],
+        order: 1,
+        is_local: true,
+        is_daemon: false,
+        is_client: false,
+        consistent_id: IgniteValue::String(addr.to_string()),
+        version: NodeVersion {
+            major: 2,
+            minor: 16,
+            maintenance: 0,
+            stage: "fake".to_string(),
+            revision_timestamp: 0,
+            revision_hash: Vec::new(),
+        },
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wake up the accepting thread
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+struct State {
+    caches: HashMap<i32, FakeCache>,
+    /// Caches as they were when the transaction started
+    transactions: HashMap<i32, HashMap<i32, FakeCache>>,
+    last_tx_id: i32,
+    /// Last id of a cursor or a compute task
+    last_resource_id: i64,
+    cluster_state: ClusterState,
+    nodes: Vec<ClusterNode>,
+    /// Thin client port of all the nodes
+    port: i32,
+    /// Newest protocol version accepted in the handshake
+    version: (i16, i16, i16),
+    tasks: HashMap<String, Task>,
+    services: Vec<(ServiceDescriptor, Service)>,
+    continuous_queries: Vec<FakeContinuousQuery>,
+    /// Cache id and flags of the open data streamers
+    streamers: HashMap<i64, (i32, u8)>,
+    /// Atomic longs by the name and the group name
+    atomic_longs: HashMap<(String, Option<String>), i64>,
+    sets: HashMap<String, FakeSet>,
+    /// Values left to return by the open set iterators
+    set_iterators: HashMap<i64, Vec<Vec<u8>>>,
+    binary_types: HashMap<i32, BinaryType>,
+}
+
+/// Serialized values of a set in the insertion order
+struct FakeSet {
+    cache_id: i32,
+    collocated: bool,
+    values: Vec<Vec<u8>>,
+}
+
+/// Writer of a client connection, shared to push the continuous query events
+type SharedWriter = Arc<Mutex<BufWriter<TcpStream>>>;
+
+/// Continuous query listening on the connection of the writer
+struct FakeContinuousQuery {
+    cache_id: i32,
+    cursor_id: i64,
+    page_size: usize,
+    include_expired: bool,
+    writer: SharedWriter,
+}
+
+/// Compute task reading its argument from the request and returning the serialized result
+type Task = Box<dyn Fn(&mut Cursor<Vec<u8>>) -> Result<Vec<u8>> + Send>;
+
+/// Service called with the method name and the arguments
+type Service = Box<dyn Fn(&str, Vec<IgniteValue>) -> Result<IgniteValue> + Send>;
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            caches: HashMap::new(),
+            transactions: HashMap::new(),
+            last_tx_id: 0,
+            last_resource_id: 0,
+            // the node has no persistence, so it starts active
+            cluster_state: ClusterState::Active,
+            nodes: Vec::new(),
+            port: 0,
+            version: VERSION,
+            tasks: HashMap::new(),
+            services: Vec::new(),
+            continuous_queries: Vec::new(),
+            streamers: HashMap::new(),
+            atomic_longs: HashMap::new(),
+            sets: HashMap::new(),
+            set_iterators: HashMap::new(),
+            binary_types: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct FakeCache {
+    config: CacheConfiguration,
+    /// Serialized key-value pairs in the insertion order
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    wal_enabled: bool,
+    /// Changes not yet sent to the continuous queries
+    events: Vec<FakeEvent>,
+    /// Expiration times of the entries that are not eternal
+    deadlines: HashMap<Vec<u8>, Instant>,
+}
+
+/// Serialized key, old and new value of a changed entry
+#[derive(Clone)]
+struct FakeEvent {
+    key: Vec<u8>,
+    old_value: Option<Vec<u8>>,
+    value: Option<Vec<u8>>,
+    event_type: CacheEntryEventType,
+}
+
+impl FakeCache {
+    fn new(config: CacheConfiguration) -> FakeCache {
+        FakeCache {
+            config,
+            entries: Vec::new(),
+            wal_enabled: true,
+            events: Vec::new(),
+            deadlines: HashMap::new(),
+        }
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&Vec<u8>> {
+        self.entries.iter().find(|e| e.0 == key).map(|e| &e.1)
+    }
+
+    /// Returns the replaced value
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) -> Option<Vec<u8>> {
+        let old = match self.entries.iter_mut().find(|e| e.0 == key) {
+            Some(entry) => Some(std::mem::replace(&mut entry.1, value.clone())),
+            None => {
+                self.entries.push((key.clone(), value.clone()));
+                None
+            }
+        };
+        self.events.push(FakeEvent {
+            key,
+            old_value: old.clone(),
+            value: Some(value),
+            event_type: match old {
+                Some(_) => CacheEntryEventType::Updated,
+                None => CacheEntryEventType::Created,
+            },
+        });
+        old
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.remove_as(key, CacheEntryEventType::Removed)
+    }
+
+    fn remove_as(&mut self, key: &[u8], event_type: CacheEntryEventType) -> Option<Vec<u8>> {
+        let pos = self.entries.iter().position(|e| e.0 == key)?;
+        let old = self.entries.remove(pos).1;
+        self.deadlines.remove(key);
+        self.events.push(FakeEvent {
+            key: key.to_vec(),
+            old_value: Some(old.clone()),
+            value: None,
+            event_type,
+        });
+        Some(old)
+    }
+
+    /// Removes the entries whose time to live has passed
+    fn expire(&mut self, now: Instant) {
+        let expired: Vec<Vec<u8>> = self
+            .deadlines
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            self.remove_as(&key, CacheEntryEventType::Expired);
+        }
+    }
+
+    /// Sets the expiration times of the entries changed by an operation, as recorded
+    /// in the pending events, and of the entries it read.
+    /// The policy of the operation takes precedence over the configured one
+    fn apply_expiry(&mut self, policy: Option<ExpiryPolicy>, read: &[Vec<u8>], now: Instant) {
+        let configured = self.config.expiry_policy;
+        let policy = match policy.or(configured) {
+            Some(policy) => policy,
+            None => return,
+        };
+        let created = match (policy.create, configured) {
+            (ExpiryDuration::Unchanged, Some(configured)) => configured.create,
+            (create, _) => create,
+        };
+        let changes: Vec<(Vec<u8>, ExpiryDuration)> = self
+            .events
+            .iter()
+            .filter_map(|e| match e.event_type {
+                CacheEntryEventType::Created => Some((e.key.clone(), created)),
+                CacheEntryEventType::Updated => Some((e.key.clone(), policy.update)),
+                _ => None,
+            })
+            .chain(read.iter().map(|key| (key.clone(), policy.access)))
+            .collect();
+        for (key, duration) in changes {
+            match duration {
+                ExpiryDuration::Unchanged => {}
+                ExpiryDuration::Eternal => {
+                    self.deadlines.remove(&key);
+                }
+                ExpiryDuration::After(ttl) => match now.checked_add(ttl) {
+                    Some(deadline) if self.get(&key).is_some() => {
+                        self.deadlines.insert(key, deadline);
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        let keys: Vec<Vec<u8>> = self.entries.iter().map(|e| e.0.clone()).collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+}
+
+/// Serves a single client connection until it is closed.
+/// The continuous queries of the connection end with it
+fn serve(stream: TcpStream, state: &Mutex<State>) -> io::Result<()> {
+    let writer = Arc::new(Mutex::new(BufWriter::new(stream.try_clone()?)));
+    let result = serve_requests(stream, &writer, state);
+    let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+    state
+        .continuous_queries
+        .retain(|query| !Arc::ptr_eq(&query.writer, &writer));
+    result
+}
+
+fn serve_requests(
+    stream: TcpStream,
+    writer: &SharedWriter,
+    state: &Mutex<State>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let (node_id, version) = {
+        let state = state.lock().unwrap_or_else(|err| err.into_inner());
+        (state.nodes[0].id, state.version)
+    };
+    let session = match accept_handshake(&mut reader, &mut *lock_writer(writer), node_id, version)?
+    {
+        Some(session) => session,
+        None => return Ok(()),
+    };
+
+    loop {
+        let len = match read_len(&mut reader) {
+            Ok(len) => len,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        let mut frame = Cursor::new(read_bytes(&mut reader, len)?);
+        let op_code = read_i16(&mut frame)?;
+        let req_id = read_i64(&mut frame)?;
+
+        let mut notifications = Vec::new();
+        let result = check_supported(&session, op_code).and_then(|_| {
+            let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+            handle(&mut state, op_code, &mut frame, writer, &mut notifications)
+        });
+
+        // the status is replaced with the flags since 1.4.0
+        let mut resp = Vec::new();
+        write_i64(&mut resp, req_id)?;
+        match result {
+            Ok(payload) => {
+                match session.has_flags() {
+                    true => write_i16(&mut resp, 0)?,
+                    false => write_i32(&mut resp, 0)?,
+                }
+                resp.extend_from_slice(&payload);
+            }
+            Err(err) => {
+                if session.has_flags() {
+                    write_i16(&mut resp, RESPONSE_FLAG_ERROR)?;
+                }
+                let (code, message) = error_status(err);
+                write_i32(&mut resp, code.into())?;
+                write_string_type_code(&mut resp, message.as_str())?;
+            }
+        }
+        // notifications follow the response of the request they are about
+        write_frames(
+            &mut *lock_writer(writer),
+            std::iter::once(resp).chain(notifications),
+        )?;
+    }
+}
+
+fn lock_writer(writer: &SharedWriter) -> MutexGuard<'_, BufWriter<TcpStream>> {
+    writer.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Writes the frames with their length prefixes and flushes them
+fn write_frames(writer: &mut impl Write, frames: impl Iterator<Item = Vec<u8>>) -> io::Result<()> {
+    for frame in frames {
+        write_i32(writer, frame.len() as i32)?;
+        writer.write_all(&frame)?;
+    }
+    writer.flush()
+}
+
+/// Rejects the operations the negotiated version or features lack
+fn check_supported(session: &ProtocolContext, op_code: i16) -> Result<()> {
+    // unknown op codes are rejected later
+    match OpCode::try_from(op_code) {
+        Ok(op_code) => session
+            .check_supported(op_code)
+            .map_err(|err| server_err(StatusCode::InvalidOpCode, err.to_string().as_str())),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Reads a byte array or NULL of the handshake request
+fn read_opt_bytes(req: &mut impl Read) -> io::Result<Vec<u8>> {
+    match TypeCode::try_from(read_u8(req)?) {
+        Ok(TypeCode::Null) => Ok(Vec::new()),
+        Ok(TypeCode::ArrByte) => {
+            let len = read_len(req)?;
+            read_bytes(req, len)
+        }
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Byte array is expected",
+        )),
+    }
+}
+
+/// Reads the handshake request and replies to it.
+/// Versions from 1.0.0 up to the server's one are accepted.
+/// Returns the negotiated session or none if the client is rejected
+fn accept_handshake(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    node_id: Uuid,
+    version: (i16, i16, i16),
+) -> io::Result<Option<ProtocolContext>> {
+    let len = read_len(reader)?;
+    let mut req = Cursor::new(read_bytes(reader, len)?);
+    let op_code = read_u8(&mut req)?;
+    let major = read_i16(&mut req)?;
+    let minor = read_i16(&mut req)?;
+    let patch = read_i16(&mut req)?;
+    let client_code = read_u8(&mut req)?;
+
+    if op_code == OpCode::Handshake as u8
+        && ((V_MAJOR, 0, 0)..=version).contains(&(major, minor, patch))
+        && client_code == CLIENT_CODE
+    {
+        // the user attributes and credentials that follow the features are not checked
+        let server_features = feature_mask(&CLIENT_FEATURES);
+        let mut features = Vec::new();
+        let mut resp = Vec::new();
+        write_u8(&mut resp, 1)?;
+        if minor >= 7 {
+            features = read_opt_bytes(&mut req)?
+                .iter()
+                .zip(server_features.iter())
+                .map(|(client, server)| client & server)
+                .collect();
+            write_u8(&mut resp, TypeCode::ArrByte as u8)?;
+            write_i32(&mut resp, server_features.len() as i32)?;
+            resp.extend_from_slice(&server_features);
+        }
+        if minor >= 4 {
+            write_u8(&mut resp, TypeCode::Uuid as u8)?;
+            write_uuid(&mut resp, node_id)?;
+        }
+        write_i32(writer, resp.len() as i32)?;
+        writer.write_all(&resp)?;
+        writer.flush()?;
+        return Ok(Some(ProtocolContext::new((major, minor, patch), features)));
+    }
+
+    let mut resp = Vec::new();
+    write_u8(&mut resp, 0)?;
+    write_i16(&mut resp, version.0)?;
+    write_i16(&mut resp, version.1)?;
+    write_i16(&mut resp, version.2)?;
+    write_string_type_code(&mut resp, "Unsupported handshake request")?;
+    write_i32(&mut resp, StatusCode::Failed.into())?;
+    write_i32(writer, resp.len() as i32)?;
+    writer.write_all(&resp)?;
+    writer.flush()?;
+    Ok(None)
+}
+
+/// Status code and message sent to the client for the error
+fn error_status(err: Error) -> (StatusCode, String) {
+    match err {
+        Error::Server { code, message } => (code, message),
+        err => (StatusCode::Failed, err.to_string()),
+    }
+}
+
+/// Builds a notification frame about the resource, the length prefix is not included
+fn notification(resource_id: i64, op_code: OpCode, result: Result<Vec<u8>>) -> io::Result<Vec<u8>> {
+    let mut frame = Vec::new();
+    write_i64(&mut frame, resource_id)?;
+    match result {
+        Ok(body) => {
+            write_i16(&mut frame, RESPONSE_FLAG_NOTIFICATION)?;
+            write_i16(&mut frame, op_code as i16)?;
+            frame.extend_from_slice(&body);
+        }
+        Err(err) => {
+            write_i16(&mut frame, RESPONSE_FLAG_NOTIFICATION | RESPONSE_FLAG_ERROR)?;
+            write_i16(&mut frame, op_code as i16)?;
+            let (code, message) = error_status(err);
+            write_i32(&mut frame, code.into())?;
+            write_string_type_code(&mut frame, message.as_str())?;
+        }
+    }
+    Ok(frame)
+}
+
+fn server_err(code: StatusCode, message: &str) -> Error {
+    Error::Server {
+        code,
+        message: message.to_string(),
+    }
+}
+
+/// Executes a request received by the writer's connection and returns the response payload.
+/// Notifications to send after the response are added to `notifications`
+fn handle(
+    state: &mut State,
+    op_code: i16,
+    req: &mut Cursor<Vec<u8>>,
+    writer: &SharedWriter,
+    notifications: &mut Vec<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    let op_code = OpCode::try_from(op_code)
+        .map_err(|err| server_err(StatusCode::InvalidOpCode, err.to_string().as_str()))?;
+    check_cluster_state(state.cluster_state, op_code)?;
+    let mut resp = Vec::new();
+    match op_code {
+        OpCode::Handshake => return Err(server_err(StatusCode::Failed, "Already connected")),
+        OpCode::CacheGetNames => {
+            write_i32(&mut resp, state.caches.len() as i32)?;
+            for cache in state.caches.values() {
+                write_string_type_code(&mut resp, cache.config.name.as_str())?;
+            }
+        }
+        OpCode::CacheCreateWithName | OpCode::CacheGetOrCreateWithName => {
+            let name = String::read(req)?.ok_or_else(|| Error::from("Cache name is NULL"))?;
+            create_cache(
+                state,
+                CacheConfiguration::new(name.as_str()),
+                op_code == OpCode::CacheCreateWithName,
+            )?;
+        }
+        OpCode::CacheCreateWithConfiguration | OpCode::CacheGetOrCreateWithConfiguration => {
+            let config = read_cache_configuration_bytes(req)?;
+            create_cache(
+                state,
+                config,
+                op_code == OpCode::CacheCreateWithConfiguration,
+            )?;
+        }
+        OpCode::CacheGetConfiguration => {
+            let cache = get_cache(state, read_i32(req)?)?;
+            let mut config = Vec::new();
+            write_cache_configuration(&mut config, &cache.config)?;
+            write_i32(&mut resp, config.len() as i32)?;
+            resp.extend_from_slice(&config);
+        }
+        OpCode::CacheDestroy => {
+            let id = read_i32(req)?;
+            if state.caches.remove(&id).is_none() {
+                return Err(cache_not_found(id));
+            }
+        }
+        OpCode::GetBinaryType => match state.binary_types.get(&read_i32(req)?) {
+            Some(binary_type) => {
+                write_bool(&mut resp, true)?;
+                write_binary_type(&mut resp, binary_type)?;
+            }
+            None => write_bool(&mut resp, false)?,
+        },
+        OpCode::QuerySql | OpCode::QuerySqlFields => {
+            return Err(server_err(
+                StatusCode::FunctionalityDisabled,
+                "SQL queries are not supported",
+            ))
+        }
+        OpCode::TxStart => {
+            let _concurrency = read_u8(req)?;
+            let _isolation = read_u8(req)?;
+            let _timeout = read_i64(req)?;
+            let _label = String::read(req)?;
+            state.last_tx_id += 1;
+            let snapshot = state.caches.clone();
+            state.transactions.insert(state.last_tx_id, snapshot);
+            write_i32(&mut resp, state.last_tx_id)?;
+        }
+        OpCode::TxEnd => {
+            let tx_id = read_i32(req)?;
+            let commit = read_bool(req)?;
+            match state.transactions.remove(&tx_id) {
+                Some(snapshot) => {
+                    if !commit {
+                        state.caches = snapshot;
+                    }
+                }
+                None => {
+                    return Err(server_err(
+                        StatusCode::TxNotFound,
+                        format!("Transaction {} not found", tx_id).as_str(),
+                    ))
+                }
+            }
+        }
+        OpCode::ClusterGetState => write_u8(&mut resp, state.cluster_state.into())?,
+        OpCode::ClusterChangeState => state.cluster_state = ClusterState::try_from(read_u8(req)?)?,
+        OpCode::ClusterChangeWalState => {
+            let cache = get_cache_by_name(state, req)?;
+            let enabled = read_bool(req)?;
+            write_bool(&mut resp, cache.wal_enabled != enabled)?;
+            cache.wal_enabled = enabled;
+        }
+        OpCode::ClusterGetWalState => {
+            let cache = get_cache_by_name(state, req)?;
+            write_bool(&mut resp, cache.wal_enabled)?;
+        }
+        OpCode::ClusterGroupGetNodeIds => {
+            let _top_ver = read_i64(req)?;
+            let filters = read_node_filters(req)?;
+            let nodes: Vec<&ClusterNode> = state
+                .nodes
+                .iter()
+                .filter(|node| filters.iter().all(|filter| filter.matches(node)))
+                .collect();
+            write_bool(&mut resp, true)?; // topology changed
+            write_i64(&mut resp, state.nodes.len() as i64)?;
+            write_i32(&mut resp, nodes.len() as i32)?;
+            for node in nodes {
+                write_uuid(&mut resp, node.id)?;
+            }
+        }
+        OpCode::ClusterGroupGetNodeInfo => {
+            let count = read_i32(req)?;
+            let mut nodes = Vec::new();
+            for _ in 0..count {
+                let id = read_uuid(req)?;
+                // unknown nodes are skipped
+                nodes.extend(state.nodes.iter().find(|node| node.id == id));
+            }
+            write_i32(&mut resp, nodes.len() as i32)?;
+            for node in nodes {
+                write_cluster_node(&mut resp, node)?;
+            }
+        }
+        OpCode::ClusterGroupGetNodeEndpoints => {
+            let _start_top_ver = read_i64(req)?;
+            let _end_top_ver = read_i64(req)?;
+            let servers: Vec<&ClusterNode> =
+                state.nodes.iter().filter(|node| !node.is_client).collect();
+            write_i64(&mut resp, state.nodes.len() as i64)?;
+            write_i32(&mut resp, servers.len() as i32)?;
+            for node in servers {
+                write_uuid(&mut resp, node.id)?;
+                write_i32(&mut resp, state.port)?;
+                write_i32(&mut resp, node.addresses.len() as i32)?;
+                for addr in &node.addresses {
+                    write_string_type_code(&mut resp, addr)?;
+                }
+            }
+            write_i32(&mut resp, 0)?; // removed nodes
+        }
+        OpCode::ComputeTaskExecute => {
+            let node_count = read_i32(req)?;
+            for _ in 0..node_count {
+                read_uuid(req)?;
+            }
+            let _flags = read_u8(req)?;
+            let _timeout = read_i64(req)?;
+            let name = String::read(req)?.ok_or_else(|| Error::from("Task name is NULL"))?;
+            let result = match state.tasks.get(&name) {
+                Some(task) => task(req),
+                None => Err(server_err(
+                    StatusCode::Failed,
+                    format!("Unknown task name: {}", name).as_str(),
+                )),
+            };
+            state.last_resource_id += 1;
+            write_i64(&mut resp, state.last_resource_id)?;
+            notifications.push(notification(
+                state.last_resource_id,
+                OpCode::ComputeTaskFinished,
+                result,
+            )?);
+        }
+        OpCode::ServiceInvoke => {
+            let name = String::read(req)?.ok_or_else(|| Error::from("Service name is NULL"))?;
+            let flags = read_u8(req)?;
+            let _timeout = read_i64(req)?;
+            let node_count = read_i32(req)?;
+            for _ in 0..node_count {
+                read_uuid(req)?;
+            }
+            let method = String::read(req)?.ok_or_else(|| Error::from("Method name is NULL"))?;
+            let arg_count = read_i32(req)?;
+            let mut args = Vec::new();
+            for _ in 0..arg_count {
+                if flags & FLAG_HAS_PARAMETER_TYPES != 0 {
+                    let _type_id = read_i32(req)?;
+                }
+                args.push(read_value(TypeCode::try_from(read_u8(req)?)?, req)?);
+            }
+            let (_, service) = get_service(state, &name)?;
+            write_value(&mut resp, &service(method.as_str(), args)?)?;
+        }
+        OpCode::ServiceGetDescriptors => {
+            write_i32(&mut resp, state.services.len() as i32)?;
+            for (descriptor, _) in &state.services {
+                write_descriptor(&mut resp, descriptor)?;
+            }
+        }
+        OpCode::ServiceGetDescriptor => {
+            let name = String::read(req)?.ok_or_else(|| Error::from("Service name is NULL"))?;
+            write_descriptor(&mut resp, &get_service(state, &name)?.0)?;
+        }
+        OpCode::DataStreamerStart => {
+            let cache_id = read_i32(req)?;
+            let flags = read_u8(req)?;
+            let _per_node_buffer_size = read_i32(req)?;
+            let _per_thread_buffer_size = read_i32(req)?;
+            if read_data_object(req)? != [TypeCode::Null as u8] {
+                return Err(server_err(
+                    StatusCode::Failed,
+                    "Data streamer receivers are not supported",
+                ));
+            }
+            stream_entries(state, cache_id, flags, req)?;
+            state.last_resource_id += 1;
+            if flags & FLAG_CLOSE == 0 {
+                state
+                    .streamers
+                    .insert(state.last_resource_id, (cache_id, flags));
+            }
+            write_i64(&mut resp, state.last_resource_id)?;
+        }
+        OpCode::DataStreamerAddData => {
+            let streamer_id = read_i64(req)?;
+            let flags = read_u8(req)?;
+            let (cache_id, start_flags) = *state.streamers.get(&streamer_id).ok_or_else(|| {
+                server_err(
+                    StatusCode::ResourceDoesNotExist,
+                    format!("Data streamer {} not found", streamer_id).as_str(),
+                )
+            })?;
+            stream_entries(state, cache_id, start_flags, req)?;
+            if flags & FLAG_CLOSE != 0 {
+                state.streamers.remove(&streamer_id);
+            }
+        }
+        OpCode::AtomicLongCreate => {
+            let name = String::read(req)?.ok_or_else(|| Error::from("Name is NULL"))?;
+            let initial_value = read_i64(req)?;
+            let mut group_name = None;
+            if read_bool(req)? {
+                let _reserve_size = read_i32(req)?;
+                let _cache_mode = read_u8(req)?;
+                let _backups = read_i32(req)?;
+                group_name = String::read(req)?;
+            }
+            state
+                .atomic_longs
+                .entry((name, group_name))
+                .or_insert(initial_value);
+        }
+        OpCode::AtomicLongRemove
+        | OpCode::AtomicLongExists
+        | OpCode::AtomicLongValueGet
+        | OpCode::AtomicLongValueAddAndGet
+        | OpCode::AtomicLongValueGetAndSet
+        | OpCode::AtomicLongValueCompareAndSet => {
+            let name = String::read(req)?.ok_or_else(|| Error::from("Name is NULL"))?;
+            let key = (name, String::read(req)?);
+            if op_code == OpCode::AtomicLongExists {
+                write_bool(&mut resp, state.atomic_longs.contains_key(&key))?;
+                return Ok(resp);
+            }
+            let value = state.atomic_longs.get_mut(&key).ok_or_else(|| {
+                server_err(
+                    StatusCode::ResourceDoesNotExist,
+                    format!("AtomicLong with name '{}' does not exist.", key.0).as_str(),
+                )
+            })?;
+            match op_code {
+                OpCode::AtomicLongValueGet => write_i64(&mut resp, *value)?,
+                OpCode::AtomicLongValueAddAndGet => {
+                    *value = value.wrapping_add(read_i64(req)?);
+                    write_i64(&mut resp, *value)?;
+                }
+                OpCode::AtomicLongValueGetAndSet => {
+                    write_i64(&mut resp, std::mem::replace(value, read_i64(req)?))?;
+                }
+                OpCode::AtomicLongValueCompareAndSet => {
+                    let expected = read_i64(req)?;
+                    let new = read_i64(req)?;
+                    write_bool(&mut resp, *value == expected)?;
+                    if *value == expected {
+                        *value = new;
+                    }
+                }
+                _ => {
+                    state.atomic_longs.remove(&key);
+                }
+            }
+        }
+        OpCode::ResourceClose => {
+            let resource_id = read_i64(req)?;
+            if state.set_iterators.remove(&resource_id).is_none()
+                && state.streamers.remove(&resource_id).is_none()
+            {
+                return Err(server_err(
+                    StatusCode::ResourceDoesNotExist,
+                    format!("Failed to find resource with id: {}", resource_id).as_str(),
+                ));
+            }
+        }
+        OpCode::SetGetOrCreate => {
+            let name = String::read(req)?.ok_or_else(|| Error::from("Name is NULL"))?;
+            if read_bool(req)? {
+                let _atomicity_mode = read_u8(req)?;
+                let _cache_mode = read_u8(req)?;
+                let _backups = read_i32(req)?;
+                let group_name = String::read(req)?;
+                let collocated = read_bool(req)?;
+                state.sets.entry(name.clone()).or_insert_with(|| FakeSet {
+                    cache_id: string_to_java_hashcode(
+                        format!("datastructures_{}", group_name.unwrap_or_default()).as_str(),
+                    ),
+                    collocated,
+                    values: Vec::new(),
+                });
+            }
+            match state.sets.get(&name) {
+                Some(set) => {
+                    write_bool(&mut resp, true)?;
+                    write_bool(&mut resp, set.collocated)?;
+                    write_i32(&mut resp, set.cache_id)?;
+                }
+                None => write_bool(&mut resp, false)?,
+            }
+        }
+        OpCode::SetIteratorGetPage => {
+            let resource_id = read_i64(req)?;
+            let page_size = read_i32(req)?;
+            let values = state.set_iterators.remove(&resource_id).ok_or_else(|| {
+                server_err(
+                    StatusCode::ResourceDoesNotExist,
+                    format!("Failed to find resource with id: {}", resource_id).as_str(),
+                )
+            })?;
+            if let Some(rest) = write_set_page(&mut resp, values, page_size)? {
+                state.set_iterators.insert(resource_id, rest);
+            }
+        }
+        OpCode::SetClose
+        | OpCode::SetExists
+        | OpCode::SetValueAdd
+        | OpCode::SetValueAddAll
+        | OpCode::SetValueRemove
+        | OpCode::SetValueRemoveAll
+        | OpCode::SetValueContains
+        | OpCode::SetValueContainsAll
+        | OpCode::SetValueRetainAll
+        | OpCode::SetSize
+        | OpCode::SetClear
+        | OpCode::SetIteratorStart => handle_set_op(state, op_code, req, &mut resp)?,
+        OpCode::ComputeTaskFinished | OpCode::QueryContinuousEventNotification => {
+            return Err(server_err(
+                StatusCode::InvalidOpCode,
+                "Notifications are sent by the server",
+            ))
+        }
+        _ => handle_cache_op(state, op_code, req, writer, &mut resp)?,
+    }
+    Ok(resp)
+}
+
+/// Rejects the cache operations an inactive cluster cannot serve
+/// and the modifications a read-only cluster does not allow
+fn check_cluster_state(cluster_state: ClusterState, op_code: OpCode) -> Result<()> {
+    let allowed = match op_code {
+        OpCode::Handshake
+        | OpCode::ResourceClose
+        | OpCode::GetBinaryType
+        | OpCode::TxStart
+        | OpCode::TxEnd
+        | OpCode::ClusterGetState
+        | OpCode::ClusterChangeState
+        | OpCode::ClusterGroupGetNodeIds
+        | OpCode::ClusterGroupGetNodeInfo
+        | OpCode::ClusterGroupGetNodeEndpoints => true,
+        OpCode::CacheGetNames
+        | OpCode::CacheGetConfiguration
+        | OpCode::CacheGet
+        | OpCode::CacheGetAll
+        | OpCode::CacheContainsKey
+        | OpCode::CacheContainsKeys
+        | OpCode::CacheGetSize
+        | OpCode::ClusterGetWalState
+        | OpCode::AtomicLongExists
+        | OpCode::AtomicLongValueGet
+        | OpCode::SetExists
+        | OpCode::SetValueContains
+        | OpCode::SetValueContainsAll
+        | OpCode::SetSize
+        | OpCode::SetIteratorStart
+        | OpCode::SetIteratorGetPage
+        | OpCode::QueryScan
+        | OpCode::QueryContinuous
+        | OpCode::QuerySql
+        | OpCode::QuerySqlFields => cluster_state != ClusterState::Inactive,
+        _ => cluster_state == ClusterState::Active,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(server_err(
+            StatusCode::InvalidNodeState,
+            format!(
+                "Operation {:?} is not allowed in the {:?} state",
+                op_code, cluster_state
+            )
+            .as_str(),
+        ))
+    }
+}
+
+fn create_cache(state: &mut State, config: CacheConfiguration, fail_if_exists: bool) -> Result<()> {
+    let id = string_to_java_hashcode(config.name.as_str());
+    if state.caches.contains_key(&id) {
+        if fail_if_exists {
+            return Err(server_err(
+                StatusCode::CacheExists,
+                format!("Cache already exists: {}", config.name).as_str(),
+            ));
+        }
+        return Ok(());
+    }
+    state.caches.insert(id, FakeCache::new(config));
+    Ok(())
+}
+
+fn cache_not_found(id: i32) -> Error {
+    server_err(
+        StatusCode::CacheDoesNotExist,
+        format!("Cache does not exist [cacheId= {}]", id).as_str(),
+    )
+}
+
+fn get_cache(state: &mut State, id: i32) -> Result<&mut FakeCache> {
+    state.caches.get_mut(&id).ok_or_else(|| cache_not_found(id))
+}
+
+fn get_service<'a>(state: &'a State, name: &str) -> Result<&'a (ServiceDescriptor, Service)> {
+    state
+        .services
+        .iter()
+        .find(|(descriptor, _)| descriptor.name == name)
+        .ok_or_else(|| {
+            server_err(
+                StatusCode::Failed,
+                format!("Service not found: {}", name).as_str(),
+            )
+        })
+}
+
+fn get_cache_by_name<'a>(state: &'a mut State, req: &mut impl Read) -> Result<&'a mut FakeCache> {
+    let name = String::read(req)?.ok_or_else(|| Error::from("Cache name is NULL"))?;
+    get_cache(state, string_to_java_hashcode(name.as_str()))
+}
+
+/// Executes the key-value, scan and continuous query operations.
+/// All of them start with the cache id and the flags.
+/// The changes are sent to the continuous queries of the cache
+fn handle_cache_op(
+    state: &mut State,
+    op_code: OpCode,
+    req: &mut Cursor<Vec<u8>>,
+    writer: &SharedWriter,
+    resp: &mut Vec<u8>,
+) -> Result<()> {
+    let id = read_i32(req)?;
+    let flags = read_u8(req)?;
+    if flags & FLAG_TRANSACTIONAL != 0 {
+        let _tx_id = read_i32(req)?;
+    }
+    let expiry_policy = match flags & FLAG_WITH_EXPIRY_POLICY {
+        0 => None,
+        _ => Some(read_expiry_policy(req)?),
+    };
+
+    // the entries are expired lazily, when their cache is used
+    let now = Instant::now();
+    let cache = get_cache(state, id)?;
+    cache.expire(now);
+    let expired = std::mem::take(&mut cache.events);
+    notify_continuous_queries(state, id, &expired);
+
+    if op_code == OpCode::QueryScan {
+        state.last_resource_id += 1;
+        let cursor_id = state.last_resource_id;
+        return query_scan(get_cache(state, id)?, cursor_id, req, resp);
+    }
+    if op_code == OpCode::QueryContinuous {
+        get_cache(state, id)?;
+        state.last_resource_id += 1;
+        let query = query_continuous(id, state.last_resource_id, req, writer)?;
+        write_i64(resp, query.cursor_id)?;
+        state.continuous_queries.push(query);
+        return Ok(());
+    }
+
+    let cache = get_cache(state, id)?;
+    let mut read = Vec::new();
+    match op_code {
+        OpCode::CacheGet => {
+            let key = read_data_object(req)?;
+            write_opt_value(resp, cache.get(&key))?;
+            read.push(key);
+        }
+        OpCode::CacheGetAll => {
+            let keys = read_data_objects(req)?;
+            let found: Vec<_> = keys
+                .into_iter()
+                .filter_map(|k| cache.get(&k).cloned().map(|v| (k, v)))
+                .collect();
+            write_i32(resp, found.len() as i32)?;
+            for (key, value) in found {
+                resp.extend_from_slice(&key);
+                resp.extend_from_slice(&value);
+                read.push(key);
+            }
+        }
+        OpCode::CachePut => {
+            let key = read_data_object(req)?;
+            let value = read_data_object(req)?;
+            cache.put(key, value);
+        }
+        OpCode::CachePutAll => {
+            let count = read_i32(req)?;
+            for _ in 0..count {
+                let key = read_data_object(req)?;
+                let value = read_data_object(req)?;
+                cache.put(key, value);
+            }
+        }
+        OpCode::CachePutIfAbsent => {
+            let key = read_data_object(req)?;
+            let value = read_data_object(req)?;
+            let absent = cache.get(&key).is_none();
+            if absent {
+                cache.put(key, value);
+            }
+            write_bool(resp, absent)?;
+        }
+        OpCode::CacheGetAndPut => {
+            let key = read_data_object(req)?;
+            let value = read_data_object(req)?;
+            write_opt_value(resp, cache.put(key, value).as_ref())?;
+        }
+        OpCode::CacheGetAndReplace => {
+            let key = read_data_object(req)?;
+            let value = read_data_object(req)?;
+            let old = match cache.get(&key) {
+                Some(_) => cache.put(key, value),
+                None => None,
+            };
+            write_opt_value(resp, old.as_ref())?;
+        }
+        OpCode::CacheGetAndRemove => {
+            let key = read_data_object(req)?;
+            write_opt_value(resp, cache.remove(&key).as_ref())?;
+        }
+        OpCode::CacheGetAndPutIfAbsent => {
+            let key = read_data_object(req)?;
+            let value = read_data_object(req)?;
+            let old = cache.get(&key).cloned();
+            if old.is_none() {
+                cache.put(key, value);
+            }
+            write_opt_value(resp, old.as_ref())?;
+        }
+        OpCode::CacheReplace => {
+            let key = read_data_object(req)?;
+            let value = read_data_object(req)?;
+            let present = cache.get(&key).is_some();
+            if present {
+                cache.put(key, value);
+            }
+            write_bool(resp, present)?;
+        }
+        OpCode::CacheReplaceIfEquals => {
+            let key = read_data_object(req)?;
+            let old = read_data_object(req)?;
+            let new = read_data_object(req)?;
+            let equals = cache.get(&key) == Some(&old);
+            if equals {
+                cache.put(key, new);
+            }
+            write_bool(resp, equals)?;
+        }
+        OpCode::CacheContainsKey => {
+            let key = read_data_object(req)?;
+            write_bool(resp, cache.get(&key).is_some())?;
+        }
+        OpCode::CacheContainsKeys => {
+            let keys = read_data_objects(req)?;
+            write_bool(resp, keys.iter().all(|k| cache.get(k).is_some()))?;
+        }
+        OpCode::CacheClear | OpCode::CacheRemoveAll => cache.clear(),
+        OpCode::CacheClearKey => {
+            let key = read_data_object(req)?;
+            cache.remove(&key);
+        }
+        OpCode::CacheClearKeys | OpCode::CacheRemoveKeys => {
+            for key in read_data_objects(req)? {
+                cache.remove(&key);
+            }
+        }
+        OpCode::CacheRemoveKey => {
+            let key = read_data_object(req)?;
+            write_bool(resp, cache.remove(&key).is_some())?;
+        }
+        OpCode::CacheRemoveIfEquals => {
+            let key = read_data_object(req)?;
+            let value = read_data_object(req)?;
+            let equals = cache.get(&key) == Some(&value);
+            if equals {
+                cache.remove(&key);
+            }
+            write_bool(resp, equals)?;
+        }
+        OpCode::CacheGetSize => {
+            let count = read_i32(req)?;
+            for _ in 0..count {
+                let _peek_mode = read_u8(req)?;
+            }
+            write_i64(resp, cache.entries.len() as i64)?;
+        }
+        _ => {
+            return Err(server_err(
+                StatusCode::InvalidOpCode,
+                format!("Unsupported op code {:?}", op_code).as_str(),
+            ))
+        }
+    }
+    cache.apply_expiry(expiry_policy, &read, now);
+    let events = std::mem::take(&mut cache.events);
+    notify_continuous_queries(state, id, &events);
+    Ok(())
+}
+
+/// Executes the set operations starting with the name, the cache id and the collocation flag
+fn handle_set_op(
+    state: &mut State,
+    op_code: OpCode,
+    req: &mut Cursor<Vec<u8>>,
+    resp: &mut Vec<u8>,
+) -> Result<()> {
+    let name = String::read(req)?.ok_or_else(|| Error::from("Name is NULL"))?;
+    let _cache_id = read_i32(req)?;
+    let _collocated = read_bool(req)?;
+    if op_code == OpCode::SetExists {
+        write_bool(resp, state.sets.contains_key(&name))?;
+        return Ok(());
+    }
+    if op_code == OpCode::SetClose {
+        state.sets.remove(&name);
+        return Ok(());
+    }
+    if op_code == OpCode::SetIteratorStart {
+        state.last_resource_id += 1;
+    }
+    let resource_id = state.last_resource_id;
+    let set = state.sets.get_mut(&name).ok_or_else(|| {
+        server_err(
+            StatusCode::ResourceDoesNotExist,
+            format!("IgniteSet with name '{}' does not exist.", name).as_str(),
+        )
+    })?;
+    match op_code {
+        OpCode::SetSize => write_i32(resp, set.values.len() as i32)?,
+        OpCode::SetClear => set.values.clear(),
+        OpCode::SetIteratorStart => {
+            let page_size = read_i32(req)?;
+            if let Some(rest) = write_set_page(resp, set.values.clone(), page_size)? {
+                write_i64(resp, resource_id)?;
+                state.set_iterators.insert(resource_id, rest);
+            }
+        }
+        _ => {
+            let _keep_binary = read_bool(req)?;
+            let values = match op_code {
+                OpCode::SetValueAdd | OpCode::SetValueRemove | OpCode::SetValueContains => {
+                    vec![read_data_object(req)?]
+                }
+                _ => read_data_objects(req)?,
+            };
+            let result = match op_code {
+                OpCode::SetValueAdd | OpCode::SetValueAddAll => {
+                    let len = set.values.len();
+                    for value in values {
+                        if !set.values.contains(&value) {
+                            set.values.push(value);
+                        }
+                    }
+                    set.values.len() != len
+                }
+                OpCode::SetValueRemove | OpCode::SetValueRemoveAll => {
+                    let len = set.values.len();
+                    set.values.retain(|value| !values.contains(value));
+                    set.values.len() != len
+                }
+                OpCode::SetValueRetainAll => {
+                    let len = set.values.len();
+                    set.values.retain(|value| values.contains(value));
+                    set.values.len() != len
+                }
+                _ => values.iter().all(|value| set.values.contains(value)),
+            };
+            write_bool(resp, result)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a page of the set values and returns the rest if there are more pages
+fn write_set_page(
+    resp: &mut Vec<u8>,
+    mut values: Vec<Vec<u8>>,
+    page_size: i32,
+) -> io::Result<Option<Vec<Vec<u8>>>> {
+    let count = match page_size {
+        size if size > 0 => values.len().min(size as usize),
+        _ => values.len(),
+    };
+    let rest = values.split_off(count);
+    write_i32(resp, values.len() as i32)?;
+    for value in values {
+        resp.extend_from_slice(&value);
+    }
+    write_bool(resp, !rest.is_empty())?;
+    Ok(if rest.is_empty() { None } else { Some(rest) })
+}
+
+/// Stores the streamed entries. Without overwriting only the absent keys are put,
+/// a null value removes the key
+fn stream_entries(
+    state: &mut State,
+    cache_id: i32,
+    flags: u8,
+    req: &mut Cursor<Vec<u8>>,
+) -> Result<()> {
+    let count = read_i32(req)?;
+    let cache = get_cache(state, cache_id)?;
+    for _ in 0..count {
+        let key = read_data_object(req)?;
+        let value = read_data_object(req)?;
+        if flags & FLAG_ALLOW_OVERWRITE == 0 {
+            if value != [TypeCode::Null as u8] && cache.get(&key).is_none() {
+                cache.put(key, value);
+            }
+        } else if value == [TypeCode::Null as u8] {
+            cache.remove(&key);
+        } else {
+            cache.put(key, value);
+        }
+    }
+    let events = std::mem::take(&mut cache.events);
+    notify_continuous_queries(state, cache_id, &events);
+    Ok(())
+}
+
+/// Reads the continuous query request following the flags
+fn query_continuous(
+    cache_id: i32,
+    cursor_id: i64,
+    req: &mut Cursor<Vec<u8>>,
+    writer: &SharedWriter,
+) -> Result<FakeContinuousQuery> {
+    let page_size = read_i32(req)?;
+    let _time_interval = read_i64(req)?;
+    let include_expired = read_bool(req)?;
+    for object in ["filter", "transformer"] {
+        if read_data_object(req)? != [TypeCode::Null as u8] {
+            return Err(server_err(
+                StatusCode::Failed,
+                format!("Continuous query {} is not supported", object).as_str(),
+            ));
+        }
+    }
+    if read_u8(req)? != 0 {
+        return Err(server_err(
+            StatusCode::Failed,
+            "Continuous query initial query is not supported",
+        ));
+    }
+    Ok(FakeContinuousQuery {
+        cache_id,
+        cursor_id,
+        page_size: page_size.max(1) as usize,
+        include_expired,
+        writer: writer.clone(),
+    })
+}
+
+/// Pushes the events to the continuous queries of the cache.
+/// The queries whose connection fails are dropped
+fn notify_continuous_queries(state: &mut State, cache_id: i32, events: &[FakeEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    state.continuous_queries.retain(|query| {
+        if query.cache_id != cache_id {
+            return true;
+        }
+        let events: Vec<&FakeEvent> = events
+            .iter()
+            .filter(|e| query.include_expired || e.event_type != CacheEntryEventType::Expired)
+            .collect();
+        let frames: io::Result<Vec<Vec<u8>>> = events
+            .chunks(query.page_size)
+            .map(|batch| {
+                notification(
+                    query.cursor_id,
+                    OpCode::QueryContinuousEventNotification,
+                    event_batch(batch),
+                )
+            })
+            .collect();
+        frames
+            .and_then(|frames| write_frames(&mut *lock_writer(&query.writer), frames.into_iter()))
+            .is_ok()
+    });
+}
+
+/// Body of the event notification
+fn event_batch(events: &[&FakeEvent]) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    write_i32(&mut body, events.len() as i32)?;
+    for event in events {
+        body.extend_from_slice(&event.key);
+        write_opt_value(&mut body, event.old_value.as_ref())?;
+        write_opt_value(&mut body, event.value.as_ref())?;
+        write_u8(&mut body, event.event_type.into())?;
+    }
+    Ok(body)
+}
+
+/// Replies with the first page of the entries. Following pages are not kept,
+/// as the client never requests them
+fn query_scan(
+    cache: &FakeCache,
+    cursor_id: i64,
+    req: &mut Cursor<Vec<u8>>,
+    resp: &mut Vec<u8>,
+) -> Result<()> {
+    if read_data_object(req)? != [TypeCode::Null as u8] {
+        return Err(server_err(
+            StatusCode::Failed,
+            "Scan query filters are not supported",
+        ));
+    }
+    let page_size = read_i32(req)?;
+    let _partitions = read_i32(req)?;
+    let _local = read_bool(req)?;
+
+    let count = match page_size {
+        size if size > 0 => cache.entries.len().min(size as usize),
+        _ => cache.entries.len(),
+    };
+    write_i64(resp, cursor_id)?;
+    write_i32(resp, count as i32)?;
+    for (key, value) in &cache.entries[..count] {
+        resp.extend_from_slice(key);
+        resp.extend_from_slice(value);
+    }
+    write_bool(resp, count < cache.entries.len())?;
+    Ok(())
+}
+
+/// Reads a single data object and returns its serialized form
+fn read_data_object(reader: &mut Cursor<Vec<u8>>) -> Result<Vec<u8>> {
+    let start = reader.position() as usize;
+    let type_code = TypeCode::try_from(read_u8(reader)?)?;
+    read_value(type_code, reader)?;
+    let end = reader.position() as usize;
+    Ok(reader.get_ref()[start..end].to_vec())
+}
+
+/// Reads a count followed by as many data objects
+fn read_data_objects(reader: &mut Cursor<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+    let count = read_i32(reader)?;
+    let mut objects = Vec::new();
+    for _ in 0..count {
+        objects.push(read_data_object(reader)?);
+    }
+    Ok(objects)
+}
+
+fn write_opt_value(writer: &mut Vec<u8>, value: Option<&Vec<u8>>) -> io::Result<()> {
+    match value {
+        Some(v) => {
+            writer.extend_from_slice(v);
+            Ok(())
+        }
+        None => write_null(writer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{QueryEntity, QueryField};
+    use crate::handshake::{Feature, V_MINOR, V_PATCH};
+    use crate::Ignite;
+
+    #[test]
+    fn test_cache_config_ops() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+
+        client.create_cache::<i32, i32>("first").unwrap();
+        client.get_or_create_cache::<i32, i32>("first").unwrap();
+        match client.create_cache::<i32, i32>("first") {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::CacheExists),
+            _ => panic!("duplicate cache is created"),
+        }
+
+        let mut config = CacheConfiguration::new("second");
+        config.num_backup = 2;
+        config.sql_schema = Some("PUBLIC".to_string());
+        config.query_entities = Some(vec![QueryEntity {
+            key_type: "java.lang.Integer".to_string(),
+            value_type: "Rainbow".to_string(),
+            table: "RAINBOW".to_string(),
+            key_field: "ID".to_string(),
+            value_field: "".to_string(),
+            query_fields: vec![QueryField {
+                name: "ID".to_string(),
+                type_name: "java.lang.Integer".to_string(),
+                key_field: true,
+                not_null_constraint: true,
+                precision: -1,
+                scale: -1,
+            }],
+            field_aliases: vec![],
+            query_indexes: vec![],
+            default_value: None,
+        }]);
+        let ttl = ExpiryDuration::After(std::time::Duration::from_secs(60));
+        config.expiry_policy = Some(ExpiryPolicy {
+            create: ttl,
+            update: ttl,
+            access: ExpiryDuration::Unchanged,
+        });
+        client
+            .create_cache_with_config::<i32, i32>(&config)
+            .unwrap();
+
+        let read = client.get_cache_config("second").unwrap();
+        assert_eq!(read.name, "second");
+        assert_eq!(read.num_backup, 2);
+        assert_eq!(read.sql_schema, Some("PUBLIC".to_string()));
+        let entities = read.query_entities.unwrap();
+        assert_eq!(entities[0].table, "RAINBOW");
+        assert_eq!(entities[0].query_fields[0].name, "ID");
+        assert!(entities[0].query_fields[0].key_field);
+        assert_eq!(read.expiry_policy, config.expiry_policy);
+
+        let mut names = client.get_cache_names().unwrap();
+        names.sort();
+        assert_eq!(names, vec!["first".to_string(), "second".to_string()]);
+
+        client.destroy_cache("first").unwrap();
+        match client.get_cache::<i32, i32>("first") {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::CacheDoesNotExist),
+            _ => panic!("destroyed cache is found"),
+        }
+    }
+
+    #[test]
+    fn test_key_value_ops() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<String, i64>("kv").unwrap();
+        let (a, b, c) = ("a".to_string(), "b".to_string(), "c".to_string());
+
+        assert_eq!(cache.get(&a).unwrap(), None);
+        cache.put(&a, &1).unwrap();
+        assert_eq!(cache.get(&a).unwrap(), Some(1));
+        assert!(!cache.put_if_absent(&a, &2).unwrap());
+        assert!(cache.put_if_absent(&b, &2).unwrap());
+        assert_eq!(cache.get_and_put(&a, &10).unwrap(), Some(1));
+        assert_eq!(cache.get_and_replace(&c, &3).unwrap(), None);
+        assert!(!cache.contains_key(&c).unwrap());
+        assert!(cache.contains_keys(&[a.clone(), b.clone()]).unwrap());
+        assert!(!cache.replace_if_equals(&b, &3, &4).unwrap());
+        assert!(cache.replace_if_equals(&b, &2, &20).unwrap());
+        assert_eq!(cache.get_size().unwrap(), 2);
+
+        let all = cache.get_all(&[a.clone(), b.clone(), c.clone()]).unwrap();
+        assert_eq!(
+            all,
+            vec![(Some(a.clone()), Some(10)), (Some(b.clone()), Some(20))]
+        );
+
+        assert!(!cache.remove_if_equals(&a, &1).unwrap());
+        assert!(cache.remove_key(&a).unwrap());
+        assert_eq!(cache.get_and_remove(&b).unwrap(), Some(20));
+        assert_eq!(cache.get_size().unwrap(), 0);
+
+        cache.put_all(&[(a.clone(), 1), (b, 2), (c, 3)]).unwrap();
+        cache.clear_key(&a).unwrap();
+        assert_eq!(cache.get_size().unwrap(), 2);
+        cache.remove_all().unwrap();
+        assert_eq!(cache.get_size().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let short = ExpiryDuration::After(std::time::Duration::from_millis(200));
+        let mut config = CacheConfiguration::new("sessions");
+        config.expiry_policy = Some(ExpiryPolicy {
+            create: short,
+            update: ExpiryDuration::Unchanged,
+            access: ExpiryDuration::Unchanged,
+        });
+        let cache = client
+            .create_cache_with_config::<i32, String>(&config)
+            .unwrap();
+        let eternal = cache.with_expiry_policy(
+            ExpiryDuration::Eternal,
+            ExpiryDuration::Unchanged,
+            ExpiryDuration::Unchanged,
+        );
+        let mut query = cache
+            .query_continuous(1, std::time::Duration::ZERO, true)
+            .unwrap();
+
+        cache.put(&1, &"configured".to_string()).unwrap();
+        eternal.put(&2, &"eternal".to_string()).unwrap();
+        // the update keeps the expiration time
+        eternal.put(&1, &"updated".to_string()).unwrap();
+        // the read through the view makes the entry eternal
+        let touched = cache.with_expiry_policy(
+            ExpiryDuration::Unchanged,
+            ExpiryDuration::Unchanged,
+            ExpiryDuration::Eternal,
+        );
+        cache.put(&3, &"read".to_string()).unwrap();
+        assert_eq!(touched.get(&3).unwrap(), Some("read".to_string()));
+        thread::sleep(std::time::Duration::from_millis(300));
+
+        assert_eq!(cache.get(&1).unwrap(), None);
+        let keys: Vec<i32> = cache
+            .query_scan(10)
+            .unwrap()
+            .into_iter()
+            .filter_map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, vec![2, 3]);
+        let expired = (0..6)
+            .map(|_| {
+                query
+                    .recv_timeout(std::time::Duration::from_secs(5))
+                    .unwrap()
+            })
+            .find(|e| e.as_ref().unwrap().event_type == CacheEntryEventType::Expired)
+            .unwrap()
+            .unwrap();
+        assert_eq!(expired.key, Some(1));
+        assert_eq!(expired.old_value, Some("updated".to_string()));
+
+        // zero time to live expires the entry right away
+        let zero = ExpiryDuration::After(std::time::Duration::ZERO);
+        let immediate = cache.with_expiry_policy(zero, zero, zero);
+        immediate.put(&5, &"gone".to_string()).unwrap();
+        assert!(!cache.contains_key(&5).unwrap());
+        immediate.get(&2).unwrap();
+        assert_eq!(cache.get_size().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_scan_and_missing_cache() {
+        let server = FakeServer::start().unwrap();
+        server.add_cache(CacheConfiguration::new("SQL_PUBLIC_RAINBOW"));
+        let mut client = server.connect().unwrap();
+
+        let cache = client
+            .get_cache::<i32, String>("SQL_PUBLIC_RAINBOW")
+            .unwrap();
+        for i in 0..5 {
+            cache.put(&i, &i.to_string()).unwrap();
+        }
+        let page = cache.query_scan(3).unwrap();
+        assert_eq!(
+            page,
+            vec![
+                (Some(0), Some("0".to_string())),
+                (Some(1), Some("1".to_string())),
+                (Some(2), Some("2".to_string())),
+            ]
+        );
+        assert_eq!(cache.query_scan(10).unwrap().len(), 5);
+        assert!(cache.query_scan_sql(10, "Rainbow", "ID > 1").is_err());
+
+        client.destroy_cache("SQL_PUBLIC_RAINBOW").unwrap();
+        match cache.get(&1) {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::CacheDoesNotExist),
+            _ => panic!("destroyed cache is used"),
+        }
+    }
+
+    #[test]
+    fn test_transactions() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, i32>("tx").unwrap();
+
+        let tx = client.start_transaction().unwrap();
+        cache.put(&1, &1).unwrap();
+        client.end_transaction(tx, true).unwrap();
+
+        let tx = client.start_transaction().unwrap();
+        cache.put(&1, &2).unwrap();
+        cache.put(&2, &2).unwrap();
+        client.end_transaction(tx, false).unwrap();
+        assert_eq!(cache.get(&1).unwrap(), Some(1));
+        assert_eq!(cache.get(&2).unwrap(), None);
+
+        match client.end_transaction(tx, true) {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::TxNotFound),
+            _ => panic!("finished transaction is ended twice"),
+        }
+    }
+
+    /// Sends a raw handshake request, returns the stream and the response without its length
+    fn raw_handshake(
+        server: &FakeServer,
+        minor: i16,
+        features: &[Feature],
+    ) -> (TcpStream, Cursor<Vec<u8>>) {
+        let mut req = Vec::new();
+        write_u8(&mut req, OpCode::Handshake as u8).unwrap();
+        write_i16(&mut req, V_MAJOR).unwrap();
+        write_i16(&mut req, minor).unwrap();
+        write_i16(&mut req, 0).unwrap();
+        write_u8(&mut req, CLIENT_CODE).unwrap();
+        if minor >= 7 {
+            let mask = feature_mask(features);
+            write_u8(&mut req, TypeCode::ArrByte as u8).unwrap();
+            write_i32(&mut req, mask.len() as i32).unwrap();
+            req.extend_from_slice(&mask);
+            write_null(&mut req).unwrap();
+        }
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        write_i32(&mut stream, req.len() as i32).unwrap();
+        stream.write_all(&req).unwrap();
+        let resp = raw_frame(&mut stream);
+        (stream, resp)
+    }
+
+    fn raw_frame(stream: &mut TcpStream) -> Cursor<Vec<u8>> {
+        let len = read_len(stream).unwrap();
+        Cursor::new(read_bytes(stream, len).unwrap())
+    }
+
+    /// Sends a request without payload, returns the response after the request id
+    fn raw_request(stream: &mut TcpStream, op_code: OpCode) -> Cursor<Vec<u8>> {
+        write_i32(stream, 10).unwrap();
+        write_i16(stream, op_code as i16).unwrap();
+        write_i64(stream, 0).unwrap();
+        let mut resp = raw_frame(stream);
+        read_i64(&mut resp).unwrap();
+        resp
+    }
+
+    #[test]
+    fn test_old_protocol_version() {
+        let server = FakeServer::start().unwrap();
+        let (mut stream, mut resp) = raw_handshake(&server, 2, &[]);
+        assert_eq!(read_u8(&mut resp).unwrap(), 1);
+        // neither the features nor the node id
+        assert_eq!(resp.position(), resp.get_ref().len() as u64);
+
+        // the status instead of the flags
+        let mut resp = raw_request(&mut stream, OpCode::CacheGetNames);
+        assert_eq!(read_i32(&mut resp).unwrap(), 0);
+        let mut resp = raw_request(&mut stream, OpCode::ClusterGetState);
+        assert_eq!(read_i32(&mut resp).unwrap(), 2);
+        assert_eq!(
+            String::read(&mut resp).unwrap().unwrap(),
+            "ClusterGetState needs protocol 1.7.0"
+        );
+    }
+
+    #[test]
+    fn test_newer_protocol_version() {
+        let server = FakeServer::start().unwrap();
+        let (_, mut resp) = raw_handshake(&server, V_MINOR + 1, &CLIENT_FEATURES);
+        assert_eq!(read_u8(&mut resp).unwrap(), 0);
+        assert_eq!(read_i16(&mut resp).unwrap(), V_MAJOR);
+        assert_eq!(read_i16(&mut resp).unwrap(), V_MINOR);
+        assert_eq!(read_i16(&mut resp).unwrap(), V_PATCH);
+    }
+
+    #[test]
+    fn test_missing_feature() {
+        let server = FakeServer::start().unwrap();
+        let (mut stream, mut resp) = raw_handshake(&server, V_MINOR, &[Feature::ClusterGroups]);
+        assert_eq!(read_u8(&mut resp).unwrap(), 1);
+        assert_eq!(read_u8(&mut resp).unwrap(), TypeCode::ArrByte as u8);
+        let len = read_len(&mut resp).unwrap();
+        assert_eq!(
+            read_bytes(&mut resp, len).unwrap(),
+            feature_mask(&CLIENT_FEATURES)
+        );
+        assert_eq!(read_u8(&mut resp).unwrap(), TypeCode::Uuid as u8);
+
+        let mut resp = raw_request(&mut stream, OpCode::ClusterGetState);
+        assert_eq!(read_i16(&mut resp).unwrap(), RESPONSE_FLAG_ERROR);
+        assert_eq!(read_i32(&mut resp).unwrap(), 2);
+        assert_eq!(
+            String::read(&mut resp).unwrap().unwrap(),
+            "ClusterGetState needs the ClusterStates feature"
+        );
+    }
+
+    #[test]
+    fn test_client_falls_back_to_server_version() {
+        let server = FakeServer::start_with_version((1, 2, 0)).unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<i32, i32>("cache").unwrap();
+        cache.put(&1, &2).unwrap();
+        assert_eq!(cache.get(&1).unwrap(), Some(2));
+        assert_eq!(client.get_cache_names().unwrap(), vec!["cache".to_string()]);
+
+        // rejected by the client, the request is not sent
+        match client.get_cluster_state() {
+            Err(Error::Custom { desc }) => assert_eq!(desc, "ClusterGetState needs protocol 1.7.0"),
+            _ => panic!("cluster state is requested with protocol 1.2.0"),
+        }
+        assert!(client.compute().execute::<_, i64>("Sum", &1i64).is_err());
+        assert_eq!(cache.get(&1).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_client_uses_server_version_ops() {
+        let server = FakeServer::start_with_version((1, 6, 0)).unwrap();
+        let mut client = server.connect().unwrap();
+        client.get_or_create_cache::<i32, i32>("cache").unwrap();
+        assert!(client.is_wal_enabled("cache").unwrap());
+        let tx = client.start_transaction().unwrap();
+        client.end_transaction(tx, true).unwrap();
+        // the features are not negotiated before 1.7.0
+        assert!(client.get_cluster_state().is_err());
+    }
+
+    #[test]
+    fn test_unsupported_server_version() {
+        let server = FakeServer::start_with_version((1, 1, 0)).unwrap();
+        match server.connect() {
+            Err(Error::Handshake { server_version, .. }) => assert_eq!(server_version, (1, 1, 0)),
+            _ => panic!("protocol 1.1.0 is accepted"),
+        }
+    }
+}
@@ -1,74 +1,194 @@
-use std::io::{Read, Write};
+use std::io::{Cursor, Read, Write};
 
 use crate::api::OpCode;
-use crate::error::{Error, Result};
+use crate::error::{Error, Result, StatusCode};
 use crate::protocol::{
-    read_i16, read_i32, read_u8, write_i16, write_i32, write_string_type_code, write_u8,
+    read_bytes, read_i16, read_i32, read_len, read_u8, write_i16, write_i32, write_null,
+    write_string_type_code, write_u8, TypeCode,
 };
 use crate::{ClientConfig, ReadableType};
 
-const MIN_HANDSHAKE_SIZE: usize = 8;
-const CLIENT_CODE: u8 = 2;
+pub(crate) const CLIENT_CODE: u8 = 2;
 
-const V_MAJOR: i16 = 1;
-const V_MINOR: i16 = 2;
-const V_PATCH: i16 = 0;
+// 1.7.0 is the first version negotiating the features with a bitmask
+pub(crate) const V_MAJOR: i16 = 1;
+pub(crate) const V_MINOR: i16 = 7;
+pub(crate) const V_PATCH: i16 = 0;
+pub(crate) const VERSION: (i16, i16, i16) = (V_MAJOR, V_MINOR, V_PATCH);
 
-pub(crate) fn handshake<T: Read + Write>(conn: &mut T, conf: &ClientConfig) -> Result<()> {
-    let mut msg_size = MIN_HANDSHAKE_SIZE;
+/// Oldest version the client falls back to when the server rejects the current one
+pub(crate) const MIN_VERSION: (i16, i16, i16) = (1, 2, 0);
 
-    if conf.username.is_some() != conf.password.is_some() {
-        return Err(Error::from("Both username and password expected!"));
+/// First version with the flags in the response header, the status takes their place before
+pub(crate) const FLAGS_VERSION: (i16, i16, i16) = (1, 4, 0);
+
+/// Protocol features negotiated in the handshake, by their bit in the feature mask.
+/// https://ignite.apache.org/docs/latest/binary-client-protocol/binary-client-protocol#connection-handshake
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Feature {
+    ExecuteTaskByName = 1,
+    ClusterStates = 2,
+    ClusterGroupGetNodesEndpoints = 3,
+    ClusterGroups = 4,
+    ServiceInvoke = 5,
+    GetServiceDescriptors = 9,
+}
+
+/// Features the client sends requests of
+pub(crate) const CLIENT_FEATURES: [Feature; 6] = [
+    Feature::ExecuteTaskByName,
+    Feature::ClusterStates,
+    Feature::ClusterGroupGetNodesEndpoints,
+    Feature::ClusterGroups,
+    Feature::ServiceInvoke,
+    Feature::GetServiceDescriptors,
+];
+
+/// Packs the features into a mask, the lowest bit of the first byte is the feature 0
+pub(crate) fn feature_mask(features: &[Feature]) -> Vec<u8> {
+    let mut mask = Vec::new();
+    for feature in features {
+        let bit = *feature as usize;
+        if mask.len() <= bit / 8 {
+            mask.resize(bit / 8 + 1, 0);
+        }
+        mask[bit / 8] |= 1 << (bit % 8);
     }
+    mask
+}
 
-    if let Some(ref user) = conf.username {
-        msg_size += user.len() + 4 + 1; // string itself, len, type code
+/// Checks if the feature is set in the mask
+pub(crate) fn has_feature(mask: &[u8], feature: Feature) -> bool {
+    let bit = feature as usize;
+    mask.get(bit / 8)
+        .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
+/// Protocol version and features negotiated in the handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProtocolContext {
+    pub(crate) version: (i16, i16, i16),
+    /// Features both sides support, empty before 1.7.0
+    features: Vec<u8>,
+}
+
+impl ProtocolContext {
+    pub(crate) fn new(version: (i16, i16, i16), features: Vec<u8>) -> ProtocolContext {
+        ProtocolContext { version, features }
     }
 
-    if let Some(ref pass) = conf.password {
-        msg_size += pass.len() + 4 + 1; // string itself, len, type code
+    /// Responses and notifications have the flags since 1.4.0
+    pub(crate) fn has_flags(&self) -> bool {
+        self.version >= FLAGS_VERSION
+    }
+
+    /// Fails if the negotiated version or features lack the operation
+    pub(crate) fn check_supported(&self, op_code: OpCode) -> Result<()> {
+        let (minor, feature) = match op_code {
+            OpCode::TxStart | OpCode::TxEnd => (5, None),
+            OpCode::ClusterChangeWalState | OpCode::ClusterGetWalState => (6, None),
+            // the events are pushed as notifications, which can't be told from responses
+            // without the flags
+            OpCode::QueryContinuous => (4, None),
+            OpCode::ClusterGetState | OpCode::ClusterChangeState => {
+                (7, Some(Feature::ClusterStates))
+            }
+            OpCode::ClusterGroupGetNodeIds | OpCode::ClusterGroupGetNodeInfo => {
+                (7, Some(Feature::ClusterGroups))
+            }
+            OpCode::ClusterGroupGetNodeEndpoints => {
+                (7, Some(Feature::ClusterGroupGetNodesEndpoints))
+            }
+            OpCode::ComputeTaskExecute => (7, Some(Feature::ExecuteTaskByName)),
+            OpCode::ServiceInvoke => (7, Some(Feature::ServiceInvoke)),
+            OpCode::ServiceGetDescriptors | OpCode::ServiceGetDescriptor => {
+                (7, Some(Feature::GetServiceDescriptors))
+            }
+            _ => (0, None),
+        };
+        if self.version < (V_MAJOR, minor, 0) {
+            return Err(Error::from(
+                format!("{:?} needs protocol {}.{}.0", op_code, V_MAJOR, minor).as_str(),
+            ));
+        }
+        match feature {
+            Some(feature) if !has_feature(&self.features, feature) => Err(Error::from(
+                format!("{:?} needs the {:?} feature", op_code, feature).as_str(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Offers the version to the server. The feature mask is sent since 1.7.0.
+/// A rejected handshake fails with the version the server supports
+pub(crate) fn handshake<T: Read + Write>(
+    conn: &mut T,
+    conf: &ClientConfig,
+    version: (i16, i16, i16),
+) -> Result<ProtocolContext> {
+    if conf.username.is_some() != conf.password.is_some() {
+        return Err(Error::from("Both username and password expected!"));
     }
 
-    write_i32(conn, msg_size as i32)?;
-    write_u8(conn, OpCode::Handshake as u8)?;
-    write_i16(conn, V_MAJOR)?;
-    write_i16(conn, V_MINOR)?;
-    write_i16(conn, V_PATCH)?;
-    write_u8(conn, CLIENT_CODE)?;
+    let mut req = Vec::new();
+    write_u8(&mut req, OpCode::Handshake as u8)?;
+    write_i16(&mut req, version.0)?;
+    write_i16(&mut req, version.1)?;
+    write_i16(&mut req, version.2)?;
+    write_u8(&mut req, CLIENT_CODE)?;
+
+    // feature mask as a byte array and no user attributes
+    let client_features = feature_mask(&CLIENT_FEATURES);
+    let has_features = version >= (1, 7, 0);
+    if has_features {
+        write_u8(&mut req, TypeCode::ArrByte as u8)?;
+        write_i32(&mut req, client_features.len() as i32)?;
+        req.write_all(&client_features)?;
+        write_null(&mut req)?;
+    }
 
     if let Some(ref user) = conf.username {
-        write_string_type_code(conn, user)?;
+        write_string_type_code(&mut req, user)?;
     }
 
     if let Some(ref pass) = conf.password {
-        write_string_type_code(conn, pass)?;
+        write_string_type_code(&mut req, pass)?;
     }
 
     // send bytes
+    write_i32(conn, req.len() as i32)?;
+    conn.write_all(&req)?;
     conn.flush()?;
 
-    // read header
-    let _ = read_i32(conn)?;
-    match read_u8(conn)? {
-        1 => Ok(()),
-        _ => match read_handshake_err(conn) {
-            Ok(msg) => Err(Error::from(msg.as_str())),
-            Err(err) => Err(err),
-        },
+    // read the whole response, the node id that follows the features is not used
+    let len = read_len(conn)?;
+    let mut resp = Cursor::new(read_bytes(conn, len)?);
+    if read_u8(&mut resp)? != 1 {
+        return Err(read_handshake_err(&mut resp)?);
+    }
+    let mut features = Vec::new();
+    if has_features {
+        let server_features = Vec::<u8>::read(&mut resp)?.unwrap_or_default();
+        features = client_features
+            .iter()
+            .zip(server_features.iter())
+            .map(|(client, server)| client & server)
+            .collect();
     }
+    Ok(ProtocolContext::new(version, features))
 }
 
-fn read_handshake_err<T: Read + Write>(conn: &mut T) -> Result<String> {
-    let major_v = read_i16(conn)?;
-    let minor_v = read_i16(conn)?;
-    let patch_v = read_i16(conn)?;
-    let err_msg = String::read(conn)?;
-
-    Ok(format!(
-        "Handshake error: v{}.{}.{} err: {}",
-        major_v,
-        minor_v,
-        patch_v,
-        err_msg.unwrap()
-    ))
+fn read_handshake_err(resp: &mut impl Read) -> Result<Error> {
+    let major_v = read_i16(resp)?;
+    let minor_v = read_i16(resp)?;
+    let patch_v = read_i16(resp)?;
+    let err_msg = String::read(resp)?;
+    let status = read_i32(resp)?; // sent by servers since v1.1.0
+
+    Ok(Error::Handshake {
+        server_version: (major_v, minor_v, patch_v),
+        code: StatusCode::from(status),
+        message: err_msg.unwrap_or_default(),
+    })
 }
@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::api::cache_config::ClientIntResp;
+use crate::api::ignite_set::{
+    ResourceCloseReq, SetIdentity, SetIteratorGetPageReq, SetIteratorPageResp, SetIteratorStartReq,
+    SetIteratorStartResp, SetValueReq, SetValuesReq,
+};
+use crate::api::key_value::CacheBoolResp;
+use crate::api::OpCode;
+use crate::cache::{AtomicityMode, CacheMode};
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::{ReadableType, WritableType};
+
+/// Settings of the cache keeping a collection. Applied when the collection is created
+#[derive(Clone, Debug)]
+pub struct CollectionConfiguration {
+    pub atomicity_mode: AtomicityMode,
+    pub cache_mode: CacheMode,
+    /// Number of backups in the partitioned mode
+    pub backups: i32,
+    /// Cache group of the collection, none for the default group
+    pub group_name: Option<String>,
+    /// All the values are kept on one node. Faster for small collections
+    pub collocated: bool,
+}
+
+impl Default for CollectionConfiguration {
+    fn default() -> Self {
+        // the defaults of Java's ClientCollectionConfiguration
+        CollectionConfiguration {
+            atomicity_mode: AtomicityMode::Atomic,
+            cache_mode: CacheMode::Partitioned,
+            backups: 0,
+            group_name: None,
+            collocated: false,
+        }
+    }
+}
+
+/// Cluster-wide set, created by `Client::get_or_create_set`.
+/// The values are compared in their serialized form, so the set can be shared with Java
+/// code as long as both sides serialize the values the same way
+#[derive(Clone)]
+pub struct IgniteSet<T: WritableType + ReadableType> {
+    conn: Arc<Connection>,
+    name: String,
+    cache_id: i32,
+    collocated: bool,
+    phantom: PhantomData<T>,
+}
+
+impl<T: WritableType + ReadableType> IgniteSet<T> {
+    pub(crate) fn new(
+        conn: Arc<Connection>,
+        name: &str,
+        cache_id: i32,
+        collocated: bool,
+    ) -> IgniteSet<T> {
+        IgniteSet {
+            conn,
+            name: name.to_string(),
+            cache_id,
+            collocated,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Adds the value. Returns false if it was already present
+    pub fn add(&self, value: &T) -> Result<bool> {
+        self.value_op(OpCode::SetValueAdd, value)
+    }
+
+    /// Adds the values. Returns true if any of them was absent
+    pub fn add_all(&self, values: &[T]) -> Result<bool> {
+        self.values_op(OpCode::SetValueAddAll, values)
+    }
+
+    /// Removes the value. Returns false if it was absent
+    pub fn remove(&self, value: &T) -> Result<bool> {
+        self.value_op(OpCode::SetValueRemove, value)
+    }
+
+    /// Removes the values. Returns true if any of them was present
+    pub fn remove_all(&self, values: &[T]) -> Result<bool> {
+        self.values_op(OpCode::SetValueRemoveAll, values)
+    }
+
+    /// Checks if the value is present
+    pub fn contains(&self, value: &T) -> Result<bool> {
+        self.value_op(OpCode::SetValueContains, value)
+    }
+
+    /// Checks if all the values are present
+    pub fn contains_all(&self, values: &[T]) -> Result<bool> {
+        self.values_op(OpCode::SetValueContainsAll, values)
+    }
+
+    /// Removes the values not in the provided ones. Returns true if any value was removed
+    pub fn retain_all(&self, values: &[T]) -> Result<bool> {
+        self.values_op(OpCode::SetValueRetainAll, values)
+    }
+
+    /// Returns the number of values
+    pub fn size(&self) -> Result<i32> {
+        let resp: ClientIntResp = self.conn.send_and_read(OpCode::SetSize, self.identity())?;
+        Ok(resp.value)
+    }
+
+    /// Removes all the values
+    pub fn clear(&self) -> Result<()> {
+        self.conn.send(OpCode::SetClear, self.identity())
+    }
+
+    /// Removes the set from the cluster
+    pub fn close(&self) -> Result<()> {
+        self.conn.send(OpCode::SetClose, self.identity())
+    }
+
+    /// Returns false if the set was closed
+    pub fn exists(&self) -> Result<bool> {
+        let resp: CacheBoolResp = self
+            .conn
+            .send_and_read(OpCode::SetExists, self.identity())?;
+        Ok(resp.flag)
+    }
+
+    /// Returns an iterator over the values, fetching them in pages of `page_size`.
+    /// The values changed during the iteration may be missed or returned twice
+    pub fn iter(&self, page_size: i32) -> Result<SetIterator<T>> {
+        let resp: SetIteratorStartResp<T> = self.conn.send_and_read(
+            OpCode::SetIteratorStart,
+            SetIteratorStartReq {
+                set: self.identity(),
+                page_size,
+            },
+        )?;
+        Ok(SetIterator {
+            conn: self.conn.clone(),
+            page_size,
+            values: resp.page.values.into(),
+            resource_id: resp.resource_id,
+        })
+    }
+
+    fn value_op(&self, op_code: OpCode, value: &T) -> Result<bool> {
+        let resp: CacheBoolResp = self.conn.send_and_read(
+            op_code,
+            SetValueReq {
+                set: self.identity(),
+                value,
+            },
+        )?;
+        Ok(resp.flag)
+    }
+
+    fn values_op(&self, op_code: OpCode, values: &[T]) -> Result<bool> {
+        let resp: CacheBoolResp = self.conn.send_and_read(
+            op_code,
+            SetValuesReq {
+                set: self.identity(),
+                values,
+            },
+        )?;
+        Ok(resp.flag)
+    }
+
+    fn identity(&self) -> SetIdentity<'_> {
+        SetIdentity {
+            name: self.name.as_str(),
+            cache_id: self.cache_id,
+            collocated: self.collocated,
+        }
+    }
+}
+
+/// Iterator over the values of an `IgniteSet`, created by `IgniteSet::iter`.
+/// A failed page request is returned as an error and ends the iteration.
+/// The server keeps the iterator until the last page is fetched or this is dropped
+pub struct SetIterator<T: ReadableType> {
+    conn: Arc<Connection>,
+    page_size: i32,
+    values: VecDeque<T>,
+    /// Id of the server iterator, none if there are no more pages
+    resource_id: Option<i64>,
+}
+
+impl<T: ReadableType> Iterator for SetIterator<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.values.is_empty() {
+            let resource_id = self.resource_id?;
+            let page: Result<SetIteratorPageResp<T>> = self.conn.send_and_read(
+                OpCode::SetIteratorGetPage,
+                SetIteratorGetPageReq {
+                    resource_id,
+                    page_size: self.page_size,
+                },
+            );
+            match page {
+                Ok(page) => {
+                    if !page.has_next {
+                        self.resource_id = None;
+                    }
+                    self.values.extend(page.values);
+                }
+                Err(err) => {
+                    self.resource_id = None;
+                    return Some(Err(err));
+                }
+            }
+        }
+        self.values.pop_front().map(Ok)
+    }
+}
+
+impl<T: ReadableType> Drop for SetIterator<T> {
+    fn drop(&mut self) {
+        if let Some(resource_id) = self.resource_id {
+            let _ = self
+                .conn
+                .send(OpCode::ResourceClose, ResourceCloseReq { resource_id });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, StatusCode};
+    use crate::fake_server::FakeServer;
+
+    #[test]
+    fn test_set() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        assert!(client
+            .get_or_create_set::<String>("seen", None)
+            .unwrap()
+            .is_none());
+        let config = CollectionConfiguration {
+            backups: 1,
+            collocated: true,
+            ..CollectionConfiguration::default()
+        };
+        let set = client
+            .get_or_create_set::<String>("seen", Some(&config))
+            .unwrap()
+            .unwrap();
+        assert_eq!(set.name(), "seen");
+
+        let a = "a".to_string();
+        assert!(set.add(&a).unwrap());
+        assert!(!set.add(&a).unwrap());
+        assert!(set.contains(&a).unwrap());
+        let values: Vec<String> = ["b", "c", "d"].iter().map(|v| v.to_string()).collect();
+        assert!(set.add_all(&values).unwrap());
+        assert!(!set.add_all(&values[..1]).unwrap());
+        assert_eq!(set.size().unwrap(), 4);
+        assert!(set.contains_all(&values).unwrap());
+        assert!(set.remove(&a).unwrap());
+        assert!(!set.contains_all(&[a.clone(), values[0].clone()]).unwrap());
+        assert!(set.remove_all(&values[..1]).unwrap());
+        assert!(set.retain_all(&values[2..]).unwrap());
+        assert_eq!(set.size().unwrap(), 1);
+
+        // the existing set is returned without the configuration
+        let same = client
+            .get_or_create_set::<String>("seen", None)
+            .unwrap()
+            .unwrap();
+        assert!(same.contains(&values[2]).unwrap());
+        same.clear().unwrap();
+        assert_eq!(set.size().unwrap(), 0);
+
+        assert!(set.exists().unwrap());
+        set.close().unwrap();
+        assert!(!same.exists().unwrap());
+        match same.add(&a) {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::ResourceDoesNotExist),
+            _ => panic!("closed set is modified"),
+        }
+    }
+
+    #[test]
+    fn test_iterator() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let set = client
+            .get_or_create_set::<i64>("ids", Some(&CollectionConfiguration::default()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(set.iter(10).unwrap().count(), 0);
+        let values: Vec<i64> = (0..7).collect();
+        set.add_all(&values).unwrap();
+
+        let read: Result<Vec<i64>> = set.iter(3).unwrap().collect();
+        assert_eq!(read.unwrap(), values);
+        let read: Result<Vec<i64>> = set.iter(0).unwrap().collect();
+        assert_eq!(read.unwrap(), values);
+
+        // the unfinished iterator is closed on the server
+        let mut iter = set.iter(2).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), 0);
+        let resource_id = iter.resource_id.unwrap();
+        drop(iter);
+        assert!(set
+            .conn
+            .send(OpCode::ResourceClose, ResourceCloseReq { resource_id })
+            .is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::sync::{Arc, Mutex};
+
+use crate::cache::{CachePeekMode, KeyValueCache};
+use crate::error::Result;
+
+/// `KeyValueCache` that keeps the entries in memory, in the insertion order.
+/// Keys and values are compared with `PartialEq`, while Ignite compares their serialized form.
+/// Clones share the entries, like clones of `Cache` share the cluster connection
+pub struct InMemoryCache<K, V> {
+    entries: Arc<Mutex<Vec<(K, V)>>>,
+}
+
+impl<K, V> InMemoryCache<K, V> {
+    pub fn new() -> InMemoryCache<K, V> {
+        InMemoryCache {
+            entries: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl<K, V> Default for InMemoryCache<K, V> {
+    fn default() -> Self {
+        InMemoryCache::new()
+    }
+}
+
+impl<K, V> Clone for InMemoryCache<K, V> {
+    fn clone(&self) -> Self {
+        InMemoryCache {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+fn find<'a, K: PartialEq, V>(entries: &'a mut [(K, V)], key: &K) -> Option<&'a mut V> {
+    entries.iter_mut().find(|e| e.0 == *key).map(|e| &mut e.1)
+}
+
+fn remove<K: PartialEq, V>(entries: &mut Vec<(K, V)>, key: &K) -> Option<V> {
+    let pos = entries.iter().position(|e| e.0 == *key)?;
+    Some(entries.remove(pos).1)
+}
+
+/// Returns the replaced value
+fn put<K: PartialEq + Clone, V: Clone>(entries: &mut Vec<(K, V)>, key: &K, value: &V) -> Option<V> {
+    match find(entries, key) {
+        Some(current) => Some(std::mem::replace(current, value.clone())),
+        None => {
+            entries.push((key.clone(), value.clone()));
+            None
+        }
+    }
+}
+
+impl<K: PartialEq + Clone, V: PartialEq + Clone> KeyValueCache<K, V> for InMemoryCache<K, V> {
+    fn query_scan(&self, page_size: i32) -> Result<Vec<(Option<K>, Option<V>)>> {
+        let entries = self.entries.lock()?;
+        let count = match page_size {
+            size if size > 0 => entries.len().min(size as usize),
+            _ => entries.len(),
+        };
+        Ok(entries[..count]
+            .iter()
+            .map(|(k, v)| (Some(k.clone()), Some(v.clone())))
+            .collect())
+    }
+
+    fn get(&self, key: &K) -> Result<Option<V>> {
+        let mut entries = self.entries.lock()?;
+        Ok(find(&mut entries, key).cloned())
+    }
+
+    fn get_all(&self, keys: &[K]) -> Result<Vec<(Option<K>, Option<V>)>> {
+        let mut entries = self.entries.lock()?;
+        Ok(keys
+            .iter()
+            .filter_map(|k| find(&mut entries, k).map(|v| (Some(k.clone()), Some(v.clone()))))
+            .collect())
+    }
+
+    fn put(&self, key: &K, value: &V) -> Result<()> {
+        put(&mut *self.entries.lock()?, key, value);
+        Ok(())
+    }
+
+    fn put_all(&self, pairs: &[(K, V)]) -> Result<()> {
+        let mut entries = self.entries.lock()?;
+        for (key, value) in pairs {
+            put(&mut entries, key, value);
+        }
+        Ok(())
+    }
+
+    fn contains_key(&self, key: &K) -> Result<bool> {
+        let mut entries = self.entries.lock()?;
+        Ok(find(&mut entries, key).is_some())
+    }
+
+    fn contains_keys(&self, keys: &[K]) -> Result<bool> {
+        let mut entries = self.entries.lock()?;
+        Ok(keys.iter().all(|k| find(&mut entries, k).is_some()))
+    }
+
+    fn get_and_put(&self, key: &K, value: &V) -> Result<Option<V>> {
+        Ok(put(&mut *self.entries.lock()?, key, value))
+    }
+
+    fn get_and_replace(&self, key: &K, value: &V) -> Result<Option<V>> {
+        let mut entries = self.entries.lock()?;
+        Ok(find(&mut entries, key).map(|current| std::mem::replace(current, value.clone())))
+    }
+
+    fn get_and_remove(&self, key: &K) -> Result<Option<V>> {
+        Ok(remove(&mut *self.entries.lock()?, key))
+    }
+
+    fn put_if_absent(&self, key: &K, value: &V) -> Result<bool> {
+        Ok(self.get_and_put_if_absent(key, value)?.is_none())
+    }
+
+    fn get_and_put_if_absent(&self, key: &K, value: &V) -> Result<Option<V>> {
+        let mut entries = self.entries.lock()?;
+        match find(&mut entries, key) {
+            Some(current) => Ok(Some(current.clone())),
+            None => {
+                entries.push((key.clone(), value.clone()));
+                Ok(None)
+            }
+        }
+    }
+
+    fn replace(&self, key: &K, value: &V) -> Result<bool> {
+        Ok(self.get_and_replace(key, value)?.is_some())
+    }
+
+    fn replace_if_equals(&self, key: &K, old: &V, new: &V) -> Result<bool> {
+        let mut entries = self.entries.lock()?;
+        match find(&mut entries, key) {
+            Some(current) if *current == *old => {
+                *current = new.clone();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.entries.lock()?.clear();
+        Ok(())
+    }
+
+    fn clear_key(&self, key: &K) -> Result<()> {
+        remove(&mut *self.entries.lock()?, key);
+        Ok(())
+    }
+
+    fn clear_keys(&self, keys: &[K]) -> Result<()> {
+        let mut entries = self.entries.lock()?;
+        for key in keys {
+            remove(&mut entries, key);
+        }
+        Ok(())
+    }
+
+    fn remove_key(&self, key: &K) -> Result<bool> {
+        Ok(remove(&mut *self.entries.lock()?, key).is_some())
+    }
+
+    fn remove_if_equals(&self, key: &K, value: &V) -> Result<bool> {
+        let mut entries = self.entries.lock()?;
+        match find(&mut entries, key) {
+            Some(current) if *current == *value => {
+                remove(&mut entries, key);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn get_size(&self) -> Result<i64> {
+        Ok(self.entries.lock()?.len() as i64)
+    }
+
+    /// There are no near or backup copies, so all the modes count every entry
+    fn get_size_peek_mode(&self, _mode: CachePeekMode) -> Result<i64> {
+        self.get_size()
+    }
+
+    fn get_size_peek_modes(&self, _modes: Vec<CachePeekMode>) -> Result<i64> {
+        self.get_size()
+    }
+
+    fn remove_keys(&self, keys: &[K]) -> Result<()> {
+        self.clear_keys(keys)
+    }
+
+    fn remove_all(&self) -> Result<()> {
+        self.clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_server::FakeServer;
+    use crate::Ignite;
+
+    /// Runs all the operations and returns their results
+    fn exercise(cache: &impl KeyValueCache<String, i32>) -> Result<Vec<String>> {
+        let (a, b, c) = ("a".to_string(), "b".to_string(), "c".to_string());
+        let mut log = Vec::new();
+        log.push(format!("{:?}", cache.get(&a)?));
+        cache.put(&a, &1)?;
+        cache.put_all(&[(b.clone(), 2), (c.clone(), 3)])?;
+        log.push(format!("{:?}", cache.query_scan(2)?));
+        log.push(format!(
+            "{:?}",
+            cache.get_all(&[c.clone(), "x".to_string()])?
+        ));
+        log.push(format!("{:?}", cache.put_if_absent(&a, &10)?));
+        log.push(format!("{:?}", cache.get_and_put_if_absent(&a, &10)?));
+        log.push(format!("{:?}", cache.get_and_put(&a, &11)?));
+        log.push(format!(
+            "{:?}",
+            cache.get_and_replace(&"x".to_string(), &0)?
+        ));
+        log.push(format!("{:?}", cache.replace(&b, &20)?));
+        log.push(format!("{:?}", cache.replace_if_equals(&b, &2, &21)?));
+        log.push(format!("{:?}", cache.replace_if_equals(&b, &20, &22)?));
+        log.push(format!(
+            "{:?}",
+            cache.contains_keys(&[a.clone(), b.clone()])?
+        ));
+        log.push(format!("{:?}", cache.remove_if_equals(&c, &4)?));
+        log.push(format!("{:?}", cache.get_and_remove(&c)?));
+        log.push(format!("{:?}", cache.contains_key(&c)?));
+        log.push(format!(
+            "{:?}",
+            cache.get_size_peek_mode(CachePeekMode::All)?
+        ));
+        cache.clear_key(&a)?;
+        log.push(format!("{:?}", cache.remove_key(&b)?));
+        log.push(format!("{:?}", cache.get_size()?));
+        Ok(log)
+    }
+
+    #[test]
+    fn test_same_as_cache() {
+        let in_memory = exercise(&InMemoryCache::new()).unwrap();
+        assert_eq!(
+            in_memory[1],
+            r#"[(Some("a"), Some(1)), (Some("b"), Some(2))]"#
+        );
+        assert_eq!(in_memory.last().unwrap(), "0");
+
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let cache = client.get_or_create_cache::<String, i32>("cache").unwrap();
+        assert_eq!(exercise(&cache).unwrap(), in_memory);
+    }
+
+    #[test]
+    fn test_clones_share_entries() {
+        let cache = InMemoryCache::new();
+        cache.clone().put(&1, &"one").unwrap();
+        assert_eq!(cache.get(&1).unwrap(), Some("one"));
+        cache.clone().remove_all().unwrap();
+        assert_eq!(cache.get_size().unwrap(), 0);
+    }
+}
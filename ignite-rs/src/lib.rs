@@ -1,34 +1,63 @@
 extern crate core;
 
+use crate::api::atomic_long::AtomicLongCreateReq;
+use crate::api::binary_type::{BinaryTypeGetReq, BinaryTypeGetResp};
 use crate::api::cache_config::{
     CacheCreateWithConfigReq, CacheCreateWithNameReq, CacheDestroyReq, CacheGetConfigReq,
     CacheGetConfigResp, CacheGetNamesReq, CacheGetNamesResp, CacheGetOrCreateWithConfigReq,
     CacheGetOrCreateWithNameReq, ClientIntResp, TxnEndReq, TxnStartReq,
 };
+use crate::api::cluster::{
+    ClusterChangeStateReq, ClusterChangeWalStateReq, ClusterGetStateReq, ClusterGetStateResp,
+    ClusterGetWalStateReq, ClusterGroupGetNodeEndpointsReq, ClusterGroupGetNodeEndpointsResp,
+};
+use crate::api::ignite_set::{SetGetOrCreateReq, SetGetOrCreateResp};
+use crate::api::key_value::CacheBoolResp;
 use crate::api::OpCode;
 
+use crate::atomic_long::{AtomicConfiguration, AtomicLong};
 use crate::cache::{Cache, CacheConfiguration};
+use crate::cluster::{ClusterGroup, ClusterState, NodeEndpoints};
+use crate::compute::Compute;
 use crate::connection::Connection;
 use crate::error::Result;
+use crate::ignite_set::{CollectionConfiguration, IgniteSet};
+use crate::protocol::binary_object::BinaryType;
 use crate::protocol::{read_wrapped_data, TypeCode};
+use crate::service::Services;
 use crate::utils::string_to_java_hashcode;
 
 use std::io;
 use std::io::{Read, Write};
+use std::path::PathBuf;
 use std::sync::Arc;
 
+use num_bigint::BigInt;
 #[cfg(feature = "ssl")]
 use rustls;
 use std::time::Duration;
 
 mod api;
+pub mod atomic_long;
 pub mod cache;
+pub mod cluster;
+pub mod compute;
 mod connection;
+pub mod continuous_query;
+pub mod data_streamer;
 pub mod error;
+#[cfg(any(test, feature = "fake-server"))]
+pub mod fake_server;
 mod handshake;
+pub mod ignite_set;
+pub mod in_memory;
 pub mod protocol;
+pub mod recording;
+pub mod service;
 pub mod utils;
 
+pub use num_bigint;
+
 /// Implementations of this trait could be serialized into Ignite byte sequence
 /// It is indented to be implemented by structs which represents requests
 pub(crate) trait WriteableReq {
@@ -58,11 +87,14 @@ pub trait ReadableType: Sized {
 }
 
 /// Combines the WritableType and ReadableType crates.
-/// Intended to be used in the #[derive(IgniteObj)] attribute to automatically generate
-/// serialization/deserialization for the user-defined structs
+/// Vec<T> of such types is packed as Java's ArrayList.
+/// Implemented by the #[derive(IgniteObj)] attribute only if the struct has
+/// #[ignite(collection)], so existing manual implementations don't conflict.
+/// Vectors of primitives and Strings are still packed as arrays
 ///
 /// use ignite_rs_derive::IgniteObj;
 /// #[derive(IgniteObj)]
+/// #[ignite(collection)]
 /// struct MyType {
 ///     bar: String,
 ///     foo: i32,
@@ -84,6 +116,10 @@ pub struct ClientConfig {
     pub tcp_ttl: Option<u32>,
     pub tcp_read_buff_size: Option<usize>,
     pub tcp_write_buff_size: Option<usize>,
+    /// Max size of a response in bytes. Bigger responses are rejected before being read
+    pub max_message_size: Option<usize>,
+    /// File to record all the sent and received frames to. See the `recording` module
+    pub session_record_path: Option<PathBuf>,
     #[cfg(feature = "ssl")]
     pub tls_conf: (rustls::ClientConfig, String),
 }
@@ -102,6 +138,8 @@ impl ClientConfig {
             tcp_ttl: None,
             tcp_read_buff_size: None,
             tcp_write_buff_size: None,
+            max_message_size: None,
+            session_record_path: None,
         }
     }
 
@@ -118,6 +156,8 @@ impl ClientConfig {
             tcp_ttl: None,
             tcp_read_buff_size: None,
             tcp_write_buff_size: None,
+            max_message_size: None,
+            session_record_path: None,
             tls_conf: (client_conf, hostname),
         }
     }
@@ -176,7 +216,10 @@ pub trait Ignite {
 }
 
 /// Basic Ignite Client
-/// Uses single blocking TCP connection
+/// Uses single blocking TCP connection, shared by the clones of the client.
+/// An I/O error in the middle of a request leaves the connection broken: this and all
+/// the following requests of the client and its clones fail with an error.
+/// The client does not reconnect, create a new one with `new_client` instead
 pub struct Client {
     _conf: ClientConfig,
     conn: Arc<Connection>,
@@ -315,7 +358,114 @@ impl Ignite for Client {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+impl Client {
+    /// Returns the current state of the cluster
+    pub fn get_cluster_state(&mut self) -> Result<ClusterState> {
+        let resp: ClusterGetStateResp = self
+            .conn
+            .send_and_read(OpCode::ClusterGetState, ClusterGetStateReq {})?;
+        Ok(resp.state)
+    }
+
+    /// Changes the state of the cluster, e.g. activates a freshly started persistent cluster
+    pub fn change_cluster_state(&mut self, state: ClusterState) -> Result<()> {
+        self.conn
+            .send(OpCode::ClusterChangeState, ClusterChangeStateReq { state })
+    }
+
+    /// Enables or disables the write-ahead log of the cache. Disabling it speeds up bulk loads,
+    /// but the cache data is lost if a node fails before the WAL is enabled back.
+    /// Returns false if the WAL was already in the requested state
+    pub fn set_wal_enabled(&mut self, cache_name: &str, enabled: bool) -> Result<bool> {
+        let resp: CacheBoolResp = self.conn.send_and_read(
+            OpCode::ClusterChangeWalState,
+            ClusterChangeWalStateReq {
+                cache_name,
+                enabled,
+            },
+        )?;
+        Ok(resp.flag)
+    }
+
+    /// Returns true if the write-ahead log of the cache is enabled
+    pub fn is_wal_enabled(&mut self, cache_name: &str) -> Result<bool> {
+        let resp: CacheBoolResp = self.conn.send_and_read(
+            OpCode::ClusterGetWalState,
+            ClusterGetWalStateReq { cache_name },
+        )?;
+        Ok(resp.flag)
+    }
+
+    /// Returns the group of all the cluster nodes
+    pub fn cluster_group(&mut self) -> ClusterGroup {
+        ClusterGroup::new(self.conn.clone())
+    }
+
+    /// Returns the thin client endpoints of all the server nodes.
+    /// Any of them could be used as `ClientConfig::addr`
+    pub fn get_node_endpoints(&mut self) -> Result<Vec<NodeEndpoints>> {
+        let resp: ClusterGroupGetNodeEndpointsResp = self.conn.send_and_read(
+            OpCode::ClusterGroupGetNodeEndpoints,
+            ClusterGroupGetNodeEndpointsReq {},
+        )?;
+        Ok(resp.endpoints)
+    }
+
+    /// Returns the metadata of the complex object type, none if the type is not registered
+    pub fn get_binary_type(&mut self, type_id: i32) -> Result<Option<BinaryType>> {
+        let resp: BinaryTypeGetResp = self
+            .conn
+            .send_and_read(OpCode::GetBinaryType, BinaryTypeGetReq { type_id })?;
+        Ok(resp.binary_type)
+    }
+
+    /// Returns the compute API running tasks on all the server nodes
+    pub fn compute(&mut self) -> Compute {
+        Compute::new(self.conn.clone())
+    }
+
+    /// Returns the API invoking the services deployed in the cluster
+    pub fn services(&mut self) -> Services {
+        Services::new(self.conn.clone())
+    }
+
+    /// Returns the cluster-wide long value with the name, creating it if it does not exist.
+    /// The initial value and the configuration are used only for the creation
+    pub fn atomic_long(
+        &mut self,
+        name: &str,
+        initial_value: i64,
+        config: Option<&AtomicConfiguration>,
+    ) -> Result<AtomicLong> {
+        self.conn.send(
+            OpCode::AtomicLongCreate,
+            AtomicLongCreateReq {
+                name,
+                initial_value,
+                config,
+            },
+        )?;
+        let group_name = config.and_then(|config| config.group_name.clone());
+        Ok(AtomicLong::new(self.conn.clone(), name, group_name))
+    }
+
+    /// Returns the cluster-wide set with the name. The set is created with the configuration
+    /// if it does not exist. Without the configuration returns none for a missing set
+    pub fn get_or_create_set<T: WritableType + ReadableType>(
+        &mut self,
+        name: &str,
+        config: Option<&CollectionConfiguration>,
+    ) -> Result<Option<IgniteSet<T>>> {
+        let resp: SetGetOrCreateResp = self
+            .conn
+            .send_and_read(OpCode::SetGetOrCreate, SetGetOrCreateReq { name, config })?;
+        Ok(resp.set.map(|(collocated, cache_id)| {
+            IgniteSet::new(self.conn.clone(), name, cache_id, collocated)
+        }))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 ///Value of an enumerable type. For such types defined only a finite number of named values.
 pub struct Enum {
@@ -324,3 +474,45 @@ pub struct Enum {
     /// Enumeration value ordinal.
     pub ordinal: i32,
 }
+
+/// Universally unique identifier. Matches Java's `java.util.UUID`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Uuid {
+    /// Most significant 64 bits.
+    pub most_sig_bits: i64,
+    /// Least significant 64 bits.
+    pub least_sig_bits: i64,
+}
+
+impl From<u128> for Uuid {
+    fn from(value: u128) -> Self {
+        Uuid {
+            most_sig_bits: (value >> 64) as i64,
+            least_sig_bits: value as i64,
+        }
+    }
+}
+
+impl From<Uuid> for u128 {
+    fn from(value: Uuid) -> Self {
+        ((value.most_sig_bits as u64 as u128) << 64) | value.least_sig_bits as u64 as u128
+    }
+}
+
+/// Date without nanoseconds. Matches Java's `java.util.Date`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Date {
+    /// Number of milliseconds elapsed since 00:00:00 1 Jan 1970 UTC.
+    pub millis: i64,
+}
+
+/// Arbitrary-precision decimal number. Matches Java's `java.math.BigDecimal`:
+/// the value is `unscaled * 10^(-scale)`.
+/// Like in Java, numbers with different scales are not equal (`1.0 != 1.00`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    /// Unscaled value.
+    pub unscaled: BigInt,
+    /// Number of digits to the right of the decimal point. May be negative.
+    pub scale: i32,
+}
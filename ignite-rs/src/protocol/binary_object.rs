@@ -0,0 +1,416 @@
+use std::convert::TryFrom;
+use std::io;
+use std::io::{Cursor, Read, Write};
+
+use crate::error::{Error, Result};
+use crate::protocol::complex_obj::{read_value, IgniteValue};
+use crate::protocol::{
+    read_bytes, read_i32, read_u16, read_u8, TypeCode, COMPLEX_OBJ_HEADER_LEN, FLAG_COMPACT_FOOTER,
+    FLAG_HAS_SCHEMA, FLAG_OFFSET_ONE_BYTE, FLAG_OFFSET_TWO_BYTES, HAS_RAW_DATA,
+};
+use crate::utils::string_to_java_hashcode;
+use crate::{ReadableType, WritableType};
+
+// https://ignite.apache.org/docs/latest/binary-client-protocol/data-format#complex-object
+const FLAGS_OFFSET: u64 = 2;
+const TYPE_ID_OFFSET: u64 = 4;
+const LENGTH_OFFSET: usize = 12;
+const SCHEMA_ID_OFFSET: u64 = 16;
+const SCHEMA_OFFSET_OFFSET: u64 = 20;
+
+/// Metadata of a complex object type, as returned by `Client::get_binary_type`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryType {
+    pub type_id: i32,
+    pub type_name: String,
+    pub affinity_key_field: Option<String>,
+    pub fields: Vec<BinaryField>,
+    pub is_enum: bool,
+    /// Names and ordinals of the enum values, empty for the other types
+    pub enum_values: Vec<(String, i32)>,
+    /// Schema ids with the field ids in the order of the object footer
+    pub schemas: Vec<(i32, Vec<i32>)>,
+}
+
+/// Field of a binary type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryField {
+    pub name: String,
+    /// Type code of the field values
+    pub type_id: i32,
+    pub field_id: i32,
+}
+
+/// Data object kept in its serialized form, as returned by `Cache::with_keep_binary`.
+/// It is written back byte by byte, so the objects can be moved between caches
+/// without knowing their types. The fields of complex objects are read one by one
+/// with `field`, the whole object is read into a Rust type with `deserialize`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryObject {
+    /// The data object starting with its type code
+    bytes: Vec<u8>,
+}
+
+impl BinaryObject {
+    /// Serializes the value, e.g. to look up a key in a binary cache
+    pub fn serialize<T: WritableType>(value: &T) -> Result<BinaryObject> {
+        let mut bytes = Vec::with_capacity(value.size());
+        value.write(&mut bytes)?;
+        BinaryObject::read(&mut Cursor::new(bytes))?
+            .ok_or_else(|| Error::from("NULL is not a binary object"))
+    }
+
+    /// Reads the object into a Rust type
+    pub fn deserialize<T: ReadableType>(&self) -> Result<T> {
+        T::read(&mut Cursor::new(self.bytes.as_slice()))?
+            .ok_or_else(|| Error::from("Binary object is read as NULL"))
+    }
+
+    /// Serialized data object, the type code included
+    pub fn bytes(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    /// Type id of a complex object, none for the other data objects
+    pub fn type_id(&self) -> Option<i32> {
+        if !self.is_complex() {
+            return None;
+        }
+        let mut cur = Cursor::new(self.bytes.as_slice());
+        cur.set_position(TYPE_ID_OFFSET);
+        read_i32(&mut cur).ok()
+    }
+
+    /// Reads the field of a complex object. Returns none if the object has no such field.
+    /// Fields are found by their ids, the lowercase names hashed as Ignite does by default.
+    /// Objects written with the compact footer carry no field ids, their fields
+    /// are read with `field_of_type`
+    pub fn field(&self, name: &str) -> Result<Option<IgniteValue>> {
+        let field_id = string_to_java_hashcode(name.to_lowercase().as_str());
+        self.find_field(field_id, None)
+    }
+
+    /// Reads the field of a complex object of the binary type, see `Client::get_binary_type`.
+    /// The field is found by its name as registered in the type. The ids of the fields
+    /// in a compact footer are taken from the schema of the type
+    pub fn field_of_type(
+        &self,
+        name: &str,
+        binary_type: &BinaryType,
+    ) -> Result<Option<IgniteValue>> {
+        if self.type_id() != Some(binary_type.type_id) {
+            return Err(Error::from(
+                format!("Object is not of the binary type {}", binary_type.type_name).as_str(),
+            ));
+        }
+        match binary_type.fields.iter().find(|field| field.name == name) {
+            Some(field) => self.find_field(field.field_id, Some(binary_type)),
+            None => Ok(None),
+        }
+    }
+
+    fn find_field(
+        &self,
+        field_id: i32,
+        binary_type: Option<&BinaryType>,
+    ) -> Result<Option<IgniteValue>> {
+        if !self.is_complex() {
+            return Err(Error::from("Only complex objects have fields"));
+        }
+        let mut cur = Cursor::new(self.bytes.as_slice());
+        cur.set_position(FLAGS_OFFSET);
+        let flags = read_u16(&mut cur)?;
+        if flags & FLAG_HAS_SCHEMA == 0 {
+            return Ok(None);
+        }
+        // a compact footer has the offsets only, in the order of the schema fields
+        let compact_ids = match (flags & FLAG_COMPACT_FOOTER != 0, binary_type) {
+            (false, _) => None,
+            (true, None) => {
+                return Err(Error::from(
+                    "Compact footer needs the binary type to resolve the fields",
+                ))
+            }
+            (true, Some(binary_type)) => {
+                cur.set_position(SCHEMA_ID_OFFSET);
+                let schema_id = read_i32(&mut cur)?;
+                let schema = binary_type.schemas.iter().find(|(id, _)| *id == schema_id);
+                match schema {
+                    Some((_, field_ids)) => Some(field_ids),
+                    None => {
+                        return Err(Error::from(
+                            format!("Schema {} is not in the binary type", schema_id).as_str(),
+                        ))
+                    }
+                }
+            }
+        };
+        let offset_size = match (
+            flags & FLAG_OFFSET_ONE_BYTE != 0,
+            flags & FLAG_OFFSET_TWO_BYTES != 0,
+        ) {
+            (true, false) => 1,
+            (false, true) => 2,
+            (false, false) => 4,
+            (true, true) => return Err(Error::from("Invalid offset flags")),
+        };
+        // the raw data offset follows the schema
+        let schema_end = match flags & HAS_RAW_DATA {
+            0 => self.bytes.len(),
+            _ => self.bytes.len().saturating_sub(4),
+        };
+
+        let entry_size = match compact_ids {
+            Some(_) => offset_size,
+            None => 4 + offset_size,
+        };
+
+        cur.set_position(SCHEMA_OFFSET_OFFSET);
+        let schema_offset = read_i32(&mut cur)?.max(0);
+        cur.set_position(schema_offset as u64);
+        let mut index = 0;
+        while cur.position() as usize + entry_size <= schema_end {
+            let id = match compact_ids {
+                Some(field_ids) => *field_ids
+                    .get(index)
+                    .ok_or_else(|| Error::from("Schema has fewer fields than the footer"))?,
+                None => read_i32(&mut cur)?,
+            };
+            index += 1;
+            let offset = match offset_size {
+                1 => read_u8(&mut cur)? as u64,
+                2 => read_u16(&mut cur)? as u64,
+                _ => read_i32(&mut cur)?.max(0) as u64,
+            };
+            if id == field_id {
+                cur.set_position(offset);
+                let type_code = TypeCode::try_from(read_u8(&mut cur)?)?;
+                return read_value(type_code, &mut cur).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn is_complex(&self) -> bool {
+        self.bytes.first() == Some(&(TypeCode::ComplexObj as u8))
+    }
+}
+
+impl ReadableType for BinaryObject {
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        let bytes = match type_code {
+            TypeCode::Null => return Ok(None),
+            // complex objects are copied as they are, their fields are read on demand
+            TypeCode::ComplexObj => {
+                let mut bytes = vec![TypeCode::ComplexObj as u8];
+                bytes.extend(read_bytes(reader, COMPLEX_OBJ_HEADER_LEN as usize - 1)?);
+                let len = read_i32(&mut &bytes[LENGTH_OFFSET..])?;
+                if len < COMPLEX_OBJ_HEADER_LEN {
+                    return Err(Error::Decode {
+                        type_code: TypeCode::ComplexObj as u8,
+                        offset: LENGTH_OFFSET,
+                        desc: format!("Invalid object length: {}", len),
+                    });
+                }
+                bytes.extend(read_bytes(reader, (len - COMPLEX_OBJ_HEADER_LEN) as usize)?);
+                bytes
+            }
+            _ => {
+                let code = type_code as u8;
+                let mut tee = Tee {
+                    inner: reader,
+                    bytes: vec![code],
+                };
+                read_value(TypeCode::try_from(code)?, &mut tee)?;
+                tee.bytes
+            }
+        };
+        Ok(Some(BinaryObject { bytes }))
+    }
+}
+
+impl WritableType for BinaryObject {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        writer.write_all(&self.bytes)
+    }
+
+    fn size(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+/// Keeps a copy of the bytes read from the inner reader
+struct Tee<'a, R: Read> {
+    inner: &'a mut R,
+    bytes: Vec<u8>,
+}
+
+impl<R: Read> Read for Tee<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.bytes.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_server::FakeServer;
+    use crate::protocol::complex_obj::{
+        ComplexObject, ComplexObjectSchema, IgniteField, IgniteType,
+    };
+    use crate::Ignite;
+    use std::sync::Arc;
+
+    fn point(x: i32, name: &str) -> ComplexObject {
+        ComplexObject {
+            schema: Arc::new(ComplexObjectSchema {
+                type_name: "Point".to_string(),
+                fields: vec![
+                    IgniteField {
+                        name: "X".to_string(),
+                        r#type: IgniteType::Int,
+                    },
+                    IgniteField {
+                        name: "NAME".to_string(),
+                        r#type: IgniteType::String,
+                    },
+                ],
+            }),
+            values: vec![IgniteValue::Int(x), IgniteValue::String(name.to_string())],
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        let obj = BinaryObject::serialize(&point(7, "p")).unwrap();
+        assert_eq!(obj.type_id(), Some(string_to_java_hashcode("point")));
+        assert_eq!(obj.field("x").unwrap(), Some(IgniteValue::Int(7)));
+        assert_eq!(
+            obj.field("Name").unwrap(),
+            Some(IgniteValue::String("p".to_string()))
+        );
+        assert_eq!(obj.field("y").unwrap(), None);
+        let read: ComplexObject = obj.deserialize().unwrap();
+        assert_eq!(read.values, point(7, "p").values);
+
+        let number = BinaryObject::serialize(&5i64).unwrap();
+        assert_eq!(number.bytes(), &[4, 5, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(number.type_id(), None);
+        assert!(number.field("x").is_err());
+        assert_eq!(number.deserialize::<i64>().unwrap(), 5);
+        assert!(BinaryObject::serialize(&Option::<i64>::None).is_err());
+    }
+
+    #[test]
+    fn test_compact_footer() {
+        let obj = BinaryObject::read(&mut Cursor::new(hex_literal::hex!(
+            "67 01" // type code, version
+            "2B 00" // flags: user type, has schema, one byte offsets, compact footer
+            "90 55 5E 06" // type id: "point"
+            "00 00 00 00" // hash code
+            "25 00 00 00" // length
+            "2A 00 00 00" // schema id
+            "23 00 00 00" // schema offset
+            "03 07 00 00 00" // X = 7
+            "09 01 00 00 00 70" // NAME = "p"
+            "18 1D" // footer: offsets only
+        )))
+        .unwrap()
+        .unwrap();
+        assert!(obj.field("x").is_err());
+
+        let field = |name: &str, type_id: i32| BinaryField {
+            name: name.to_string(),
+            type_id,
+            field_id: string_to_java_hashcode(name.to_lowercase().as_str()),
+        };
+        let binary_type = BinaryType {
+            type_id: string_to_java_hashcode("point"),
+            type_name: "Point".to_string(),
+            affinity_key_field: None,
+            fields: vec![field("X", 3), field("NAME", 9)],
+            is_enum: false,
+            enum_values: vec![],
+            schemas: vec![(
+                42,
+                vec![
+                    string_to_java_hashcode("x"),
+                    string_to_java_hashcode("name"),
+                ],
+            )],
+        };
+
+        // both the type and the object come from the server
+        let server = FakeServer::start().unwrap();
+        server.add_binary_type(binary_type.clone());
+        let mut client = server.connect().unwrap();
+        assert_eq!(client.get_binary_type(1).unwrap(), None);
+        let fetched = client
+            .get_binary_type(obj.type_id().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched, binary_type);
+        let cache = client
+            .get_or_create_cache::<i32, ComplexObject>("points")
+            .unwrap()
+            .with_keep_binary();
+        let key = BinaryObject::serialize(&1).unwrap();
+        cache.put(&key, &obj).unwrap();
+        let obj = cache.get(&key).unwrap().unwrap();
+
+        assert_eq!(
+            obj.field_of_type("X", &fetched).unwrap(),
+            Some(IgniteValue::Int(7))
+        );
+        assert_eq!(
+            obj.field_of_type("NAME", &fetched).unwrap(),
+            Some(IgniteValue::String("p".to_string()))
+        );
+        assert_eq!(obj.field_of_type("Y", &fetched).unwrap(), None);
+
+        // another schema of the type
+        let mut other = fetched.clone();
+        other.schemas[0].0 = 43;
+        assert!(obj.field_of_type("X", &other).is_err());
+        other.type_id = 1;
+        assert!(obj.field_of_type("X", &other).is_err());
+    }
+
+    #[test]
+    fn test_keep_binary() {
+        let server = FakeServer::start().unwrap();
+        let mut client = server.connect().unwrap();
+        let source = client
+            .get_or_create_cache::<i32, ComplexObject>("source")
+            .unwrap();
+        let target = client
+            .get_or_create_cache::<i32, ComplexObject>("target")
+            .unwrap();
+        source.put(&1, &point(1, "a")).unwrap();
+        source.put(&2, &point(2, "b")).unwrap();
+
+        // the values are forwarded without being read
+        let (source, binary_target) = (source.with_keep_binary(), target.with_keep_binary());
+        let key = BinaryObject::serialize(&1).unwrap();
+        let value = source.get(&key).unwrap().unwrap();
+        assert_eq!(
+            value.field("name").unwrap(),
+            Some(IgniteValue::String("a".to_string()))
+        );
+        binary_target.put(&key, &value).unwrap();
+        for (key, value) in source.query_scan(10).unwrap() {
+            binary_target
+                .put_if_absent(&key.unwrap(), &value.unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(target.get_size().unwrap(), 2);
+        assert_eq!(
+            target.get(&2).unwrap().unwrap().values,
+            point(2, "b").values
+        );
+        assert_eq!(binary_target.get(&key).unwrap(), Some(value));
+    }
+}
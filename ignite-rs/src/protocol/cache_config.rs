@@ -2,8 +2,8 @@ use std::convert::TryFrom;
 use std::io::{Read, Write};
 
 use crate::cache::{
-    AtomicityMode, CacheMode, IndexType, PartitionLossPolicy, RebalanceMode,
-    WriteSynchronizationMode,
+    AtomicityMode, CacheMode, ExpiryDuration, ExpiryPolicy, IndexType, PartitionLossPolicy,
+    RebalanceMode, WriteSynchronizationMode,
 };
 use crate::cache::{
     CacheConfiguration, CacheKeyConfiguration, QueryEntity, QueryField, QueryIndex,
@@ -11,9 +11,11 @@ use crate::cache::{
 use crate::error::Error;
 use crate::error::Result;
 use crate::protocol::cache_config::ConfigPropertyCode::*;
+#[cfg(any(test, feature = "fake-server"))]
+use crate::protocol::read_i16;
 use crate::protocol::{
     read_bool, read_i32, read_i64, read_object, read_u8, write_bool, write_i16, write_i32,
-    write_i64, write_string_type_code, write_u8,
+    write_i64, write_null, write_string_type_code, write_u8,
 };
 use crate::ReadableType;
 use std::io;
@@ -32,6 +34,7 @@ pub(crate) enum ConfigPropertyCode {
     DataRegionName = 100,
     EagerTtl = 405,
     StatisticsEnabled = 406,
+    ExpiryPolicy = 407,
     GroupName = 400,
     DefaultLockTimeout = 402,
     MaxConcurrentAsyncOps = 403,
@@ -55,9 +58,53 @@ pub(crate) enum ConfigPropertyCode {
     QueryEntities = 200,
 }
 
-impl Into<i16> for ConfigPropertyCode {
-    fn into(self) -> i16 {
-        self as i16
+impl From<ConfigPropertyCode> for i16 {
+    fn from(code: ConfigPropertyCode) -> i16 {
+        code as i16
+    }
+}
+
+#[cfg(any(test, feature = "fake-server"))]
+impl TryFrom<i16> for ConfigPropertyCode {
+    type Error = Error;
+
+    fn try_from(value: i16) -> Result<Self> {
+        match value {
+            0 => Ok(Name),
+            1 => Ok(CacheMode),
+            2 => Ok(CacheAtomicityMode),
+            3 => Ok(Backups),
+            4 => Ok(WriteSynchronizationMode),
+            5 => Ok(CopyOnRead),
+            6 => Ok(ReadFromBackup),
+            100 => Ok(DataRegionName),
+            101 => Ok(IsOnheapCacheEnabled),
+            200 => Ok(QueryEntities),
+            201 => Ok(QueryParallelism),
+            202 => Ok(QueryDetailMetricsSize),
+            203 => Ok(SqlSchema),
+            204 => Ok(SqlIndexInlineMaxSize),
+            205 => Ok(SqlEscapeAll),
+            206 => Ok(MaxQueryIterators),
+            300 => Ok(RebalanceMode),
+            301 => Ok(RebalanceDelay),
+            302 => Ok(RebalanceTimeout),
+            303 => Ok(RebalanceBatchSize),
+            304 => Ok(RebalanceBatchesPrefetchCount),
+            305 => Ok(RebalanceOrder),
+            306 => Ok(RebalanceThrottle),
+            400 => Ok(GroupName),
+            401 => Ok(CacheKeyConfigurations),
+            402 => Ok(DefaultLockTimeout),
+            403 => Ok(MaxConcurrentAsyncOps),
+            404 => Ok(PartitionLossPolicy),
+            405 => Ok(EagerTtl),
+            406 => Ok(StatisticsEnabled),
+            407 => Ok(ExpiryPolicy),
+            _ => Err(Error::from(
+                format!("Unknown cache configuration property {}", value).as_str(),
+            )),
+        }
     }
 }
 
@@ -174,7 +221,12 @@ pub(crate) fn get_cache_configuration_bytes(config: &CacheConfiguration) -> io::
     }
     if let Some(ref v) = config.query_entities {
         write_i16(&mut config_opts, QueryEntities as i16)?;
-        write_query_entities(&mut config_opts, v)?;
+        write_query_entities(&mut config_opts, v, false)?;
+        config_param_len += 1;
+    }
+    if let Some(ref v) = config.expiry_policy {
+        write_i16(&mut config_opts, ExpiryPolicy as i16)?;
+        write_opt_expiry_policy(&mut config_opts, Some(v))?;
         config_param_len += 1;
     }
 
@@ -217,17 +269,169 @@ pub(crate) fn read_cache_configuration(reader: &mut impl Read) -> Result<CacheCo
         sql_schema: String::read(reader)?,
         write_synchronization_mode: WriteSynchronizationMode::try_from(read_i32(reader)?)?,
         cache_key_configurations: Some(read_cache_key_configs(reader)?),
-        query_entities: Some(read_query_entities(reader)?),
+        query_entities: Some(read_query_entities(reader, true)?),
+        expiry_policy: None,
     };
     Ok(config)
 }
 
+/// Reads the property list written by `get_cache_configuration_bytes`.
+/// Properties that are not listed keep their default values
+#[cfg(any(test, feature = "fake-server"))]
+pub(crate) fn read_cache_configuration_bytes(reader: &mut impl Read) -> Result<CacheConfiguration> {
+    let _len = read_i32(reader)?;
+    let count = read_i16(reader)?;
+    let mut config = CacheConfiguration::new("");
+    for _ in 0..count {
+        match ConfigPropertyCode::try_from(read_i16(reader)?)? {
+            Name => config.name = read_non_null_string(reader)?,
+            CacheMode => config.cache_mode = CacheMode::try_from(read_i32(reader)?)?,
+            CacheAtomicityMode => {
+                config.atomicity_mode = AtomicityMode::try_from(read_i32(reader)?)?
+            }
+            Backups => config.num_backup = read_i32(reader)?,
+            WriteSynchronizationMode => {
+                config.write_synchronization_mode =
+                    WriteSynchronizationMode::try_from(read_i32(reader)?)?
+            }
+            CopyOnRead => config.copy_on_read = read_bool(reader)?,
+            DataRegionName => config.data_region_name = String::read(reader)?,
+            EagerTtl => config.eager_ttl = read_bool(reader)?,
+            StatisticsEnabled => config.statistics_enabled = read_bool(reader)?,
+            GroupName => config.group_name = String::read(reader)?,
+            DefaultLockTimeout => config.default_lock_timeout_ms = read_i64(reader)?,
+            MaxConcurrentAsyncOps => config.max_concurrent_async_operations = read_i32(reader)?,
+            MaxQueryIterators => config.max_query_iterators = read_i32(reader)?,
+            IsOnheapCacheEnabled => config.onheap_cache_enabled = read_bool(reader)?,
+            PartitionLossPolicy => {
+                config.partition_loss_policy = PartitionLossPolicy::try_from(read_i32(reader)?)?
+            }
+            QueryDetailMetricsSize => config.query_detail_metrics_size = read_i32(reader)?,
+            QueryParallelism => config.query_parallelism = read_i32(reader)?,
+            ReadFromBackup => config.read_from_backup = read_bool(reader)?,
+            RebalanceBatchSize => config.rebalance_batch_size = read_i32(reader)?,
+            RebalanceBatchesPrefetchCount => {
+                config.rebalance_batches_prefetch_count = read_i64(reader)?
+            }
+            RebalanceDelay => config.rebalance_delay_ms = read_i64(reader)?,
+            RebalanceMode => config.rebalance_mode = RebalanceMode::try_from(read_i32(reader)?)?,
+            RebalanceOrder => config.rebalance_order = read_i32(reader)?,
+            RebalanceThrottle => config.rebalance_throttle_ms = read_i64(reader)?,
+            RebalanceTimeout => config.rebalance_timeout_ms = read_i64(reader)?,
+            SqlEscapeAll => config.sql_escape_all = read_bool(reader)?,
+            SqlIndexInlineMaxSize => config.sql_index_max_size = read_i32(reader)?,
+            SqlSchema => config.sql_schema = String::read(reader)?,
+            CacheKeyConfigurations => {
+                config.cache_key_configurations = Some(read_cache_key_configs(reader)?)
+            }
+            QueryEntities => config.query_entities = Some(read_query_entities(reader, false)?),
+            ExpiryPolicy => config.expiry_policy = read_opt_expiry_policy(reader)?,
+        }
+    }
+    Ok(config)
+}
+
+/// Writes the configuration in the form read by `read_cache_configuration`
+#[cfg(any(test, feature = "fake-server"))]
+pub(crate) fn write_cache_configuration(
+    writer: &mut dyn Write,
+    config: &CacheConfiguration,
+) -> io::Result<()> {
+    write_i32(writer, config.atomicity_mode.clone() as i32)?;
+    write_i32(writer, config.num_backup)?;
+    write_i32(writer, config.cache_mode.clone() as i32)?;
+    write_bool(writer, config.copy_on_read)?;
+    write_opt_string(writer, &config.data_region_name)?;
+    write_bool(writer, config.eager_ttl)?;
+    write_bool(writer, config.statistics_enabled)?;
+    write_opt_string(writer, &config.group_name)?;
+    write_i64(writer, config.default_lock_timeout_ms)?;
+    write_i32(writer, config.max_concurrent_async_operations)?;
+    write_i32(writer, config.max_query_iterators)?;
+    write_string_type_code(writer, config.name.as_str())?;
+    write_bool(writer, config.onheap_cache_enabled)?;
+    write_i32(writer, config.partition_loss_policy.clone() as i32)?;
+    write_i32(writer, config.query_detail_metrics_size)?;
+    write_i32(writer, config.query_parallelism)?;
+    write_bool(writer, config.read_from_backup)?;
+    write_i32(writer, config.rebalance_batch_size)?;
+    write_i64(writer, config.rebalance_batches_prefetch_count)?;
+    write_i64(writer, config.rebalance_delay_ms)?;
+    write_i32(writer, config.rebalance_mode.clone() as i32)?;
+    write_i32(writer, config.rebalance_order)?;
+    write_i64(writer, config.rebalance_throttle_ms)?;
+    write_i64(writer, config.rebalance_timeout_ms)?;
+    write_bool(writer, config.sql_escape_all)?;
+    write_i32(writer, config.sql_index_max_size)?;
+    write_opt_string(writer, &config.sql_schema)?;
+    write_i32(writer, config.write_synchronization_mode.clone() as i32)?;
+    write_cache_key_configs(
+        writer,
+        config.cache_key_configurations.as_deref().unwrap_or(&[]),
+    )?;
+    write_query_entities(
+        writer,
+        config.query_entities.as_deref().unwrap_or(&[]),
+        true,
+    )?;
+    write_opt_expiry_policy(writer, config.expiry_policy.as_ref())?;
+    Ok(())
+}
+
+#[cfg(any(test, feature = "fake-server"))]
+fn write_opt_string(writer: &mut dyn Write, value: &Option<String>) -> io::Result<()> {
+    match value {
+        Some(v) => write_string_type_code(writer, v.as_str()),
+        None => write_null(writer),
+    }
+}
+
+/// Writes the create, update and access durations in milliseconds
+pub(crate) fn write_expiry_policy(writer: &mut dyn Write, policy: &ExpiryPolicy) -> io::Result<()> {
+    write_i64(writer, policy.create.into())?;
+    write_i64(writer, policy.update.into())?;
+    write_i64(writer, policy.access.into())?;
+    Ok(())
+}
+
+pub(crate) fn read_expiry_policy(reader: &mut impl Read) -> Result<ExpiryPolicy> {
+    Ok(ExpiryPolicy {
+        create: ExpiryDuration::try_from(read_i64(reader)?)?,
+        update: ExpiryDuration::try_from(read_i64(reader)?)?,
+        access: ExpiryDuration::try_from(read_i64(reader)?)?,
+    })
+}
+
+/// Configured policy is preceded by a flag telling if it is present
+fn write_opt_expiry_policy(
+    writer: &mut dyn Write,
+    policy: Option<&ExpiryPolicy>,
+) -> io::Result<()> {
+    write_bool(writer, policy.is_some())?;
+    match policy {
+        Some(policy) => write_expiry_policy(writer, policy),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn read_opt_expiry_policy(reader: &mut impl Read) -> Result<Option<ExpiryPolicy>> {
+    match read_bool(reader)? {
+        true => Ok(Some(read_expiry_policy(reader)?)),
+        false => Ok(None),
+    }
+}
+
+/// Reads a String that is not expected to be NULL
+fn read_non_null_string(reader: &mut impl Read) -> Result<String> {
+    String::read(reader)?.ok_or_else(|| Error::from("NULL string is not expected"))
+}
+
 fn read_cache_key_configs(reader: &mut impl Read) -> Result<Vec<CacheKeyConfiguration>> {
     let count = read_i32(reader)?;
     let mut result = Vec::<CacheKeyConfiguration>::new();
     for _ in 0..count {
-        let type_name = String::read(reader)?.unwrap();
-        let affinity_key_field_name = String::read(reader)?.unwrap();
+        let type_name = read_non_null_string(reader)?;
+        let affinity_key_field_name = read_non_null_string(reader)?;
         result.push(CacheKeyConfiguration {
             type_name,
             affinity_key_field_name,
@@ -249,16 +453,18 @@ fn write_cache_key_configs(
     Ok(())
 }
 
-fn read_query_entities(reader: &mut impl Read) -> Result<Vec<QueryEntity>> {
+/// Query fields carry the default value, precision and scale only in the configuration
+/// returned by the server
+fn read_query_entities(reader: &mut impl Read, full: bool) -> Result<Vec<QueryEntity>> {
     let count = read_i32(reader)?;
     let mut result = Vec::<QueryEntity>::new();
     for _ in 0..count {
-        let key_type = String::read(reader)?.unwrap();
-        let value_type = String::read(reader)?.unwrap();
-        let table = String::read(reader)?.unwrap();
+        let key_type = read_non_null_string(reader)?;
+        let value_type = read_non_null_string(reader)?;
+        let table = read_non_null_string(reader)?;
         let key_field = String::read(reader)?.unwrap_or("".to_string());
         let value_field = String::read(reader)?.unwrap_or("".to_string());
-        let query_fields = read_query_fields(reader)?;
+        let query_fields = read_query_fields(reader, full)?;
         let field_aliases = read_query_field_aliases(reader)?;
         let query_indexes = read_query_indexes(reader)?;
         result.push(QueryEntity {
@@ -276,7 +482,11 @@ fn read_query_entities(reader: &mut impl Read) -> Result<Vec<QueryEntity>> {
     Ok(result)
 }
 
-fn write_query_entities(writer: &mut dyn Write, entities: &[QueryEntity]) -> io::Result<()> {
+fn write_query_entities(
+    writer: &mut dyn Write,
+    entities: &[QueryEntity],
+    full: bool,
+) -> io::Result<()> {
     write_i32(writer, entities.len() as i32)?;
     for entity in entities.iter() {
         write_string_type_code(writer, entity.key_type.as_str())?;
@@ -284,7 +494,7 @@ fn write_query_entities(writer: &mut dyn Write, entities: &[QueryEntity]) -> io:
         write_string_type_code(writer, entity.table.as_str())?;
         write_string_type_code(writer, entity.key_field.as_str())?;
         write_string_type_code(writer, entity.value_field.as_str())?;
-        write_query_fields(writer, &entity.query_fields)?;
+        write_query_fields(writer, &entity.query_fields, full)?;
         write_field_aliases(writer, &entity.field_aliases)?;
         write_query_indexes(writer, &entity.query_indexes)?;
     }
@@ -292,7 +502,7 @@ fn write_query_entities(writer: &mut dyn Write, entities: &[QueryEntity]) -> io:
     Ok(())
 }
 
-fn read_query_fields(reader: &mut impl Read) -> Result<Vec<QueryField>> {
+fn read_query_fields(reader: &mut impl Read, full: bool) -> Result<Vec<QueryField>> {
     let count = read_i32(reader)?;
     let mut result = Vec::<QueryField>::new();
     for _ in 0..count {
@@ -300,9 +510,12 @@ fn read_query_fields(reader: &mut impl Read) -> Result<Vec<QueryField>> {
         let type_name = String::read(reader)?.unwrap_or("".to_string());
         let key_field = read_bool(reader)?;
         let not_null_constraint = read_bool(reader)?;
-        let _default_val = read_object(reader)?;
-        let precision = read_i32(reader)?;
-        let scale = read_i32(reader)?;
+        let (precision, scale) = if full {
+            let _default_val = read_object(reader)?;
+            (read_i32(reader)?, read_i32(reader)?)
+        } else {
+            (-1, -1)
+        };
         result.push(QueryField {
             name,
             type_name,
@@ -315,13 +528,18 @@ fn read_query_fields(reader: &mut impl Read) -> Result<Vec<QueryField>> {
     Ok(result)
 }
 
-fn write_query_fields(writer: &mut dyn Write, fields: &[QueryField]) -> io::Result<()> {
+fn write_query_fields(writer: &mut dyn Write, fields: &[QueryField], full: bool) -> io::Result<()> {
     write_i32(writer, fields.len() as i32)?;
     for field in fields.iter() {
         write_string_type_code(writer, field.name.as_str())?;
         write_string_type_code(writer, field.type_name.as_str())?;
         write_bool(writer, field.key_field)?;
         write_bool(writer, field.not_null_constraint)?;
+        if full {
+            write_null(writer)?; // default value
+            write_i32(writer, field.precision)?;
+            write_i32(writer, field.scale)?;
+        }
     }
     Ok(())
 }
@@ -330,8 +548,8 @@ fn read_query_field_aliases(reader: &mut impl Read) -> Result<Vec<(String, Strin
     let count = read_i32(reader)?;
     let mut result = Vec::<(String, String)>::new();
     for _ in 0..count {
-        let name = String::read(reader)?.unwrap();
-        let alias = String::read(reader)?.unwrap();
+        let name = read_non_null_string(reader)?;
+        let alias = read_non_null_string(reader)?;
         result.push((name, alias))
     }
     Ok(result)
@@ -350,7 +568,7 @@ fn read_query_indexes(reader: &mut impl Read) -> Result<Vec<QueryIndex>> {
     let count = read_i32(reader)?;
     let mut result = Vec::<QueryIndex>::new();
     for _ in 0..count {
-        let index_name = String::read(reader)?.unwrap();
+        let index_name = read_non_null_string(reader)?;
         let index_type = IndexType::try_from(read_u8(reader)?)?;
         let inline_size = read_i32(reader)?;
         let fields = read_query_index_fields(reader)?;
@@ -379,7 +597,7 @@ fn read_query_index_fields(reader: &mut impl Read) -> Result<Vec<(String, bool)>
     let count = read_i32(reader)?;
     let mut result = Vec::<(String, bool)>::new();
     for _ in 0..count {
-        let name = String::read(reader)?.unwrap();
+        let name = read_non_null_string(reader)?;
         let is_descending = read_bool(reader)?;
         result.push((name, is_descending))
     }
@@ -1,19 +1,22 @@
 use crate::cache::{QueryEntity, QueryField};
 use crate::error::{Error, Result};
 use crate::protocol::{
-    read_bool, read_i16, read_i32, read_i64, read_i8, read_string, read_u16, read_u8, write_i16,
-    write_i32, write_i64, write_i8, write_null, write_string, write_u16, write_u8, TypeCode,
-    COMPLEX_OBJ_HEADER_LEN, FLAG_COMPACT_FOOTER, FLAG_HAS_SCHEMA, FLAG_OFFSET_ONE_BYTE,
-    FLAG_OFFSET_TWO_BYTES, FLAG_USER_TYPE, HAS_RAW_DATA,
+    prealloc_len, read_bool, read_bytes, read_f32, read_f64, read_i16, read_i32, read_i64, read_i8,
+    read_len, read_primitive_arr, read_string, read_u16, read_u8, write_f32, write_f64, write_i16,
+    write_i32, write_i64, write_i8, write_null, write_string, write_u16, write_u8, CollectionKind,
+    MapKind, TypeCode, COMPLEX_OBJ_HEADER_LEN, FLAG_COMPACT_FOOTER, FLAG_HAS_SCHEMA,
+    FLAG_OFFSET_ONE_BYTE, FLAG_OFFSET_TWO_BYTES, FLAG_USER_TYPE, HAS_RAW_DATA,
 };
 use crate::utils::{bytes_to_java_hashcode, get_schema_id, string_to_java_hashcode};
-use crate::{ReadableType, WritableType};
+use crate::{IgniteObj, ReadableType, WritableType};
 use std::convert::TryFrom;
-use std::io::{Cursor, ErrorKind, Read, Write};
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read, Write};
 use std::mem::size_of;
 use std::sync::Arc;
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+// Eq and Hash are implemented by hand: floats are compared by their bits
+#[derive(Debug, Clone)]
 pub enum IgniteValue {
     String(String),
     Long(i64),
@@ -21,12 +24,127 @@ pub enum IgniteValue {
     Short(i16),
     Byte(i8),
     Bool(bool),
+    Float(f32),
+    Double(f64),
+    Char(u16), // UTF-16 code unit
+
     Timestamp(i64, i32), // milliseconds since 1 Jan 1970 UTC, Nanosecond fraction of a millisecond.
     Decimal(i32, Vec<u8>), // scale, big int value in bytes
     Binary(Vec<u8>),
+    ShortArray(Vec<i16>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+    FloatArray(Vec<f32>),
+    DoubleArray(Vec<f64>),
+    CharArray(Vec<u16>),
+    BoolArray(Vec<bool>),
+    Uuid(i64, i64), // most significant bits, least significant bits
+    Date(i64),      // milliseconds since 1 Jan 1970 UTC
+    Enum(i32, i32), // type id, ordinal
+    StringArray(Vec<IgniteValue>),
+    UuidArray(Vec<IgniteValue>),
+    DateArray(Vec<IgniteValue>),
+    DecimalArray(Vec<IgniteValue>),
+    TimestampArray(Vec<IgniteValue>),
+    EnumArray(i32, Vec<IgniteValue>), // type id of elements, elements
+    ObjectArray(i32, Vec<IgniteValue>), // type id of elements, elements
+    Collection(CollectionKind, Vec<IgniteValue>), // collection kind, elements
+    Map(MapKind, Vec<(IgniteValue, IgniteValue)>), // map kind, key-value pairs
+    Object(NestedObject),             // nested complex object
     Null,
 }
 
+impl PartialEq for IgniteValue {
+    fn eq(&self, other: &Self) -> bool {
+        use IgniteValue::*;
+        match (self, other) {
+            (String(a), String(b)) => a == b,
+            (Long(a), Long(b)) => a == b,
+            (Int(a), Int(b)) => a == b,
+            (Short(a), Short(b)) => a == b,
+            (Byte(a), Byte(b)) => a == b,
+            (Bool(a), Bool(b)) => a == b,
+            (Float(a), Float(b)) => a.to_bits() == b.to_bits(),
+            (Double(a), Double(b)) => a.to_bits() == b.to_bits(),
+            (Char(a), Char(b)) => a == b,
+            (Timestamp(a, a_nanos), Timestamp(b, b_nanos)) => a == b && a_nanos == b_nanos,
+            (Decimal(a_scale, a), Decimal(b_scale, b)) => a_scale == b_scale && a == b,
+            (Binary(a), Binary(b)) => a == b,
+            (ShortArray(a), ShortArray(b)) => a == b,
+            (IntArray(a), IntArray(b)) => a == b,
+            (LongArray(a), LongArray(b)) => a == b,
+            (FloatArray(a), FloatArray(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.to_bits() == b.to_bits())
+            }
+            (DoubleArray(a), DoubleArray(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.to_bits() == b.to_bits())
+            }
+            (CharArray(a), CharArray(b)) => a == b,
+            (BoolArray(a), BoolArray(b)) => a == b,
+            (Uuid(a_most, a_least), Uuid(b_most, b_least)) => {
+                a_most == b_most && a_least == b_least
+            }
+            (Date(a), Date(b)) => a == b,
+            (Enum(a_type, a), Enum(b_type, b)) => a_type == b_type && a == b,
+            (StringArray(a), StringArray(b)) => a == b,
+            (UuidArray(a), UuidArray(b)) => a == b,
+            (DateArray(a), DateArray(b)) => a == b,
+            (DecimalArray(a), DecimalArray(b)) => a == b,
+            (TimestampArray(a), TimestampArray(b)) => a == b,
+            (EnumArray(a_type, a), EnumArray(b_type, b)) => a_type == b_type && a == b,
+            (ObjectArray(a_type, a), ObjectArray(b_type, b)) => a_type == b_type && a == b,
+            (Collection(a_kind, a), Collection(b_kind, b)) => a_kind == b_kind && a == b,
+            (Map(a_kind, a), Map(b_kind, b)) => a_kind == b_kind && a == b,
+            (Object(a), Object(b)) => a == b,
+            (Null, Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for IgniteValue {}
+
+impl Hash for IgniteValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        use IgniteValue::*;
+        std::mem::discriminant(self).hash(state);
+        match self {
+            String(val) => val.hash(state),
+            Long(val) => val.hash(state),
+            Int(val) => val.hash(state),
+            Short(val) => val.hash(state),
+            Byte(val) => val.hash(state),
+            Bool(val) => val.hash(state),
+            Float(val) => val.to_bits().hash(state),
+            Double(val) => val.to_bits().hash(state),
+            Char(val) => val.hash(state),
+            Timestamp(millis, nanos) => (millis, nanos).hash(state),
+            Decimal(scale, data) => (scale, data).hash(state),
+            Binary(data) => data.hash(state),
+            ShortArray(data) => data.hash(state),
+            IntArray(data) => data.hash(state),
+            LongArray(data) => data.hash(state),
+            FloatArray(data) => data.iter().for_each(|val| val.to_bits().hash(state)),
+            DoubleArray(data) => data.iter().for_each(|val| val.to_bits().hash(state)),
+            CharArray(data) => data.hash(state),
+            BoolArray(data) => data.hash(state),
+            Uuid(most_sig_bits, least_sig_bits) => (most_sig_bits, least_sig_bits).hash(state),
+            Date(millis) => millis.hash(state),
+            Enum(type_id, ordinal) => (type_id, ordinal).hash(state),
+            StringArray(items)
+            | UuidArray(items)
+            | DateArray(items)
+            | DecimalArray(items)
+            | TimestampArray(items) => items.hash(state),
+            EnumArray(type_id, items) | ObjectArray(type_id, items) => (type_id, items).hash(state),
+            Collection(kind, items) => (*kind as i8, items).hash(state),
+            Map(kind, entries) => (*kind as i8, entries).hash(state),
+            Object(obj) => obj.hash(state),
+            Null => {}
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum IgniteType {
     String,
@@ -35,9 +153,32 @@ pub enum IgniteType {
     Short,
     Byte,
     Bool,
+    Float,
+    Double,
+    Char,
     Timestamp,
     Decimal(i32, i32), // precision, scale
     Binary,
+    ShortArray,
+    IntArray,
+    LongArray,
+    FloatArray,
+    DoubleArray,
+    CharArray,
+    BoolArray,
+    Uuid,
+    Date,
+    Enum,
+    StringArray,
+    UuidArray,
+    DateArray,
+    DecimalArray,
+    TimestampArray,
+    EnumArray,
+    ObjectArray,
+    Collection,
+    Map,
+    Object,
     Null,
 }
 
@@ -61,6 +202,75 @@ pub struct ComplexObject {
     pub values: Vec<IgniteValue>,
 }
 
+/// Complex object stored in a field, array or collection. The field names are not known
+/// when it is read, so the ids from its header and footer are kept instead, and
+/// the object is written back as it was read
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct NestedObject {
+    pub type_id: i32,
+    pub schema_id: i32,
+    /// Field ids in the order of the values. None for the objects read with a compact footer,
+    /// which has the field offsets only. Such objects are written with a compact footer too,
+    /// so the cluster resolves the fields by the schema id
+    pub field_ids: Option<Vec<i32>>,
+    pub values: Vec<IgniteValue>,
+}
+
+impl From<&ComplexObject> for NestedObject {
+    fn from(obj: &ComplexObject) -> Self {
+        NestedObject {
+            type_id: string_to_java_hashcode(obj.schema.type_name.to_lowercase().as_str()),
+            schema_id: get_schema_id(&obj.schema.fields),
+            field_ids: Some(
+                obj.schema
+                    .fields
+                    .iter()
+                    .map(|f| string_to_java_hashcode(f.name.to_lowercase().as_str()))
+                    .collect(),
+            ),
+            values: obj.values.clone(),
+        }
+    }
+}
+
+impl NestedObject {
+    fn write(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+        let mut flags = FLAG_HAS_SCHEMA | FLAG_USER_TYPE;
+        if let Some(ids) = &self.field_ids {
+            if ids.len() != self.values.len() {
+                return Err(std::io::Error::other(format!(
+                    "{} field ids for {} values",
+                    ids.len(),
+                    self.values.len()
+                )));
+            }
+        } else {
+            flags |= FLAG_COMPACT_FOOTER;
+        }
+        let mut values: Vec<u8> = Vec::new();
+        let mut schema: Vec<u8> = Vec::new();
+        for (i, val) in self.values.iter().enumerate() {
+            if let Some(ids) = &self.field_ids {
+                write_i32(&mut schema, ids[i])?;
+            }
+            write_i32(&mut schema, COMPLEX_OBJ_HEADER_LEN + values.len() as i32)?;
+            write_value(&mut values, val)?;
+        }
+        let len = COMPLEX_OBJ_HEADER_LEN as usize + values.len() + schema.len();
+        write_u8(writer, TypeCode::ComplexObj as u8)?;
+        write_u8(writer, 1)?; // version
+        write_u16(writer, flags)?;
+        write_i32(writer, self.type_id)?;
+        write_i32(writer, bytes_to_java_hashcode(values.as_slice()))?;
+        write_i32(writer, len as i32)?;
+        write_i32(writer, self.schema_id)?;
+        write_i32(writer, COMPLEX_OBJ_HEADER_LEN + values.len() as i32)?; // offset to schema
+        writer.write_all(&values)?;
+        writer.write_all(&schema)?;
+        Ok(())
+    }
+}
+
 impl ComplexObject {
     fn get_data(&self) -> std::io::Result<(Vec<u8>, Vec<u8>)> {
         let mut values: Vec<u8> = Vec::new();
@@ -71,51 +281,7 @@ impl ComplexObject {
                 string_to_java_hashcode(field.name.to_lowercase().as_str()),
             )?;
             write_i32(&mut schema, COMPLEX_OBJ_HEADER_LEN + values.len() as i32)?;
-            match val {
-                IgniteValue::String(val) => {
-                    write_u8(&mut values, TypeCode::String as u8)?;
-                    write_string(&mut values, val)?
-                }
-                IgniteValue::Long(val) => {
-                    write_u8(&mut values, TypeCode::Long as u8)?;
-                    write_i64(&mut values, *val)?;
-                }
-                IgniteValue::Int(val) => {
-                    write_u8(&mut values, TypeCode::Int as u8)?;
-                    write_i32(&mut values, *val)?;
-                }
-                IgniteValue::Short(val) => {
-                    write_u8(&mut values, TypeCode::Short as u8)?;
-                    write_i16(&mut values, *val)?;
-                }
-                IgniteValue::Byte(val) => {
-                    write_u8(&mut values, TypeCode::Byte as u8)?;
-                    write_i8(&mut values, *val)?;
-                }
-                IgniteValue::Bool(val) => {
-                    write_u8(&mut values, TypeCode::Bool as u8)?;
-                    write_u8(&mut values, *val as u8)?;
-                }
-                IgniteValue::Timestamp(big, little) => {
-                    write_u8(&mut values, TypeCode::Timestamp as u8)?;
-                    write_i64(&mut values, *big)?;
-                    write_i32(&mut values, *little)?;
-                }
-                IgniteValue::Decimal(scale, data) => {
-                    write_u8(&mut values, TypeCode::Decimal as u8)?;
-                    write_i32(&mut values, *scale)?;
-                    write_i32(&mut values, data.len() as i32)?;
-                    values.write_all(data)?;
-                }
-                IgniteValue::Binary(data) => {
-                    write_u8(&mut values, TypeCode::ArrByte as u8)?;
-                    write_i32(&mut values, data.len() as i32)?;
-                    values.write_all(data)?;
-                }
-                IgniteValue::Null => {
-                    write_null(&mut values)?;
-                }
-            }
+            write_value(&mut values, val)?;
         }
         Ok((values, schema))
     }
@@ -136,132 +302,420 @@ impl ComplexObject {
     }
 }
 
-impl ReadableType for ComplexObject {
-    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
-        let mut me = ComplexObject {
-            schema: Arc::new(ComplexObjectSchema {
-                type_name: "".to_string(),
-                fields: vec![],
-            }),
-            values: vec![],
-        };
-        match type_code {
-            TypeCode::String => {
-                let str = read_string(reader).unwrap();
-                let field = IgniteValue::String(str);
-                me.values.push(field); // TODO: set type_name to "java.lang.Long"
-            }
-            TypeCode::Long => {
-                let val = read_i64(reader).unwrap();
-                let field = IgniteValue::Long(val);
-                me.values.push(field);
+/// Writes a value of the dynamic model as a data object, i.e. with its type code
+pub(crate) fn write_value(writer: &mut dyn Write, val: &IgniteValue) -> std::io::Result<()> {
+    match val {
+        IgniteValue::String(val) => {
+            write_u8(writer, TypeCode::String as u8)?;
+            write_string(writer, val)?;
+        }
+        IgniteValue::Long(val) => {
+            write_u8(writer, TypeCode::Long as u8)?;
+            write_i64(writer, *val)?;
+        }
+        IgniteValue::Int(val) => {
+            write_u8(writer, TypeCode::Int as u8)?;
+            write_i32(writer, *val)?;
+        }
+        IgniteValue::Short(val) => {
+            write_u8(writer, TypeCode::Short as u8)?;
+            write_i16(writer, *val)?;
+        }
+        IgniteValue::Byte(val) => {
+            write_u8(writer, TypeCode::Byte as u8)?;
+            write_i8(writer, *val)?;
+        }
+        IgniteValue::Bool(val) => {
+            write_u8(writer, TypeCode::Bool as u8)?;
+            write_u8(writer, *val as u8)?;
+        }
+        IgniteValue::Float(val) => {
+            write_u8(writer, TypeCode::Float as u8)?;
+            write_f32(writer, *val)?;
+        }
+        IgniteValue::Double(val) => {
+            write_u8(writer, TypeCode::Double as u8)?;
+            write_f64(writer, *val)?;
+        }
+        IgniteValue::Char(val) => {
+            write_u8(writer, TypeCode::Char as u8)?;
+            write_u16(writer, *val)?;
+        }
+        IgniteValue::Timestamp(big, little) => {
+            write_u8(writer, TypeCode::Timestamp as u8)?;
+            write_i64(writer, *big)?;
+            write_i32(writer, *little)?;
+        }
+        IgniteValue::Decimal(scale, data) => {
+            write_u8(writer, TypeCode::Decimal as u8)?;
+            write_i32(writer, *scale)?;
+            write_i32(writer, data.len() as i32)?;
+            writer.write_all(data)?;
+        }
+        IgniteValue::Binary(data) => {
+            write_u8(writer, TypeCode::ArrByte as u8)?;
+            write_i32(writer, data.len() as i32)?;
+            writer.write_all(data)?;
+        }
+        IgniteValue::ShortArray(data) => data.write(writer)?,
+        IgniteValue::IntArray(data) => data.write(writer)?,
+        IgniteValue::LongArray(data) => data.write(writer)?,
+        IgniteValue::FloatArray(data) => data.write(writer)?,
+        IgniteValue::DoubleArray(data) => data.write(writer)?,
+        IgniteValue::CharArray(data) => data.write(writer)?,
+        IgniteValue::BoolArray(data) => data.write(writer)?,
+        IgniteValue::Uuid(most_sig_bits, least_sig_bits) => {
+            write_u8(writer, TypeCode::Uuid as u8)?;
+            write_i64(writer, *most_sig_bits)?;
+            write_i64(writer, *least_sig_bits)?;
+        }
+        IgniteValue::Date(millis) => {
+            write_u8(writer, TypeCode::Date as u8)?;
+            write_i64(writer, *millis)?;
+        }
+        IgniteValue::Enum(type_id, ordinal) => {
+            write_u8(writer, TypeCode::Enum as u8)?;
+            write_i32(writer, *type_id)?;
+            write_i32(writer, *ordinal)?;
+        }
+        IgniteValue::StringArray(items) => write_value_arr(writer, TypeCode::ArrString, items)?,
+        IgniteValue::UuidArray(items) => write_value_arr(writer, TypeCode::ArrUuid, items)?,
+        IgniteValue::DateArray(items) => write_value_arr(writer, TypeCode::ArrDate, items)?,
+        IgniteValue::DecimalArray(items) => write_value_arr(writer, TypeCode::ArrDecimal, items)?,
+        IgniteValue::TimestampArray(items) => {
+            write_value_arr(writer, TypeCode::TimestampArray, items)?
+        }
+        IgniteValue::EnumArray(type_id, items) => {
+            write_u8(writer, TypeCode::ArrEnum as u8)?;
+            write_i32(writer, *type_id)?;
+            write_values(writer, items)?;
+        }
+        IgniteValue::ObjectArray(type_id, items) => {
+            write_u8(writer, TypeCode::ArrObj as u8)?;
+            write_i32(writer, *type_id)?;
+            write_values(writer, items)?;
+        }
+        IgniteValue::Collection(kind, items) => {
+            write_u8(writer, TypeCode::Collection as u8)?;
+            write_i32(writer, items.len() as i32)?;
+            write_i8(writer, *kind as i8)?;
+            for item in items {
+                write_value(writer, item)?;
             }
-            TypeCode::Int => {
-                let val = read_i32(reader).unwrap();
-                let field = IgniteValue::Int(val);
-                me.values.push(field);
+        }
+        IgniteValue::Map(kind, entries) => {
+            write_u8(writer, TypeCode::Map as u8)?;
+            write_i32(writer, entries.len() as i32)?;
+            write_i8(writer, *kind as i8)?;
+            for (key, value) in entries {
+                write_value(writer, key)?;
+                write_value(writer, value)?;
             }
-            TypeCode::Short => {
-                let val = read_i16(reader).unwrap();
-                let field = IgniteValue::Short(val);
-                me.values.push(field);
+        }
+        IgniteValue::Object(obj) => obj.write(writer)?,
+        IgniteValue::Null => {
+            write_null(writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the array length followed by the elements
+fn write_values(writer: &mut dyn Write, items: &[IgniteValue]) -> std::io::Result<()> {
+    write_i32(writer, items.len() as i32)?;
+    for item in items {
+        write_value(writer, item)?;
+    }
+    Ok(())
+}
+
+fn write_value_arr(
+    writer: &mut dyn Write,
+    type_code: TypeCode,
+    items: &[IgniteValue],
+) -> std::io::Result<()> {
+    write_u8(writer, type_code as u8)?;
+    write_values(writer, items)
+}
+
+/// Reads the array length followed by the elements. Each element is either Null
+/// or has the expected type code. Any type is accepted if none is expected
+fn read_values(reader: &mut impl Read, expected: Option<TypeCode>) -> Result<Vec<IgniteValue>> {
+    let len = read_i32(reader)?;
+    let mut items = Vec::with_capacity(prealloc_len(len));
+    for _ in 0..len {
+        let type_code = TypeCode::try_from(read_u8(reader)?)?;
+        match &expected {
+            Some(code) if type_code != *code && type_code != TypeCode::Null => {
+                return Err(Error::from(
+                    format!("Expected {:?} array element, got {:?}", code, type_code).as_str(),
+                ))
             }
-            TypeCode::Byte => {
-                let val = read_i8(reader).unwrap();
-                let field = IgniteValue::Byte(val);
-                me.values.push(field);
+            _ => items.push(read_value(type_code, reader)?),
+        }
+    }
+    Ok(items)
+}
+
+/// Reads a value of the dynamic model. Type code is expected to be already read
+pub(crate) fn read_value(type_code: TypeCode, reader: &mut impl Read) -> Result<IgniteValue> {
+    let val = match type_code {
+        TypeCode::String => IgniteValue::String(read_string(reader)?),
+        TypeCode::Long => IgniteValue::Long(read_i64(reader)?),
+        TypeCode::Int => IgniteValue::Int(read_i32(reader)?),
+        TypeCode::Short => IgniteValue::Short(read_i16(reader)?),
+        TypeCode::Byte => IgniteValue::Byte(read_i8(reader)?),
+        TypeCode::Bool => IgniteValue::Bool(read_bool(reader)?),
+        TypeCode::Float => IgniteValue::Float(read_f32(reader)?),
+        TypeCode::Double => IgniteValue::Double(read_f64(reader)?),
+        TypeCode::Char => IgniteValue::Char(read_u16(reader)?),
+        TypeCode::ArrShort => IgniteValue::ShortArray(read_primitive_arr(reader, read_i16)?),
+        TypeCode::ArrInt => IgniteValue::IntArray(read_primitive_arr(reader, read_i32)?),
+        TypeCode::ArrLong => IgniteValue::LongArray(read_primitive_arr(reader, read_i64)?),
+        TypeCode::ArrFloat => IgniteValue::FloatArray(read_primitive_arr(reader, read_f32)?),
+        TypeCode::ArrDouble => IgniteValue::DoubleArray(read_primitive_arr(reader, read_f64)?),
+        TypeCode::ArrChar => IgniteValue::CharArray(read_primitive_arr(reader, read_u16)?),
+        TypeCode::ArrBool => IgniteValue::BoolArray(read_primitive_arr(reader, read_bool)?),
+        TypeCode::ComplexObj => IgniteValue::Object(read_complex_obj(reader)?),
+        TypeCode::WrappedData => {
+            // a byte array with the wrapped object and the offset of the object in it
+            let len = read_len(reader)?;
+            let buf = read_bytes(reader, len)?;
+            let offset = read_i32(reader)?;
+            let mut inner = Cursor::new(buf);
+            inner.set_position(offset as u64);
+            let type_code = TypeCode::try_from(read_u8(&mut inner)?)?;
+            read_value(type_code, &mut inner)?
+        }
+        TypeCode::Timestamp => {
+            let big = read_i64(reader)?;
+            let little = read_i32(reader)?;
+            IgniteValue::Timestamp(big, little)
+        }
+        TypeCode::Decimal => {
+            let scale = read_i32(reader)?;
+            let len = read_len(reader)?;
+            let buf = read_bytes(reader, len)?;
+            IgniteValue::Decimal(scale, buf)
+        }
+        TypeCode::Uuid => {
+            let most_sig_bits = read_i64(reader)?;
+            let least_sig_bits = read_i64(reader)?;
+            IgniteValue::Uuid(most_sig_bits, least_sig_bits)
+        }
+        TypeCode::Date => IgniteValue::Date(read_i64(reader)?),
+        TypeCode::Enum => {
+            let type_id = read_i32(reader)?;
+            let ordinal = read_i32(reader)?;
+            IgniteValue::Enum(type_id, ordinal)
+        }
+        TypeCode::ArrString => {
+            IgniteValue::StringArray(read_values(reader, Some(TypeCode::String))?)
+        }
+        TypeCode::ArrUuid => IgniteValue::UuidArray(read_values(reader, Some(TypeCode::Uuid))?),
+        TypeCode::ArrDate => IgniteValue::DateArray(read_values(reader, Some(TypeCode::Date))?),
+        TypeCode::ArrDecimal => {
+            IgniteValue::DecimalArray(read_values(reader, Some(TypeCode::Decimal))?)
+        }
+        TypeCode::TimestampArray => {
+            IgniteValue::TimestampArray(read_values(reader, Some(TypeCode::Timestamp))?)
+        }
+        TypeCode::ArrEnum => {
+            let type_id = read_i32(reader)?;
+            IgniteValue::EnumArray(type_id, read_values(reader, Some(TypeCode::Enum))?)
+        }
+        TypeCode::ArrObj => {
+            let type_id = read_i32(reader)?;
+            IgniteValue::ObjectArray(type_id, read_values(reader, None)?)
+        }
+        TypeCode::Collection => {
+            let len = read_i32(reader)?;
+            let kind = CollectionKind::try_from(read_i8(reader)?)?;
+            let mut items = Vec::with_capacity(prealloc_len(len));
+            for _ in 0..len {
+                items.push(read_value(TypeCode::try_from(read_u8(reader)?)?, reader)?);
             }
-            TypeCode::ComplexObj => {
-                // read header minus type code
-                let mut partial_header = vec![0u8; COMPLEX_OBJ_HEADER_LEN as usize - 1];
-                reader.read_exact(&mut partial_header)?;
-
-                // construct full header
-                let mut data = vec![];
-                write_u8(&mut data, TypeCode::ComplexObj as u8)?;
-                data.extend(partial_header);
-
-                // read values from our reconstructed header
-                let mut header = Cursor::new(&mut data);
-                let _type_code = read_u8(&mut header)?; // offset 0
-                assert_eq!(read_u8(&mut header)?, 1, "Only version 1 supported"); // version
-                let flags = read_u16(&mut header)?; // offset 2
-                let _type_id = read_i32(&mut header)?; // offset 4
-                let _hash_code = read_i32(&mut header)?; // offset 8
-                let object_len = read_i32(&mut header)? as usize; // offset 12
-                let _schema_id = read_i32(&mut header)?; // offset 16
-                let field_indexes_offset = read_i32(&mut header)? as usize; // offset 20
-
-                // compute stuff we need to read body
-                let (one, two) = (
-                    (flags & FLAG_OFFSET_ONE_BYTE) != 0,
-                    (flags & FLAG_OFFSET_TWO_BYTES) != 0,
-                );
-                assert_eq!(flags & HAS_RAW_DATA, 0, "Cannot read raw data");
-                let _compact = flags & FLAG_COMPACT_FOOTER != 0;
-                assert_ne!(flags & FLAG_HAS_SCHEMA, 0, "Schema is required");
-                assert_ne!(flags & FLAG_USER_TYPE, 0, "Only user types are supported");
-                let _offset_sz = match (one, two) {
-                    (true, false) => 1,
-                    (false, true) => 2,
-                    (false, false) => 4,
-                    (true, true) => Err(Error::from("Invalid offset flags"))?,
-                };
-
-                // append body
-                let mut body = vec![0u8; object_len - data.len()];
-                reader.read_exact(&mut body)?;
-                data.extend(body);
-
-                // for acquiring test fixture data
-                // println!("data={:02X?}", data);
-
-                // read field data
-                let mut remainder = Cursor::new(data);
-                remainder.set_position(COMPLEX_OBJ_HEADER_LEN as u64);
-                while (remainder.position() as usize) < field_indexes_offset {
-                    let field_type = TypeCode::try_from(read_u8(&mut remainder)?)?;
-                    let val = match field_type {
-                        TypeCode::String => IgniteValue::String(read_string(&mut remainder)?),
-                        TypeCode::Long => IgniteValue::Long(read_i64(&mut remainder)?),
-                        TypeCode::Int => IgniteValue::Int(read_i32(&mut remainder)?),
-                        TypeCode::Short => IgniteValue::Short(read_i16(&mut remainder)?),
-                        TypeCode::Byte => IgniteValue::Byte(read_i8(&mut remainder)?),
-                        TypeCode::Bool => IgniteValue::Bool(read_bool(&mut remainder)?),
-                        TypeCode::Timestamp => {
-                            let big = read_i64(&mut remainder)?;
-                            let little = read_i32(&mut remainder)?;
-                            IgniteValue::Timestamp(big, little)
-                        }
-                        TypeCode::Decimal => {
-                            let scale = read_i32(&mut remainder)?;
-                            let len = read_i32(&mut remainder)?;
-                            let mut buf = vec![0; len as usize];
-                            remainder.read_exact(&mut buf)?;
-                            IgniteValue::Decimal(scale, buf)
-                        }
-                        TypeCode::Null => IgniteValue::Null,
-                        TypeCode::ArrByte => {
-                            let len = read_i32(&mut remainder)?;
-                            let mut buf = vec![0; len as usize];
-                            remainder.read_exact(&mut buf)?;
-                            IgniteValue::Binary(buf)
-                        }
-                        _ => {
-                            let msg = format!("Unknown type: {:?}", field_type);
-                            Err(Error::from(msg.as_str()))?
-                        }
-                    };
-                    me.values.push(val);
-                }
-                // the remainder of bytes are offsets to fields which we have already read
+            IgniteValue::Collection(kind, items)
+        }
+        TypeCode::Map => {
+            let len = read_i32(reader)?;
+            let kind = MapKind::try_from(read_i8(reader)?)?;
+            let mut entries = Vec::with_capacity(prealloc_len(len));
+            for _ in 0..len {
+                let key = read_value(TypeCode::try_from(read_u8(reader)?)?, reader)?;
+                let value = read_value(TypeCode::try_from(read_u8(reader)?)?, reader)?;
+                entries.push((key, value));
             }
-            _ => todo!("Unsupported type code: {:?}", type_code),
+            IgniteValue::Map(kind, entries)
+        }
+        TypeCode::Null => IgniteValue::Null,
+        TypeCode::ArrByte => {
+            let len = read_len(reader)?;
+            let buf = read_bytes(reader, len)?;
+            IgniteValue::Binary(buf)
+        }
+    };
+    Ok(val)
+}
+
+/// Error in the complex object header at the given offset
+pub(crate) fn decode_err(offset: usize, desc: String) -> Error {
+    Error::Decode {
+        type_code: TypeCode::ComplexObj as u8,
+        offset,
+        desc,
+    }
+}
+
+fn empty_schema() -> Arc<ComplexObjectSchema> {
+    Arc::new(ComplexObjectSchema {
+        type_name: "".to_string(),
+        fields: vec![],
+    })
+}
+
+/// Reads a complex object (type code = 103 is already consumed). Field names are not known
+/// at this point, so the field ids are taken from the footer
+fn read_complex_obj(reader: &mut impl Read) -> Result<NestedObject> {
+    // read header minus type code
+    let mut partial_header = vec![0u8; COMPLEX_OBJ_HEADER_LEN as usize - 1];
+    reader.read_exact(&mut partial_header)?;
+
+    // construct full header
+    let mut data = vec![];
+    write_u8(&mut data, TypeCode::ComplexObj as u8)?;
+    data.extend(partial_header);
+
+    // read values from our reconstructed header
+    let mut header = Cursor::new(&mut data);
+    let _type_code = read_u8(&mut header)?; // offset 0
+    let version = read_u8(&mut header)?; // offset 1
+    if version != 1 {
+        return Err(decode_err(
+            1,
+            format!("Only version 1 supported, got {}", version),
+        ));
+    }
+    let flags = read_u16(&mut header)?; // offset 2
+    let type_id = read_i32(&mut header)?; // offset 4
+    let _hash_code = read_i32(&mut header)?; // offset 8
+    let object_len = read_i32(&mut header)?; // offset 12
+    let schema_id = read_i32(&mut header)?; // offset 16
+    let field_indexes_offset = read_i32(&mut header)?; // offset 20
+    if object_len < COMPLEX_OBJ_HEADER_LEN {
+        return Err(decode_err(
+            12,
+            format!("Invalid object length: {}", object_len),
+        ));
+    }
+    if field_indexes_offset < COMPLEX_OBJ_HEADER_LEN || field_indexes_offset > object_len {
+        return Err(decode_err(
+            20,
+            format!("Invalid schema offset: {}", field_indexes_offset),
+        ));
+    }
+    let (object_len, field_indexes_offset) = (object_len as usize, field_indexes_offset as usize);
+
+    // compute stuff we need to read body
+    let (one, two) = (
+        (flags & FLAG_OFFSET_ONE_BYTE) != 0,
+        (flags & FLAG_OFFSET_TWO_BYTES) != 0,
+    );
+    if flags & HAS_RAW_DATA != 0 {
+        return Err(decode_err(2, "Cannot read raw data".to_string()));
+    }
+    let compact = flags & FLAG_COMPACT_FOOTER != 0;
+    if flags & FLAG_HAS_SCHEMA == 0 {
+        return Err(decode_err(2, "Schema is required".to_string()));
+    }
+    if flags & FLAG_USER_TYPE == 0 {
+        return Err(decode_err(2, "Only user types are supported".to_string()));
+    }
+    let offset_sz = match (one, two) {
+        (true, false) => 1,
+        (false, true) => 2,
+        (false, false) => 4,
+        (true, true) => return Err(decode_err(2, format!("Invalid offset flags: {}", flags))),
+    };
+
+    // append body
+    let body = read_bytes(reader, object_len - data.len())?;
+    data.extend(body);
+
+    // the footer is a list of field ids and offsets, or offsets only if it's compact
+    let entry_sz = if compact { offset_sz } else { 4 + offset_sz };
+    let footer = &data[field_indexes_offset..];
+    if footer.len() % entry_sz != 0 {
+        return Err(decode_err(
+            field_indexes_offset,
+            format!("Footer of {} bytes has a truncated entry", footer.len()),
+        ));
+    }
+    let field_ids = match compact {
+        true => None,
+        false => Some(
+            footer
+                .chunks(entry_sz)
+                .map(|entry| i32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]))
+                .collect::<Vec<_>>(),
+        ),
+    };
+    let field_count = footer.len() / entry_sz;
+
+    // read field data
+    let mut values = vec![];
+    let mut remainder = Cursor::new(data);
+    remainder.set_position(COMPLEX_OBJ_HEADER_LEN as u64);
+    while (remainder.position() as usize) < field_indexes_offset {
+        let offset = remainder.position() as usize;
+        let code = read_u8(&mut remainder)?;
+        let val = TypeCode::try_from(code)
+            .and_then(|field_type| read_value(field_type, &mut remainder))
+            .map_err(|err| Error::Decode {
+                type_code: code,
+                offset,
+                desc: err.to_string(),
+            })?;
+        values.push(val);
+    }
+    // the remainder of bytes are offsets to fields which we have already read
+    if field_count != values.len() {
+        return Err(decode_err(
+            field_indexes_offset,
+            format!(
+                "Footer has {} fields, the object has {} values",
+                field_count,
+                values.len()
+            ),
+        ));
+    }
+    Ok(NestedObject {
+        type_id,
+        schema_id,
+        field_ids,
+        values,
+    })
+}
+
+impl ReadableType for ComplexObject {
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        match type_code {
+            TypeCode::Null => Ok(None),
+            TypeCode::ComplexObj => Ok(Some(ComplexObject {
+                schema: empty_schema(),
+                values: read_complex_obj(reader)?.values,
+            })),
+            // primitives and other data objects are represented as a single-value object
+            _ => Ok(Some(ComplexObject {
+                schema: empty_schema(),
+                values: vec![read_value(type_code, reader)?],
+            })),
         }
-        Ok(Some(me))
     }
 }
 
+impl IgniteObj for ComplexObject {}
+
 impl WritableType for ComplexObject {
     fn write(&self, writer: &mut dyn Write) -> std::io::Result<()> {
         // Handle primitives as ComplexObjects for simplicity
@@ -269,10 +723,10 @@ impl WritableType for ComplexObject {
             let val = self
                 .values
                 .last()
-                .ok_or_else(|| std::io::Error::new(ErrorKind::Other, "No values"))?;
+                .ok_or_else(|| std::io::Error::other("No values"))?;
             let val = match val {
                 IgniteValue::Long(val) => val,
-                _ => Err(std::io::Error::new(ErrorKind::Other, "Mismatched types!"))?,
+                _ => Err(std::io::Error::other("Mismatched types!"))?,
             };
             write_u8(writer, TypeCode::Long as u8)?;
             write_i64(writer, *val)?;
@@ -282,16 +736,26 @@ impl WritableType for ComplexObject {
             let val = self
                 .values
                 .last()
-                .ok_or_else(|| std::io::Error::new(ErrorKind::Other, "No values"))?;
+                .ok_or_else(|| std::io::Error::other("No values"))?;
             let val = match val {
                 IgniteValue::String(val) => val,
-                _ => Err(std::io::Error::new(ErrorKind::Other, "Mismatched types!"))?,
+                _ => Err(std::io::Error::other("Mismatched types!"))?,
             };
             write_u8(writer, TypeCode::String as u8)?;
             write_string(writer, val)?;
             return Ok(());
         }
 
+        // the values are written by the schema fields, so none may be left out
+        if self.values.len() != self.schema.fields.len() {
+            return Err(std::io::Error::other(format!(
+                "{} values for {} fields of type '{}'",
+                self.values.len(),
+                self.schema.fields.len(),
+                self.schema.type_name
+            )));
+        }
+
         // write fields to vec so we can hash
         let (values, schema) = self.get_data()?;
 
@@ -331,10 +795,103 @@ impl WritableType for ComplexObject {
     }
 }
 
+impl ComplexObjectSchema {
+    /// Find the key and value DynamicIgniteTypes for a table.
+    pub fn infer_schemas(
+        entity: &QueryEntity,
+    ) -> Result<(Arc<ComplexObjectSchema>, Arc<ComplexObjectSchema>)> {
+        let key_fields: Vec<_> = entity
+            .query_fields
+            .iter()
+            .filter(|f| f.key_field || entity.key_field == f.name)
+            .collect();
+        let val_fields: Vec<_> = entity
+            .query_fields
+            .iter()
+            .filter(|f| !f.key_field && entity.key_field != f.name)
+            .collect();
+        let key_fields = Self::convert_fields(&key_fields)?;
+        let val_fields = Self::convert_fields(&val_fields)?;
+        let k = ComplexObjectSchema {
+            type_name: entity.key_type.clone(),
+            fields: key_fields,
+        };
+        let v = ComplexObjectSchema {
+            type_name: entity.value_type.clone(),
+            fields: val_fields,
+        };
+        Ok((Arc::new(k), Arc::new(v)))
+    }
+
+    fn convert_fields(qry_fields: &[&QueryField]) -> Result<Vec<IgniteField>> {
+        let mut fields = vec![];
+        for f in qry_fields.iter() {
+            let t: IgniteType = match f.type_name.as_str() {
+                "java.lang.Long" => IgniteType::Long,
+                "java.lang.Short" => IgniteType::Short,
+                "java.lang.Byte" => IgniteType::Byte,
+                "java.lang.String" => IgniteType::String,
+                "java.sql.Timestamp" => IgniteType::Timestamp,
+                "java.lang.Integer" => IgniteType::Int,
+                "java.lang.Boolean" => IgniteType::Bool,
+                "java.lang.Float" => IgniteType::Float,
+                "java.lang.Double" => IgniteType::Double,
+                "java.lang.Character" => IgniteType::Char,
+                "java.lang.Object" => IgniteType::Object,
+                "java.math.BigDecimal" => IgniteType::Decimal(f.precision, f.scale),
+                // '[B' is a JVM quirk -- this can happen for arrays of some
+                // primitive types. Specifically, it is the output of
+                // `System.out.println(byte[].class.getName());`
+                "[B" => IgniteType::Binary,
+                "[S" => IgniteType::ShortArray,
+                "[I" => IgniteType::IntArray,
+                "[J" => IgniteType::LongArray,
+                "[F" => IgniteType::FloatArray,
+                "[D" => IgniteType::DoubleArray,
+                "[C" => IgniteType::CharArray,
+                "[Z" => IgniteType::BoolArray,
+                "java.util.UUID" => IgniteType::Uuid,
+                "java.util.Date" | "java.sql.Date" => IgniteType::Date,
+                // the same JVM naming is used for arrays of objects
+                "[Ljava.lang.String;" => IgniteType::StringArray,
+                "[Ljava.util.UUID;" => IgniteType::UuidArray,
+                "[Ljava.util.Date;" | "[Ljava.sql.Date;" => IgniteType::DateArray,
+                "[Ljava.math.BigDecimal;" => IgniteType::DecimalArray,
+                "[Ljava.sql.Timestamp;" => IgniteType::TimestampArray,
+                "[Ljava.lang.Object;" => IgniteType::ObjectArray,
+                "java.util.Collection"
+                | "java.util.List"
+                | "java.util.ArrayList"
+                | "java.util.LinkedList"
+                | "java.util.Set"
+                | "java.util.HashSet"
+                | "java.util.LinkedHashSet" => IgniteType::Collection,
+                "java.util.Map" | "java.util.HashMap" | "java.util.LinkedHashMap" => {
+                    IgniteType::Map
+                }
+                _ => Err(Error::from(
+                    format!("Unknown field type: {}", f.type_name).as_str(),
+                ))?,
+            };
+            let field = IgniteField {
+                name: f.name.to_string(),
+                r#type: t,
+            };
+            fields.push(field);
+        }
+        Ok(fields)
+    }
+
+    pub fn type_name(&self) -> &str {
+        self.type_name.as_str()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::protocol::complex_obj::ComplexObject;
+    use crate::protocol::read_complex_obj_dyn;
     use std::convert::TryInto;
 
     #[test]
@@ -435,66 +992,372 @@ mod tests {
         let actual_hex = format!("{:02X?}", actual_bytes);
         assert_eq!(actual_hex, expected_hex);
     }
-}
 
-impl ComplexObjectSchema {
-    /// Find the key and value DynamicIgniteTypes for a table.
-    pub fn infer_schemas(
-        entity: &QueryEntity,
-    ) -> Result<(Arc<ComplexObjectSchema>, Arc<ComplexObjectSchema>)> {
-        let key_fields: Vec<_> = entity
-            .query_fields
-            .iter()
-            .filter(|f| f.key_field || entity.key_field == f.name)
-            .collect();
-        let val_fields: Vec<_> = entity
-            .query_fields
-            .iter()
-            .filter(|f| !f.key_field && entity.key_field != f.name)
-            .collect();
-        let key_fields = Self::convert_fields(&key_fields)?;
-        let val_fields = Self::convert_fields(&val_fields)?;
-        let k = ComplexObjectSchema {
-            type_name: entity.key_type.clone(),
-            fields: key_fields,
+    #[test]
+    fn test_array_fields() {
+        let schema = ComplexObjectSchema {
+            type_name: "Arrays".to_string(),
+            fields: vec![
+                IgniteField {
+                    name: "TAGS".to_string(),
+                    r#type: IgniteType::StringArray,
+                },
+                IgniteField {
+                    name: "IDS".to_string(),
+                    r#type: IgniteType::UuidArray,
+                },
+                IgniteField {
+                    name: "STATES".to_string(),
+                    r#type: IgniteType::EnumArray,
+                },
+                IgniteField {
+                    name: "PRICES".to_string(),
+                    r#type: IgniteType::DecimalArray,
+                },
+                IgniteField {
+                    name: "ITEMS".to_string(),
+                    r#type: IgniteType::ObjectArray,
+                },
+            ],
         };
-        let v = ComplexObjectSchema {
-            type_name: entity.value_type.clone(),
-            fields: val_fields,
+        let expected = ComplexObject {
+            schema: Arc::new(schema),
+            values: vec![
+                IgniteValue::StringArray(vec![
+                    IgniteValue::String("a".to_string()),
+                    IgniteValue::Null,
+                ]),
+                IgniteValue::UuidArray(vec![IgniteValue::Uuid(1, -1)]),
+                IgniteValue::EnumArray(7, vec![IgniteValue::Enum(7, 2)]),
+                IgniteValue::DecimalArray(vec![IgniteValue::Decimal(1, vec![20])]),
+                IgniteValue::ObjectArray(
+                    -1,
+                    vec![IgniteValue::Date(1_000), IgniteValue::Timestamp(1_000, 5)],
+                ),
+            ],
         };
-        Ok((Arc::new(k), Arc::new(v)))
+
+        let mut bytes = vec![];
+        expected.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), expected.size());
+
+        let mut reader = Cursor::new(bytes);
+        let type_code = read_u8(&mut reader).unwrap();
+        let actual = ComplexObject::read_unwrapped(type_code.try_into().unwrap(), &mut reader)
+            .unwrap()
+            .unwrap();
+        assert_eq!(actual.values, expected.values);
+
+        // element of another type
+        let bytes = hex_literal::hex!("14 01 00 00 00 03 01 00 00 00");
+        let mut reader = Cursor::new(bytes);
+        let type_code = TypeCode::try_from(read_u8(&mut reader).unwrap()).unwrap();
+        assert!(read_value(type_code, &mut reader).is_err());
     }
 
-    fn convert_fields(qry_fields: &[&QueryField]) -> Result<Vec<IgniteField>> {
-        let mut fields = vec![];
-        for f in qry_fields.iter() {
-            let t: IgniteType = match f.type_name.as_str() {
-                "java.lang.Long" => IgniteType::Long,
-                "java.lang.Short" => IgniteType::Short,
-                "java.lang.Byte" => IgniteType::Byte,
-                "java.lang.String" => IgniteType::String,
-                "java.sql.Timestamp" => IgniteType::Timestamp,
-                "java.lang.Integer" => IgniteType::Int,
-                "java.lang.Boolean" => IgniteType::Bool,
-                "java.math.BigDecimal" => IgniteType::Decimal(f.precision, f.scale),
-                // '[B' is a JVM quirk -- this can happen for arrays of some
-                // primitive types. Specifically, it is the output of
-                // `System.out.println(byte[].class.getName());`
-                "[B" => IgniteType::Binary,
-                _ => Err(Error::from(
-                    format!("Unknown field type: {}", f.type_name).as_str(),
-                ))?,
-            };
-            let field = IgniteField {
-                name: f.name.to_string(),
-                r#type: t,
-            };
-            fields.push(field);
+    #[test]
+    fn test_floats_and_nested_object() {
+        let inner = ComplexObject {
+            schema: Arc::new(ComplexObjectSchema {
+                type_name: "Point".to_string(),
+                fields: vec![IgniteField {
+                    name: "X".to_string(),
+                    r#type: IgniteType::Double,
+                }],
+            }),
+            values: vec![IgniteValue::Double(-0.5)],
+        };
+        let schema = ComplexObjectSchema {
+            type_name: "Shape".to_string(),
+            fields: vec![
+                IgniteField {
+                    name: "AREA".to_string(),
+                    r#type: IgniteType::Float,
+                },
+                IgniteField {
+                    name: "LABEL".to_string(),
+                    r#type: IgniteType::Char,
+                },
+                IgniteField {
+                    name: "SIDES".to_string(),
+                    r#type: IgniteType::IntArray,
+                },
+                IgniteField {
+                    name: "CENTER".to_string(),
+                    r#type: IgniteType::Object,
+                },
+            ],
+        };
+        let expected = ComplexObject {
+            schema: Arc::new(schema),
+            values: vec![
+                IgniteValue::Float(1.25),
+                IgniteValue::Char('A' as u16),
+                IgniteValue::IntArray(vec![3, 4, 5]),
+                IgniteValue::Object(NestedObject::from(&inner)),
+            ],
+        };
+
+        let mut bytes = vec![];
+        expected.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), expected.size());
+
+        let mut reader = Cursor::new(bytes);
+        let type_code = read_u8(&mut reader).unwrap();
+        let actual = ComplexObject::read_unwrapped(type_code.try_into().unwrap(), &mut reader)
+            .unwrap()
+            .unwrap();
+        assert_eq!(actual.values, expected.values);
+        match &actual.values[3] {
+            IgniteValue::Object(obj) => {
+                assert_eq!(obj.type_id, string_to_java_hashcode("point"));
+                assert_eq!(obj.field_ids, Some(vec![string_to_java_hashcode("x")]));
+                assert_eq!(obj.values, vec![IgniteValue::Double(-0.5)]);
+            }
+            other => panic!("Unexpected value: {:?}", other),
         }
-        Ok(fields)
     }
 
-    pub fn type_name(&self) -> &str {
-        self.type_name.as_str()
+    #[test]
+    fn test_nested_round_trip() {
+        let schema = |type_name: &str, names: &[&str]| {
+            Arc::new(ComplexObjectSchema {
+                type_name: type_name.to_string(),
+                fields: names
+                    .iter()
+                    .map(|name| IgniteField {
+                        name: name.to_string(),
+                        r#type: IgniteType::Object,
+                    })
+                    .collect(),
+            })
+        };
+        let leaf = ComplexObject {
+            schema: schema("Leaf", &["VALUE"]),
+            values: vec![IgniteValue::String("leaf".to_string())],
+        };
+        let branch = ComplexObject {
+            schema: schema("Branch", &["LEAF", "WEIGHT"]),
+            values: vec![
+                IgniteValue::Object(NestedObject::from(&leaf)),
+                IgniteValue::Float(f32::NAN),
+            ],
+        };
+        // read with a compact footer, i.e. with the offsets only
+        let compact = NestedObject {
+            type_id: 1,
+            schema_id: 2,
+            field_ids: None,
+            values: vec![IgniteValue::Int(3), IgniteValue::Null],
+        };
+        let tree = ComplexObject {
+            schema: schema("Tree", &["BRANCH", "COMPACT"]),
+            values: vec![
+                IgniteValue::Object(NestedObject::from(&branch)),
+                IgniteValue::Collection(
+                    CollectionKind::ArrayList,
+                    vec![IgniteValue::Object(compact)],
+                ),
+            ],
+        };
+        let mut expected_bytes = vec![];
+        tree.write(&mut expected_bytes).unwrap();
+
+        let read = |bytes: &[u8]| {
+            let mut reader = Cursor::new(bytes);
+            let type_code = read_u8(&mut reader).unwrap();
+            ComplexObject::read_unwrapped(type_code.try_into().unwrap(), &mut reader)
+                .unwrap()
+                .unwrap()
+        };
+        let first = read(&expected_bytes);
+        assert_eq!(first.values, tree.values);
+
+        // nested objects are written back as they were read
+        let second = ComplexObject {
+            schema: tree.schema.clone(),
+            values: first.values.clone(),
+        };
+        let mut actual_bytes = vec![];
+        second.write(&mut actual_bytes).unwrap();
+        assert_eq!(actual_bytes, expected_bytes);
+        assert_eq!(read(&actual_bytes), first);
+
+        // the values of the read object can't be written by its empty schema
+        assert!(first.write(&mut vec![]).is_err());
+    }
+
+    #[test]
+    fn test_float_eq_and_hash() {
+        use std::collections::HashSet;
+
+        let values: HashSet<_> = vec![
+            IgniteValue::Double(f64::NAN),
+            IgniteValue::Double(f64::NAN),
+            IgniteValue::Double(0.0),
+            IgniteValue::Double(-0.0),
+            IgniteValue::FloatArray(vec![1.5]),
+            IgniteValue::FloatArray(vec![1.5]),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(values.len(), 4);
+    }
+
+    #[test]
+    fn test_infer_float_fields() {
+        let field = |name: &str, type_name: &str| QueryField {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            key_field: false,
+            not_null_constraint: false,
+            precision: -1,
+            scale: -1,
+        };
+        let fields = [
+            field("PRICE", "java.lang.Double"),
+            field("RATIO", "java.lang.Float"),
+            field("GRADE", "java.lang.Character"),
+            field("SCORES", "[D"),
+        ];
+        let fields: Vec<_> = fields.iter().collect();
+        let types: Vec<_> = ComplexObjectSchema::convert_fields(&fields)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.r#type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                IgniteType::Double,
+                IgniteType::Float,
+                IgniteType::Char,
+                IgniteType::DoubleArray
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_object() {
+        let header = |version: u8, flags: u16, len: i32| {
+            let mut bytes = vec![TypeCode::ComplexObj as u8, version];
+            bytes.extend(flags.to_le_bytes());
+            bytes.extend(hex_literal::hex!("00 00 00 00 00 00 00 00")); // type id, hash code
+            bytes.extend(len.to_le_bytes());
+            bytes.extend(hex_literal::hex!("00 00 00 00 18 00 00 00")); // schema id, offset
+            bytes
+        };
+        let read = |bytes: Vec<u8>| {
+            let mut reader = Cursor::new(bytes);
+            let type_code = read_u8(&mut reader).unwrap();
+            ComplexObject::read_unwrapped(type_code.try_into().unwrap(), &mut reader)
+        };
+
+        let err = read(header(2, 0x03, 24)).unwrap_err();
+        assert!(matches!(err, Error::Decode { offset: 1, .. }), "{}", err);
+        let err = read(header(1, 0x07, 24)).unwrap_err(); // raw data
+        assert!(matches!(err, Error::Decode { offset: 2, .. }), "{}", err);
+        let err = read(header(1, 0x03, 4)).unwrap_err();
+        assert!(matches!(err, Error::Decode { offset: 12, .. }), "{}", err);
+
+        // a string field with negative length
+        let mut bytes = header(1, 0x03, 29);
+        bytes[20..24].copy_from_slice(&hex_literal::hex!("1D 00 00 00"));
+        bytes.extend(hex_literal::hex!("09 FF FF FF FF"));
+        let err = read(bytes).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::Decode {
+                    type_code: 9,
+                    offset: 24,
+                    ..
+                }
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_read_dyn() {
+        let first_field = |mut reader: &mut dyn Read, len: i32| {
+            assert_eq!(len, 0x27);
+            assert_eq!(i32::read(&mut reader)?, Some(7));
+            Ok(())
+        };
+        // three fields with a compact footer, then a byte after the object
+        let bytes = hex_literal::hex!(
+            "01 2B 00" // version, flags: user type, has schema, one byte offsets, compact footer
+            "90 55 5E 06 00 00 00 00" // type id, hash code
+            "27 00 00 00" // length
+            "2A 00 00 00 24 00 00 00" // schema id, schema offset
+            "03 07 00 00 00 09 01 00 00 00 70 65" // 7, "p", NULL
+            "18 1D 23" // footer
+            "FF"
+        );
+        let mut reader = Cursor::new(bytes.to_vec());
+        read_complex_obj_dyn(&mut reader, &mut |reader, len| first_field(reader, len)).unwrap();
+        assert_eq!(read_u8(&mut reader).unwrap(), 0xFF);
+
+        // the same fields with a full footer
+        let mut bytes = bytes.to_vec();
+        bytes[1] = 0x0B;
+        bytes[11] = 0x33;
+        let footer = hex_literal::hex!("78 00 00 00 18 8B 7A 33 00 1D 7A 00 00 00 23");
+        bytes.splice(35..38, footer.iter().copied());
+        let mut reader = Cursor::new(bytes);
+        let check_len = |mut reader: &mut dyn Read, len: i32| {
+            assert_eq!(len, 0x33);
+            assert_eq!(i32::read(&mut reader)?, Some(7));
+            Ok(())
+        };
+        read_complex_obj_dyn(&mut reader, &mut |reader, len| check_len(reader, len)).unwrap();
+        assert_eq!(read_u8(&mut reader).unwrap(), 0xFF);
+
+        // schema offset past the object
+        let mut reader = Cursor::new(hex_literal::hex!(
+            "01 2B 00 90 55 5E 06 00 00 00 00 27 00 00 00 2A 00 00 00 40 00 00 00"
+        ));
+        let err = read_complex_obj_dyn(&mut reader, &mut |_, _| Ok(())).unwrap_err();
+        assert!(matches!(err, Error::Decode { offset: 20, .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_map_field() {
+        let schema = ComplexObjectSchema {
+            type_name: "Attributes".to_string(),
+            fields: vec![IgniteField {
+                name: "ATTRS".to_string(),
+                r#type: IgniteType::Map,
+            }],
+        };
+        let expected = ComplexObject {
+            schema: Arc::new(schema),
+            values: vec![IgniteValue::Map(
+                MapKind::HashMap,
+                vec![
+                    (
+                        IgniteValue::String("count".to_string()),
+                        IgniteValue::Int(1),
+                    ),
+                    (
+                        IgniteValue::String("missing".to_string()),
+                        IgniteValue::Null,
+                    ),
+                ],
+            )],
+        };
+
+        let mut bytes = vec![];
+        expected.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), expected.size());
+
+        let mut reader = Cursor::new(bytes);
+        let type_code = read_u8(&mut reader).unwrap();
+        let actual = ComplexObject::read_unwrapped(type_code.try_into().unwrap(), &mut reader)
+            .unwrap()
+            .unwrap();
+        assert_eq!(actual.values, expected.values);
     }
 }
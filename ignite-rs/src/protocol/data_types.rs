@@ -4,12 +4,15 @@ use crate::error::{Error, Result};
 use crate::protocol::*;
 use crate::protocol::{read_u8, TypeCode};
 
-use crate::{Enum, ReadableType, WritableType};
+use crate::{Date, Decimal, Enum, IgniteObj, ReadableType, Uuid, WritableType};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::hash::Hash;
 use std::io;
 
-/// Ignite's 'char' is a UTF-16 code UNIT, which means its size is 2 bytes.
-/// As Rust's 'char' is a Unicode scalar value (a.k.a UTF-32 code unit) and has 4 bytes,
-/// I don't see how the API should be properly implemented. u16 is used for now
+// Ignite's 'char' is a UTF-16 code UNIT, which means its size is 2 bytes.
+// As Rust's 'char' is a Unicode scalar value (a.k.a UTF-32 code unit) and has 4 bytes,
+// I don't see how the API should be properly implemented. u16 is used for now
 
 macro_rules! write_type {
     ($t:ty, $code:path, $write_fn:ident, $size:expr) => {
@@ -36,6 +39,8 @@ write_type!(f32, TypeCode::Float, write_f32, 4);
 write_type!(f64, TypeCode::Double, write_f64, 8);
 write_type!(bool, TypeCode::Bool, write_bool, 1);
 write_type!(Enum, TypeCode::Enum, write_enum, 8);
+write_type!(Uuid, TypeCode::Uuid, write_uuid, 16);
+write_type!(Date, TypeCode::Date, write_date, 8);
 
 impl WritableType for String {
     fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
@@ -74,6 +79,25 @@ read_type!(bool, read_bool);
 read_type!(String, read_string);
 read_type!(Enum, read_enum);
 
+macro_rules! read_coded_type {
+    ($t:ty, $code:path, $read_fn:ident) => {
+        impl ReadableType for $t {
+            fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+                match type_code {
+                    TypeCode::Null => Ok(None),
+                    $code => Ok(Some($read_fn(reader)?)),
+                    _ => Err(Error::from(
+                        format!("Expected {:?}, got {:?}", $code, type_code).as_str(),
+                    )),
+                }
+            }
+        }
+    };
+}
+
+read_coded_type!(Uuid, TypeCode::Uuid, read_uuid);
+read_coded_type!(Date, TypeCode::Date, read_date);
+
 macro_rules! write_primitive_arr {
     ($t:ty, $code:path, $write_fn:ident, $size:expr) => {
         impl WritableType for Vec<$t> {
@@ -125,6 +149,91 @@ read_primitive_arr!(f32, read_f32);
 read_primitive_arr!(f64, read_f64);
 read_primitive_arr!(bool, read_bool);
 
+/// Reads the body of an array which elements are data objects: String, UUID, Date,
+/// Decimal or Timestamp arrays. Null elements are not allowed
+pub(crate) fn read_typed_arr<T: ReadableType>(reader: &mut impl Read) -> Result<Vec<T>> {
+    let len = read_i32(reader)?;
+    let mut data = Vec::with_capacity(prealloc_len(len));
+    for _ in 0..len {
+        let item = T::read(reader)?.ok_or_else(|| Error::from("NULL element is not expected"))?;
+        data.push(item);
+    }
+    Ok(data)
+}
+
+fn write_typed_arr<T: WritableType>(
+    writer: &mut dyn Write,
+    code: TypeCode,
+    items: &[T],
+) -> io::Result<()> {
+    write_u8(writer, code as u8)?;
+    write_i32(writer, items.len() as i32)?; // length of array
+    for item in items {
+        item.write(writer)?;
+    }
+    Ok(())
+}
+
+macro_rules! typed_arr {
+    ($t:ty, $code:path) => {
+        impl WritableType for Vec<$t> {
+            fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+                write_typed_arr(writer, $code, self)
+            }
+
+            fn size(&self) -> usize {
+                let items_size: usize = self.iter().map(|item| item.size()).sum();
+                items_size + 4 + 1 // items, len, type code
+            }
+        }
+
+        impl ReadableType for Vec<$t> {
+            fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+                match type_code {
+                    TypeCode::Null => Ok(None),
+                    $code => Ok(Some(read_typed_arr(reader)?)),
+                    _ => Ok(Some(read_collection(type_code, reader)?)),
+                }
+            }
+        }
+    };
+}
+
+typed_arr!(String, TypeCode::ArrString);
+typed_arr!(Uuid, TypeCode::ArrUuid);
+typed_arr!(Date, TypeCode::ArrDate);
+typed_arr!(Decimal, TypeCode::ArrDecimal);
+
+// Enum array also carries the type id of its elements
+impl WritableType for Vec<Enum> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u8(writer, TypeCode::ArrEnum as u8)?;
+        write_i32(writer, self.first().map_or(-1, |el| el.type_id))?; // type id
+        write_i32(writer, self.len() as i32)?; // length of array
+        for el in self {
+            el.write(writer)?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        (8 + 1) * self.len() + 4 + 4 + 1 // items, type id, len, type code
+    }
+}
+
+impl ReadableType for Vec<Enum> {
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        match type_code {
+            TypeCode::Null => Ok(None),
+            TypeCode::ArrEnum => {
+                read_i32(reader)?; // ignore type id
+                Ok(Some(read_typed_arr(reader)?))
+            }
+            _ => Ok(Some(read_collection(type_code, reader)?)),
+        }
+    }
+}
+
 // pack all vectors as object array
 impl<T: WritableType + ReadableType> WritableType for Vec<Option<T>> {
     fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
@@ -156,10 +265,23 @@ impl<T: WritableType + ReadableType> ReadableType for Vec<Option<T>> {
     fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
         match type_code {
             TypeCode::Null => Ok(None),
-            TypeCode::ArrObj => {
+            TypeCode::ArrObj | TypeCode::ArrEnum => {
                 read_i32(reader)?; // ignore type id
                 let len = read_i32(reader)?;
-                let mut data: Vec<Option<T>> = Vec::with_capacity(len as usize);
+                let mut data: Vec<Option<T>> = Vec::with_capacity(prealloc_len(len));
+                for _ in 0..len {
+                    let item = T::read(reader)?;
+                    data.push(item);
+                }
+                Ok(Some(data))
+            }
+            TypeCode::ArrString
+            | TypeCode::ArrUuid
+            | TypeCode::ArrDate
+            | TypeCode::ArrDecimal
+            | TypeCode::TimestampArray => {
+                let len = read_i32(reader)?;
+                let mut data: Vec<Option<T>> = Vec::with_capacity(prealloc_len(len));
                 for _ in 0..len {
                     let item = T::read(reader)?;
                     data.push(item);
@@ -168,8 +290,8 @@ impl<T: WritableType + ReadableType> ReadableType for Vec<Option<T>> {
             }
             TypeCode::Collection => {
                 let len = read_i32(reader)?;
-                read_i8(reader)?; // ignore collection type
-                let mut data: Vec<Option<T>> = Vec::with_capacity(len as usize);
+                CollectionKind::try_from(read_i8(reader)?)?; // any kind could be read
+                let mut data: Vec<Option<T>> = Vec::with_capacity(prealloc_len(len));
                 for _ in 0..len {
                     let item = T::read(reader)?;
                     data.push(item);
@@ -181,6 +303,81 @@ impl<T: WritableType + ReadableType> ReadableType for Vec<Option<T>> {
     }
 }
 
+fn write_collection<'a, T, I>(
+    writer: &mut dyn Write,
+    kind: CollectionKind,
+    len: usize,
+    items: I,
+) -> io::Result<()>
+where
+    T: WritableType + 'a,
+    I: Iterator<Item = &'a T>,
+{
+    write_u8(writer, TypeCode::Collection as u8)?;
+    write_i32(writer, len as i32)?; // number of elements
+    write_i8(writer, kind as i8)?;
+    for item in items {
+        item.write(writer)?;
+    }
+    Ok(())
+}
+
+/// Reads elements of Collection (type code = 24) or object array (type code = 23).
+/// Null elements are not supported by Rust collections
+fn read_collection<T: ReadableType>(type_code: TypeCode, reader: &mut impl Read) -> Result<Vec<T>> {
+    let len = match type_code {
+        TypeCode::Collection => {
+            let len = read_i32(reader)?;
+            CollectionKind::try_from(read_i8(reader)?)?; // any kind could be read
+            len
+        }
+        TypeCode::ArrObj => {
+            read_i32(reader)?; // ignore type id
+            read_i32(reader)?
+        }
+        _ => return Err(Error::from("Expected Collection or Array!")),
+    };
+    let mut data = Vec::with_capacity(prealloc_len(len));
+    for _ in 0..len {
+        let item = T::read(reader)?.ok_or_else(|| Error::from("NULL element is not expected"))?;
+        data.push(item);
+    }
+    Ok(data)
+}
+
+macro_rules! collection_type {
+    ($t:ident, $kind:path $(, $bound:path)*) => {
+        impl<T: WritableType + ReadableType $(+ $bound)*> WritableType for $t<T> {
+            fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+                write_collection(writer, $kind, self.len(), self.iter())
+            }
+
+            fn size(&self) -> usize {
+                let items_size: usize = self.iter().map(|item| item.size()).sum();
+                items_size + 1 + 4 + 1 // items, type code, len, collection kind
+            }
+        }
+
+        impl<T: WritableType + ReadableType $(+ $bound)*> ReadableType for $t<T> {
+            fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+                match type_code {
+                    TypeCode::Null => Ok(None),
+                    _ => Ok(Some(read_collection(type_code, reader)?.into_iter().collect())),
+                }
+            }
+        }
+    };
+}
+
+// Vectors of the IgniteObj types are packed as Java's ArrayList.
+// Vectors of primitives and Strings are packed as arrays, see above. They can't be packed
+// as collections too, as the impls would overlap
+collection_type!(Vec, CollectionKind::ArrayList, IgniteObj);
+collection_type!(VecDeque, CollectionKind::LinkedList);
+collection_type!(HashSet, CollectionKind::HashSet, Eq, Hash);
+// BTreeSet is packed as Java's LinkedHashSet to keep the order of elements
+collection_type!(BTreeSet, CollectionKind::LinkedHashSet, Ord);
+
 impl<T: WritableType> WritableType for Option<T> {
     fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
         match self {
@@ -206,3 +403,322 @@ impl<T: ReadableType> ReadableType for Option<T> {
         }
     }
 }
+
+fn write_map<'a, K, V, I>(
+    writer: &mut dyn Write,
+    kind: MapKind,
+    len: usize,
+    entries: I,
+) -> io::Result<()>
+where
+    K: WritableType + 'a,
+    V: WritableType + 'a,
+    I: Iterator<Item = (&'a K, &'a V)>,
+{
+    write_u8(writer, TypeCode::Map as u8)?;
+    write_i32(writer, len as i32)?; // number of entries
+    write_i8(writer, kind as i8)?;
+    for (key, value) in entries {
+        key.write(writer)?;
+        value.write(writer)?;
+    }
+    Ok(())
+}
+
+/// Reads Map entries. Null keys and values are not supported by Rust maps
+fn read_map<K, V>(reader: &mut impl Read) -> Result<Vec<(K, V)>>
+where
+    K: ReadableType,
+    V: ReadableType,
+{
+    let len = read_i32(reader)?;
+    MapKind::try_from(read_i8(reader)?)?; // any kind could be read
+    let mut entries = Vec::with_capacity(prealloc_len(len));
+    for _ in 0..len {
+        let key = K::read(reader)?.ok_or_else(|| Error::from("NULL map key is not expected"))?;
+        let value =
+            V::read(reader)?.ok_or_else(|| Error::from("NULL map value is not expected"))?;
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+// HashMap is packed as Java's HashMap
+impl<K, V> WritableType for HashMap<K, V>
+where
+    K: WritableType + ReadableType + Eq + Hash,
+    V: WritableType + ReadableType,
+{
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_map(writer, MapKind::HashMap, self.len(), self.iter())
+    }
+
+    fn size(&self) -> usize {
+        let items_size: usize = self.iter().map(|(k, v)| k.size() + v.size()).sum();
+        items_size + 1 + 4 + 1 // items, type code, len, map kind
+    }
+}
+
+impl<K, V> ReadableType for HashMap<K, V>
+where
+    K: WritableType + ReadableType + Eq + Hash,
+    V: WritableType + ReadableType,
+{
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        match type_code {
+            TypeCode::Null => Ok(None),
+            TypeCode::Map => Ok(Some(read_map(reader)?.into_iter().collect())),
+            _ => Err(Error::from("Expected Map!")),
+        }
+    }
+}
+
+// BTreeMap is packed as Java's LinkedHashMap to keep the order of entries
+impl<K, V> WritableType for BTreeMap<K, V>
+where
+    K: WritableType + ReadableType + Ord,
+    V: WritableType + ReadableType,
+{
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_map(writer, MapKind::LinkedHashMap, self.len(), self.iter())
+    }
+
+    fn size(&self) -> usize {
+        let items_size: usize = self.iter().map(|(k, v)| k.size() + v.size()).sum();
+        items_size + 1 + 4 + 1 // items, type code, len, map kind
+    }
+}
+
+impl<K, V> ReadableType for BTreeMap<K, V>
+where
+    K: WritableType + ReadableType + Ord,
+    V: WritableType + ReadableType,
+{
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        match type_code {
+            TypeCode::Null => Ok(None),
+            TypeCode::Map => Ok(Some(read_map(reader)?.into_iter().collect())),
+            _ => Err(Error::from("Expected Map!")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_map_round_trip() {
+        let expected_bytes = hex_literal::hex!(
+            "19" // type code
+            "02 00 00 00" // number of entries
+            "02" // LINKED_HASH_MAP
+            "09 01 00 00 00 61" "03 01 00 00 00" // "a" => 1
+            "09 01 00 00 00 62" "03 02 00 00 00" // "b" => 2
+        );
+        let mut expected = BTreeMap::new();
+        expected.insert("b".to_string(), 2i32);
+        expected.insert("a".to_string(), 1i32);
+
+        let mut actual_bytes = vec![];
+        expected.write(&mut actual_bytes).unwrap();
+        assert_eq!(actual_bytes, expected_bytes.to_vec());
+        assert_eq!(expected.size(), expected_bytes.len());
+
+        let actual = BTreeMap::<String, i32>::read(&mut Cursor::new(expected_bytes)).unwrap();
+        assert_eq!(actual, Some(expected.clone()));
+
+        let actual = HashMap::<String, i32>::read(&mut Cursor::new(expected_bytes)).unwrap();
+        assert_eq!(actual, Some(expected.into_iter().collect()));
+    }
+
+    #[test]
+    fn test_collection_round_trip() {
+        let expected_bytes = hex_literal::hex!(
+            "18" // type code
+            "02 00 00 00" // number of elements
+            "04" // LINKED_HASH_SET
+            "03 01 00 00 00" // 1
+            "03 02 00 00 00" // 2
+        );
+        let expected: BTreeSet<i32> = vec![2, 1].into_iter().collect();
+
+        let mut actual_bytes = vec![];
+        expected.write(&mut actual_bytes).unwrap();
+        assert_eq!(actual_bytes, expected_bytes.to_vec());
+        assert_eq!(expected.size(), expected_bytes.len());
+
+        let actual = HashSet::<i32>::read(&mut Cursor::new(expected_bytes)).unwrap();
+        assert_eq!(actual, Some(expected.iter().cloned().collect()));
+
+        let actual = VecDeque::<i32>::read(&mut Cursor::new(expected_bytes)).unwrap();
+        assert_eq!(actual, Some(vec![1, 2].into_iter().collect()));
+
+        let mut bytes = vec![];
+        HashSet::<i32>::new().write(&mut bytes).unwrap();
+        assert_eq!(bytes, hex_literal::hex!("18 00 00 00 00 03").to_vec()); // HASH_SET
+    }
+
+    #[test]
+    fn test_linked_list_and_hash_set() {
+        let expected_bytes = hex_literal::hex!(
+            "18" // type code
+            "02 00 00 00" // number of elements
+            "02" // LINKED_LIST
+            "09 01 00 00 00 61" // "a"
+            "09 01 00 00 00 62" // "b"
+        );
+        let expected: VecDeque<String> = vec!["a".to_string(), "b".to_string()].into();
+
+        let mut actual_bytes = vec![];
+        expected.write(&mut actual_bytes).unwrap();
+        assert_eq!(actual_bytes, expected_bytes.to_vec());
+        assert_eq!(expected.size(), expected_bytes.len());
+        let actual = VecDeque::<String>::read(&mut Cursor::new(expected_bytes)).unwrap();
+        assert_eq!(actual, Some(expected));
+
+        let expected: HashSet<i64> = vec![7].into_iter().collect();
+        let mut actual_bytes = vec![];
+        expected.write(&mut actual_bytes).unwrap();
+        assert_eq!(
+            actual_bytes,
+            hex_literal::hex!(
+                "18" // type code
+                "01 00 00 00" // number of elements
+                "03" // HASH_SET
+                "04 07 00 00 00 00 00 00 00" // 7
+            )
+            .to_vec()
+        );
+        let actual = HashSet::<i64>::read(&mut Cursor::new(actual_bytes)).unwrap();
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn test_string_arr_round_trip() {
+        let expected_bytes = hex_literal::hex!(
+            "14" // type code
+            "02 00 00 00" // length of array
+            "09 01 00 00 00 61" // "a"
+            "09 00 00 00 00" // ""
+        );
+        let expected = vec!["a".to_string(), "".to_string()];
+
+        let mut actual_bytes = vec![];
+        expected.write(&mut actual_bytes).unwrap();
+        assert_eq!(actual_bytes, expected_bytes.to_vec());
+        assert_eq!(expected.size(), expected_bytes.len());
+
+        let actual = Vec::<String>::read(&mut Cursor::new(expected_bytes)).unwrap();
+        assert_eq!(actual, Some(expected.clone()));
+
+        let actual = Vec::<Option<String>>::read(&mut Cursor::new(expected_bytes)).unwrap();
+        assert_eq!(actual, Some(expected.into_iter().map(Some).collect()));
+    }
+
+    #[test]
+    fn test_uuid_arr_round_trip() {
+        let expected_bytes = hex_literal::hex!(
+            "15" // type code
+            "01 00 00 00" // length of array
+            "0A" // UUID
+            "08 07 06 05 04 03 02 01" // most significant bits
+            "10 0F 0E 0D 0C 0B 0A 09" // least significant bits
+        );
+        let uuid = Uuid::from(0x0102_0304_0506_0708_090A_0B0C_0D0E_0F10u128);
+        assert_eq!(
+            u128::from(uuid),
+            0x0102_0304_0506_0708_090A_0B0C_0D0E_0F10u128
+        );
+        let expected = vec![uuid];
+
+        let mut actual_bytes = vec![];
+        expected.write(&mut actual_bytes).unwrap();
+        assert_eq!(actual_bytes, expected_bytes.to_vec());
+        assert_eq!(expected.size(), expected_bytes.len());
+
+        let actual = Vec::<Uuid>::read(&mut Cursor::new(expected_bytes)).unwrap();
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn test_enum_and_date_arr() {
+        let expected = vec![
+            Enum {
+                type_id: 7,
+                ordinal: 1,
+            },
+            Enum {
+                type_id: 7,
+                ordinal: 0,
+            },
+        ];
+        let mut bytes = vec![];
+        expected.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), expected.size());
+        assert_eq!(&bytes[..5], &hex_literal::hex!("1D 07 00 00 00")); // type code, type id
+        let actual = Vec::<Enum>::read(&mut Cursor::new(bytes)).unwrap().unwrap();
+        assert_eq!(actual, expected);
+
+        let expected = vec![Date { millis: -1 }, Date { millis: 1_000 }];
+        let mut bytes = vec![];
+        expected.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), expected.size());
+        let actual = Vec::<Date>::read(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(actual, Some(expected));
+
+        // null elements are not allowed
+        let bytes = hex_literal::hex!("16 01 00 00 00 65");
+        assert!(Vec::<Date>::read(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn test_option_and_negative_len() {
+        let actual = Option::<i32>::read(&mut Cursor::new(hex_literal::hex!("65"))).unwrap();
+        assert_eq!(actual, None);
+        let actual = Option::<i32>::read(&mut Cursor::new(hex_literal::hex!("03 01 00 00 00")));
+        assert_eq!(actual.unwrap(), Some(Some(1)));
+        assert!(i32::read(&mut Cursor::new(hex_literal::hex!("65")))
+            .unwrap()
+            .is_none());
+
+        let bytes = hex_literal::hex!("09 FF FF FF FF"); // String with length -1
+        assert!(String::read(&mut Cursor::new(bytes)).is_err());
+        let bytes = hex_literal::hex!("0E FF FF FF FF"); // ArrInt with length -1
+        assert!(Vec::<i32>::read(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn test_oversized_len() {
+        let bytes = hex_literal::hex!("09 FF FF FF 7F 61 62"); // String with length i32::MAX
+        assert!(String::read(&mut Cursor::new(bytes)).is_err());
+        let bytes = hex_literal::hex!("0F FF FF FF 7F 01 02"); // ArrLong with length i32::MAX
+        assert!(Vec::<i64>::read(&mut Cursor::new(bytes)).is_err());
+        let bytes = hex_literal::hex!("17 FF FF FF FF FF FF FF 7F 65"); // ArrObj
+        assert!(Vec::<Option<i32>>::read(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn test_user_map_kind() {
+        let bytes = hex_literal::hex!(
+            "19" // type code
+            "01 00 00 00" // number of entries
+            "00" // USER_MAP
+            "03 01 00 00 00" "03 02 00 00 00" // 1 => 2
+        );
+        let actual = HashMap::<i32, i32>::read(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(actual, Some(vec![(1, 2)].into_iter().collect()));
+    }
+
+    #[test]
+    fn test_map_null_value() {
+        let bytes = hex_literal::hex!(
+            "19" // type code
+            "01 00 00 00" // number of entries
+            "01" // HASH_MAP
+            "09 01 00 00 00 61" "65" // "a" => null
+        );
+        assert!(HashMap::<String, i32>::read(&mut Cursor::new(bytes)).is_err());
+    }
+}
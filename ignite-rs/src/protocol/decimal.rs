@@ -0,0 +1,249 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::str::FromStr;
+
+use num_bigint::{BigInt, Sign};
+
+use crate::error::{Error, Result};
+use crate::protocol::complex_obj::IgniteValue;
+use crate::protocol::{read_bytes, read_i32, write_i32, write_u8, TypeCode};
+use crate::{Decimal, ReadableType, WritableType};
+
+// Decimal is written the way Java's BinaryWriterExImpl does it: scale, then
+// the big-endian magnitude of the unscaled value. The sign is stored
+// in the most significant bit of the first byte.
+
+impl Decimal {
+    pub fn new(unscaled: impl Into<BigInt>, scale: i32) -> Decimal {
+        Decimal {
+            unscaled: unscaled.into(),
+            scale,
+        }
+    }
+
+    /// Converts the value into the Ignite's magnitude bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (sign, mut mag) = self.unscaled.to_bytes_be();
+        if mag[0] & 0x80 != 0 {
+            mag.insert(0, 0); // keep the sign bit free, like BigInteger.toByteArray() does
+        }
+        if sign == Sign::Minus {
+            mag[0] |= 0x80;
+        }
+        mag
+    }
+
+    /// Builds the value from the Ignite's scale and magnitude bytes
+    pub fn from_bytes(scale: i32, bytes: &[u8]) -> Decimal {
+        let mut mag = bytes.to_vec();
+        let negative = mag.first().is_some_and(|b| b & 0x80 != 0);
+        if negative {
+            mag[0] &= 0x7F;
+        }
+        let unscaled = BigInt::from_bytes_be(Sign::Plus, &mag);
+        Decimal {
+            unscaled: if negative { -unscaled } else { unscaled },
+            scale,
+        }
+    }
+}
+
+impl From<BigInt> for Decimal {
+    fn from(unscaled: BigInt) -> Self {
+        Decimal::new(unscaled, 0)
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(unscaled: i64) -> Self {
+        Decimal::new(unscaled, 0)
+    }
+}
+
+impl From<i32> for Decimal {
+    fn from(unscaled: i32) -> Self {
+        Decimal::new(unscaled, 0)
+    }
+}
+
+/// Parses plain decimal notation, e.g. "-12.345". Scale is the number of fractional digits
+impl FromStr for Decimal {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (int_part, frac_part) = match s.find('.') {
+            Some(pos) => (&s[..pos], &s[pos + 1..]),
+            None => (s, ""),
+        };
+        let digits = int_part.trim_start_matches(['-', '+']);
+        let valid = int_part.len() - digits.len() <= 1
+            && !(digits.is_empty() && frac_part.is_empty())
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && frac_part.chars().all(|c| c.is_ascii_digit());
+        if !valid {
+            return Err(Error::from(format!("Invalid decimal: {}", s).as_str()));
+        }
+        let unscaled = BigInt::from_str(&format!("{}{}", int_part, frac_part))
+            .map_err(|err| Error::from(format!("Invalid decimal {}: {}", s, err).as_str()))?;
+        Ok(Decimal::new(unscaled, frac_part.len() as i32))
+    }
+}
+
+/// Formats the value in plain notation, like Java's BigDecimal.toPlainString()
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.unscaled.magnitude().to_string();
+        let sign = if self.unscaled.sign() == Sign::Minus {
+            "-"
+        } else {
+            ""
+        };
+        if self.scale <= 0 {
+            let zeroes = "0".repeat(self.scale.unsigned_abs() as usize);
+            let zeroes = if digits == "0" { "" } else { zeroes.as_str() };
+            return write!(f, "{}{}{}", sign, digits, zeroes);
+        }
+        let scale = self.scale as usize;
+        if digits.len() > scale {
+            let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+            write!(f, "{}{}.{}", sign, int_part, frac_part)
+        } else {
+            write!(
+                f,
+                "{}0.{}{}",
+                sign,
+                "0".repeat(scale - digits.len()),
+                digits
+            )
+        }
+    }
+}
+
+impl WritableType for Decimal {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let bytes = self.to_bytes();
+        write_u8(writer, TypeCode::Decimal as u8)?;
+        write_i32(writer, self.scale)?;
+        write_i32(writer, bytes.len() as i32)?;
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.to_bytes().len() + 4 + 4 + 1 // magnitude, len, scale, type code
+    }
+}
+
+impl ReadableType for Decimal {
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        match type_code {
+            TypeCode::Null => Ok(None),
+            TypeCode::Decimal => Ok(Some(read_decimal(reader)?)),
+            _ => Err(Error::from("Expected Decimal!")),
+        }
+    }
+}
+
+/// Reads the Decimal body (type code = 30 is already consumed)
+pub(crate) fn read_decimal(reader: &mut impl Read) -> io::Result<Decimal> {
+    let scale = read_i32(reader)?;
+    let len = read_i32(reader)?;
+    if len < 0 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid Decimal length: {}", len),
+        ));
+    }
+    let bytes = read_bytes(reader, len as usize)?;
+    Ok(Decimal::from_bytes(scale, &bytes))
+}
+
+impl From<Decimal> for IgniteValue {
+    fn from(value: Decimal) -> Self {
+        IgniteValue::Decimal(value.scale, value.to_bytes())
+    }
+}
+
+impl TryFrom<&IgniteValue> for Decimal {
+    type Error = Error;
+
+    fn try_from(value: &IgniteValue) -> Result<Self, Self::Error> {
+        match value {
+            IgniteValue::Decimal(scale, bytes) => Ok(Decimal::from_bytes(*scale, bytes)),
+            _ => Err(Error::from("Expected Decimal!")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(value: &Decimal) -> Decimal {
+        let mut bytes = vec![];
+        value.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), value.size());
+        Decimal::read(&mut Cursor::new(bytes)).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_decimal_bytes() {
+        // bytes produced by Java's BinaryWriterExImpl
+        let cases: Vec<(&str, i32, Vec<u8>)> = vec![
+            ("2.0", 1, vec![0x14]),
+            ("-2.0", 1, vec![0x94]),
+            ("0", 0, vec![0x00]),
+            ("1.28", 2, vec![0x00, 0x80]),
+            ("-1.28", 2, vec![0x80, 0x80]),
+            ("-1", 0, vec![0x81]),
+        ];
+        for (text, scale, bytes) in cases {
+            let value = Decimal::from_str(text).unwrap();
+            assert_eq!(value.scale, scale, "{}", text);
+            assert_eq!(value.to_bytes(), bytes, "{}", text);
+            assert_eq!(Decimal::from_bytes(scale, &bytes), value, "{}", text);
+            assert_eq!(value.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_decimal_round_trip() {
+        // DECIMAL(38, 18)
+        let value = Decimal::from_str("-12345678901234567890.123456789012345678").unwrap();
+        assert_eq!(value.scale, 18);
+        assert_eq!(round_trip(&value), value);
+
+        let expected_bytes = hex_literal::hex!(
+            "1E" // type code
+            "01 00 00 00" // scale
+            "01 00 00 00" // length
+            "14" // magnitude
+        );
+        let mut actual_bytes = vec![];
+        Decimal::new(20, 1).write(&mut actual_bytes).unwrap();
+        assert_eq!(actual_bytes, expected_bytes.to_vec());
+    }
+
+    #[test]
+    fn test_decimal_display() {
+        assert_eq!(Decimal::new(5, 3).to_string(), "0.005");
+        assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+        assert_eq!(Decimal::new(12, -2).to_string(), "1200");
+        assert_eq!(Decimal::new(0, -2).to_string(), "0");
+        assert_eq!(Decimal::from_str(".5").unwrap(), Decimal::new(5, 1));
+        assert!(Decimal::from_str("1.2.3").is_err());
+        assert!(Decimal::from_str("--1").is_err());
+        assert!(Decimal::from_str("-").is_err());
+    }
+
+    #[test]
+    fn test_ignite_value() {
+        let value = IgniteValue::Decimal(1, vec![20]);
+        let decimal = Decimal::try_from(&value).unwrap();
+        assert_eq!(decimal.to_string(), "2.0");
+        assert_eq!(IgniteValue::from(decimal), value);
+    }
+}
@@ -0,0 +1,1064 @@
+//! Decoding of frames and data objects into a readable tree for debugging.
+//!
+//! Every node of the tree keeps the offset of the bytes it was decoded from.
+//! `Display` prints the tree with one node per line:
+//! ```text
+//!      0  ComplexObj (103)
+//!      1    version = 1
+//!      2    flags = 0x0003 [USER_TYPE, HAS_SCHEMA]
+//!    ...
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Cursor;
+
+use crate::api::key_value::FLAG_WITH_EXPIRY_POLICY;
+use crate::api::OpCode;
+use crate::error::{Error, Result, StatusCode};
+use crate::handshake::FLAGS_VERSION;
+use crate::protocol::{
+    read_bool, read_f32, read_f64, read_i16, read_i32, read_i64, read_i8, read_len,
+    read_primitive_arr, read_string, read_u16, read_u8, CollectionKind, MapKind, RespHeader,
+    TypeCode, COMPLEX_OBJ_HEADER_LEN, FLAG_COMPACT_FOOTER, FLAG_HAS_SCHEMA, FLAG_OFFSET_ONE_BYTE,
+    FLAG_OFFSET_TWO_BYTES, FLAG_USER_TYPE, HAS_RAW_DATA, RESPONSE_FLAG_ERROR,
+    RESPONSE_FLAG_NOTIFICATION, RESPONSE_FLAG_TOPOLOGY_CHANGED,
+};
+use crate::recording::{handshake_version, Direction, RecordedFrame};
+use crate::Uuid;
+
+const FLAG_NAMES: [(u16, &str); 6] = [
+    (FLAG_USER_TYPE, "USER_TYPE"),
+    (FLAG_HAS_SCHEMA, "HAS_SCHEMA"),
+    (HAS_RAW_DATA, "HAS_RAW_DATA"),
+    (FLAG_OFFSET_ONE_BYTE, "OFFSET_ONE_BYTE"),
+    (FLAG_OFFSET_TWO_BYTES, "OFFSET_TWO_BYTES"),
+    (FLAG_COMPACT_FOOTER, "COMPACT_FOOTER"),
+];
+
+const RESPONSE_FLAG_NAMES: [(i16, &str); 3] = [
+    (RESPONSE_FLAG_ERROR, "ERROR"),
+    (RESPONSE_FLAG_TOPOLOGY_CHANGED, "TOPOLOGY_CHANGED"),
+    (RESPONSE_FLAG_NOTIFICATION, "NOTIFICATION"),
+];
+
+// https://ignite.apache.org/docs/latest/binary-client-protocol/key-value-queries#op_cache_get
+const FLAG_TRANSACTIONAL: u8 = 0x02;
+
+/// Node of the decoded tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// Offset of the decoded bytes from the start of the input
+    pub offset: usize,
+    pub label: String,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    fn leaf(offset: usize, label: String) -> Node {
+        Node {
+            offset,
+            label,
+            children: vec![],
+        }
+    }
+
+    fn write_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>6}  {}{}",
+            self.offset,
+            "  ".repeat(depth),
+            self.label
+        )?;
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+/// Decodes a single data object
+pub fn inspect_data_object(bytes: &[u8]) -> Result<Node> {
+    Inspector::new(bytes, 0).data_object()
+}
+
+/// Decodes a request frame, length prefix included.
+/// Payloads of the key-value operations are decoded, other payloads are shown as bytes
+pub fn inspect_request(frame: &[u8]) -> Result<Node> {
+    let mut ins = Inspector::new(frame, 0);
+    let mut children = vec![ins.len_prefix()?];
+    let offset = ins.pos();
+    let code = read_i16(&mut ins.cur)?;
+    children.push(Node::leaf(offset, format!("op code = {}", op_name(code))));
+    let offset = ins.pos();
+    children.push(Node::leaf(
+        offset,
+        format!("request id = {}", read_i64(&mut ins.cur)?),
+    ));
+    children.extend(ins.request_payload(OpCode::try_from(code).ok())?);
+    Ok(Node {
+        offset: 0,
+        label: "request".to_string(),
+        children,
+    })
+}
+
+/// Decodes a response frame, length prefix included. Payload is decoded
+/// if the op code of the request is known
+pub fn inspect_response(frame: &[u8], op_code: Option<i16>) -> Result<Node> {
+    response_node(frame, op_code, true)
+}
+
+/// Decodes a response with the flags or, before 1.4.0, with the status in their place
+fn response_node(frame: &[u8], op_code: Option<i16>, has_flags: bool) -> Result<Node> {
+    let mut ins = Inspector::new(frame, 0);
+    let mut children = vec![ins.len_prefix()?];
+    let offset = ins.pos();
+    children.push(Node::leaf(
+        offset,
+        format!("request id = {}", read_i64(&mut ins.cur)?),
+    ));
+    // without the flags the status is sent for a successful response too
+    let failed = match has_flags {
+        true => ins.response_flags(&mut children)? & RESPONSE_FLAG_ERROR != 0,
+        false => match read_i32(&mut ins.cur.clone())? {
+            0 => {
+                let offset = ins.pos();
+                read_i32(&mut ins.cur)?;
+                children.push(Node::leaf(offset, "status = 0".to_string()));
+                false
+            }
+            _ => {
+                children.push(ins.status()?);
+                true
+            }
+        },
+    };
+    if !failed {
+        let op_code = op_code.and_then(|code| OpCode::try_from(code).ok());
+        children.extend(ins.response_payload(op_code)?);
+    } else {
+        if has_flags {
+            children.push(ins.status()?);
+        }
+        children.push(ins.data_object()?);
+    }
+    Ok(Node {
+        offset: 0,
+        label: "response".to_string(),
+        children,
+    })
+}
+
+/// Decodes a server notification frame, length prefix included
+pub fn inspect_notification(frame: &[u8]) -> Result<Node> {
+    let mut ins = Inspector::new(frame, 0);
+    let mut children = vec![ins.len_prefix()?];
+    let offset = ins.pos();
+    children.push(Node::leaf(
+        offset,
+        format!("resource id = {}", read_i64(&mut ins.cur)?),
+    ));
+    let flags = ins.response_flags(&mut children)?;
+    let offset = ins.pos();
+    let code = read_i16(&mut ins.cur)?;
+    children.push(Node::leaf(offset, format!("op code = {}", op_name(code))));
+    if flags & RESPONSE_FLAG_ERROR != 0 {
+        children.push(ins.status()?);
+    } else if code == OpCode::QueryContinuousEventNotification as i16 {
+        children.push(ins.events()?);
+    }
+    while ins.remaining() > 0 {
+        children.push(ins.data_object()?);
+    }
+    Ok(Node {
+        offset: 0,
+        label: "notification".to_string(),
+        children,
+    })
+}
+
+/// Decodes a recorded session. The first request and response are expected to be the handshake.
+/// The responses are decoded according to the version of the handshake request.
+/// Notifications are told from responses by their flags
+pub fn inspect_session(frames: &[RecordedFrame]) -> Result<Vec<Node>> {
+    let mut nodes = Vec::with_capacity(frames.len());
+    let mut op_code = None;
+    let mut has_flags = true;
+    for (i, frame) in frames.iter().enumerate() {
+        let node = match (frame.direction, i) {
+            (Direction::Request, 0) => {
+                has_flags = handshake_version(&frame.bytes)? >= FLAGS_VERSION;
+                inspect_handshake_request(&frame.bytes)?
+            }
+            (Direction::Response, 1) => inspect_handshake_response(&frame.bytes)?,
+            (Direction::Response, _) if has_flags && is_notification(&frame.bytes)? => {
+                inspect_notification(&frame.bytes)?
+            }
+            (Direction::Request, _) => {
+                op_code = frame
+                    .bytes
+                    .get(4..6)
+                    .map(|code| i16::from_le_bytes([code[0], code[1]]));
+                inspect_request(&frame.bytes)?
+            }
+            (Direction::Response, _) => response_node(&frame.bytes, op_code, has_flags)?,
+        };
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+/// Checks the flags of a response or notification frame, length prefix included
+fn is_notification(frame: &[u8]) -> Result<bool> {
+    let body = frame.get(4..).unwrap_or_default();
+    Ok(RespHeader::read(&mut Cursor::new(body), true)?
+        .notification
+        .is_some())
+}
+
+/// Decodes a handshake request frame, length prefix included
+pub fn inspect_handshake_request(frame: &[u8]) -> Result<Node> {
+    let mut ins = Inspector::new(frame, 0);
+    let mut children = vec![ins.len_prefix()?];
+    let offset = ins.pos();
+    children.push(Node::leaf(
+        offset,
+        format!("op code = {}", read_u8(&mut ins.cur)?),
+    ));
+    children.push(ins.version()?);
+    let offset = ins.pos();
+    children.push(Node::leaf(
+        offset,
+        format!("client code = {}", read_u8(&mut ins.cur)?),
+    ));
+    while ins.remaining() > 0 {
+        children.push(ins.data_object()?);
+    }
+    Ok(Node {
+        offset: 0,
+        label: "handshake request".to_string(),
+        children,
+    })
+}
+
+/// Decodes a handshake response frame, length prefix included
+pub fn inspect_handshake_response(frame: &[u8]) -> Result<Node> {
+    let mut ins = Inspector::new(frame, 0);
+    let mut children = vec![ins.len_prefix()?];
+    let offset = ins.pos();
+    let success = read_u8(&mut ins.cur)?;
+    children.push(Node::leaf(offset, format!("success = {}", success)));
+    if success != 1 {
+        children.push(ins.version()?);
+        children.push(ins.data_object()?);
+        if ins.remaining() > 0 {
+            children.push(ins.status()?);
+        }
+    } else {
+        // the feature mask and the node id
+        while ins.remaining() > 0 {
+            children.push(ins.data_object()?);
+        }
+    }
+    Ok(Node {
+        offset: 0,
+        label: "handshake response".to_string(),
+        children,
+    })
+}
+
+fn op_name(code: i16) -> String {
+    match OpCode::try_from(code) {
+        Ok(op) => format!("{} ({:?})", code, op),
+        Err(_) => format!("{} (unknown)", code),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_err(type_code: u8, offset: usize, desc: String) -> Error {
+    Error::Decode {
+        type_code,
+        offset,
+        desc,
+    }
+}
+
+struct Inspector<'a> {
+    cur: Cursor<&'a [u8]>,
+    /// Offset of the inspected bytes in the whole input
+    base: usize,
+}
+
+impl<'a> Inspector<'a> {
+    fn new(bytes: &'a [u8], base: usize) -> Inspector<'a> {
+        Inspector {
+            cur: Cursor::new(bytes),
+            base,
+        }
+    }
+
+    fn pos(&self) -> usize {
+        self.base + self.cur.position() as usize
+    }
+
+    /// Reads the flags of a response or notification and the topology version following them
+    fn response_flags(&mut self, children: &mut Vec<Node>) -> Result<i16> {
+        let offset = self.pos();
+        let flags = read_i16(&mut self.cur)?;
+        let names: Vec<&str> = RESPONSE_FLAG_NAMES
+            .iter()
+            .filter(|(flag, _)| flags & flag != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        children.push(Node::leaf(
+            offset,
+            format!("flags = 0x{:04X} [{}]", flags, names.join(", ")),
+        ));
+        if flags & RESPONSE_FLAG_TOPOLOGY_CHANGED != 0 {
+            let offset = self.pos();
+            let major = read_i64(&mut self.cur)?;
+            let minor = read_i32(&mut self.cur)?;
+            children.push(Node::leaf(
+                offset,
+                format!("topology version = {}.{}", major, minor),
+            ));
+        }
+        Ok(flags)
+    }
+
+    fn status(&mut self) -> Result<Node> {
+        let offset = self.pos();
+        let status = read_i32(&mut self.cur)?;
+        Ok(Node::leaf(
+            offset,
+            format!("status = {} ({:?})", status, StatusCode::from(status)),
+        ))
+    }
+
+    fn remaining(&self) -> usize {
+        self.cur.get_ref().len() - self.cur.position() as usize
+    }
+
+    /// Consumes the given number of bytes
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let start = self.cur.position() as usize;
+        let bytes: &'a [u8] = self.cur.get_ref();
+        let taken = bytes
+            .get(start..start + len)
+            .ok_or_else(|| Error::from("Unexpected end of the input"))?;
+        self.cur.set_position((start + len) as u64);
+        Ok(taken)
+    }
+
+    fn len_prefix(&mut self) -> Result<Node> {
+        let offset = self.pos();
+        Ok(Node::leaf(
+            offset,
+            format!("length = {}", read_i32(&mut self.cur)?),
+        ))
+    }
+
+    fn version(&mut self) -> Result<Node> {
+        let offset = self.pos();
+        let major = read_i16(&mut self.cur)?;
+        let minor = read_i16(&mut self.cur)?;
+        let patch = read_i16(&mut self.cur)?;
+        Ok(Node::leaf(
+            offset,
+            format!("version = {}.{}.{}", major, minor, patch),
+        ))
+    }
+
+    fn int_node(&mut self, name: &str) -> Result<Node> {
+        let offset = self.pos();
+        Ok(Node::leaf(
+            offset,
+            format!("{} = {}", name, read_i32(&mut self.cur)?),
+        ))
+    }
+
+    fn long_node(&mut self, name: &str) -> Result<Node> {
+        let offset = self.pos();
+        Ok(Node::leaf(
+            offset,
+            format!("{} = {}", name, read_i64(&mut self.cur)?),
+        ))
+    }
+
+    fn rest(&mut self) -> Result<Vec<Node>> {
+        if self.remaining() == 0 {
+            return Ok(vec![]);
+        }
+        let offset = self.pos();
+        let bytes = self.take(self.remaining())?;
+        Ok(vec![Node::leaf(
+            offset,
+            format!("payload ({} bytes) = {}", bytes.len(), hex(bytes)),
+        )])
+    }
+
+    /// Reads a count followed by as many groups of data objects
+    fn counted_objects(&mut self, name: &str, group: usize) -> Result<Node> {
+        let offset = self.pos();
+        let count = read_i32(&mut self.cur)?;
+        let mut children = Vec::new();
+        for _ in 0..count {
+            for _ in 0..group {
+                children.push(self.data_object()?);
+            }
+        }
+        Ok(Node {
+            offset,
+            label: format!("{} = {}", name, count),
+            children,
+        })
+    }
+
+    /// Reads the continuous query events: a count followed by the key, the old value,
+    /// the new value and the event type of each
+    fn events(&mut self) -> Result<Node> {
+        let offset = self.pos();
+        let count = read_i32(&mut self.cur)?;
+        let mut children = Vec::new();
+        for _ in 0..count {
+            for _ in 0..3 {
+                children.push(self.data_object()?);
+            }
+            let offset = self.pos();
+            let event_type = read_u8(&mut self.cur)?;
+            children.push(Node::leaf(offset, format!("event type = {}", event_type)));
+        }
+        Ok(Node {
+            offset,
+            label: format!("event count = {}", count),
+            children,
+        })
+    }
+
+    fn request_payload(&mut self, op_code: Option<OpCode>) -> Result<Vec<Node>> {
+        let op_code = match op_code {
+            Some(op)
+                if (op as i16 >= 1000 && op as i16 <= 1020)
+                    || op == OpCode::QueryScan
+                    || op == OpCode::QueryContinuous =>
+            {
+                op
+            }
+            _ => return self.rest(),
+        };
+        let mut nodes = vec![self.int_node("cache id")?];
+        let offset = self.pos();
+        let flags = read_u8(&mut self.cur)?;
+        nodes.push(Node::leaf(offset, format!("flags = 0x{:02X}", flags)));
+        if flags & FLAG_TRANSACTIONAL != 0 {
+            nodes.push(self.int_node("transaction id")?);
+        }
+        if flags & FLAG_WITH_EXPIRY_POLICY != 0 {
+            nodes.push(self.long_node("expiry for create")?);
+            nodes.push(self.long_node("expiry for update")?);
+            nodes.push(self.long_node("expiry for access")?);
+        }
+        match op_code {
+            OpCode::CacheGet
+            | OpCode::CacheContainsKey
+            | OpCode::CacheGetAndRemove
+            | OpCode::CacheClearKey
+            | OpCode::CacheRemoveKey => nodes.push(self.data_object()?),
+            OpCode::CachePut
+            | OpCode::CacheGetAndPut
+            | OpCode::CacheGetAndReplace
+            | OpCode::CachePutIfAbsent
+            | OpCode::CacheGetAndPutIfAbsent
+            | OpCode::CacheReplace
+            | OpCode::CacheRemoveIfEquals => {
+                nodes.push(self.data_object()?);
+                nodes.push(self.data_object()?);
+            }
+            OpCode::CacheReplaceIfEquals => {
+                for _ in 0..3 {
+                    nodes.push(self.data_object()?);
+                }
+            }
+            OpCode::CacheGetAll
+            | OpCode::CacheContainsKeys
+            | OpCode::CacheClearKeys
+            | OpCode::CacheRemoveKeys => nodes.push(self.counted_objects("key count", 1)?),
+            OpCode::CachePutAll => nodes.push(self.counted_objects("pair count", 2)?),
+            OpCode::QueryScan => {
+                nodes.push(self.data_object()?); // filter
+                nodes.push(self.int_node("page size")?);
+                nodes.push(self.int_node("partitions")?);
+                let offset = self.pos();
+                let local = read_bool(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("local = {}", local)));
+            }
+            OpCode::QueryContinuous => {
+                nodes.push(self.int_node("page size")?);
+                let offset = self.pos();
+                let interval = read_i64(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("time interval = {}", interval)));
+                let offset = self.pos();
+                let expired = read_bool(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("include expired = {}", expired)));
+                nodes.push(self.data_object()?); // filter
+                nodes.push(self.data_object()?); // transformer
+                let offset = self.pos();
+                let query_type = read_u8(&mut self.cur)?;
+                nodes.push(Node::leaf(
+                    offset,
+                    format!("initial query = {}", query_type),
+                ));
+            }
+            _ => {}
+        }
+        nodes.extend(self.rest()?);
+        Ok(nodes)
+    }
+
+    fn response_payload(&mut self, op_code: Option<OpCode>) -> Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        match op_code {
+            Some(OpCode::CacheGet)
+            | Some(OpCode::CacheGetAndPut)
+            | Some(OpCode::CacheGetAndReplace)
+            | Some(OpCode::CacheGetAndRemove)
+            | Some(OpCode::CacheGetAndPutIfAbsent)
+            | Some(OpCode::ServiceInvoke) => nodes.push(self.data_object()?),
+            Some(OpCode::CacheGetAll) => nodes.push(self.counted_objects("pair count", 2)?),
+            Some(OpCode::CacheGetNames) => nodes.push(self.counted_objects("cache count", 1)?),
+            Some(OpCode::CachePutIfAbsent)
+            | Some(OpCode::CacheReplace)
+            | Some(OpCode::CacheReplaceIfEquals)
+            | Some(OpCode::CacheContainsKey)
+            | Some(OpCode::CacheContainsKeys)
+            | Some(OpCode::CacheRemoveKey)
+            | Some(OpCode::CacheRemoveIfEquals)
+            | Some(OpCode::ClusterChangeWalState)
+            | Some(OpCode::ClusterGetWalState) => {
+                let offset = self.pos();
+                let result = read_bool(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("result = {}", result)));
+            }
+            Some(OpCode::CacheGetSize) => {
+                let offset = self.pos();
+                let size = read_i64(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("size = {}", size)));
+            }
+            Some(OpCode::QueryScan) | Some(OpCode::QuerySql) => {
+                let offset = self.pos();
+                let cursor_id = read_i64(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("cursor id = {}", cursor_id)));
+                nodes.push(self.counted_objects("row count", 2)?);
+                let offset = self.pos();
+                let more = read_bool(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("more = {}", more)));
+            }
+            Some(OpCode::QueryContinuous) => {
+                let offset = self.pos();
+                let cursor_id = read_i64(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("cursor id = {}", cursor_id)));
+            }
+            Some(OpCode::DataStreamerStart) => {
+                let offset = self.pos();
+                let streamer_id = read_i64(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("streamer id = {}", streamer_id)));
+            }
+            Some(OpCode::TxStart) => nodes.push(self.int_node("transaction id")?),
+            Some(OpCode::ComputeTaskExecute) => {
+                let offset = self.pos();
+                let task_id = read_i64(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("task id = {}", task_id)));
+            }
+            Some(OpCode::ClusterGetState) => {
+                let offset = self.pos();
+                let state = read_u8(&mut self.cur)?;
+                nodes.push(Node::leaf(offset, format!("cluster state = {}", state)));
+            }
+            _ => {}
+        }
+        nodes.extend(self.rest()?);
+        Ok(nodes)
+    }
+
+    /// Decodes a data object starting with the type code
+    fn data_object(&mut self) -> Result<Node> {
+        let offset = self.pos();
+        let code = read_u8(&mut self.cur)?;
+        let type_code = TypeCode::try_from(code)
+            .map_err(|_| decode_err(code, offset, "Unknown type code".to_string()))?;
+        let name = format!("{:?} ({})", type_code, code);
+        self.data_object_body(type_code, offset, name)
+            .map_err(|err| match err {
+                Error::Decode { .. } => err,
+                _ => decode_err(code, offset, err.to_string()),
+            })
+    }
+
+    fn data_object_body(
+        &mut self,
+        type_code: TypeCode,
+        offset: usize,
+        name: String,
+    ) -> Result<Node> {
+        let cur = &mut self.cur;
+        let value = match type_code {
+            TypeCode::Byte => read_i8(cur)?.to_string(),
+            TypeCode::Short => read_i16(cur)?.to_string(),
+            TypeCode::Int => read_i32(cur)?.to_string(),
+            TypeCode::Long => read_i64(cur)?.to_string(),
+            TypeCode::Float => read_f32(cur)?.to_string(),
+            TypeCode::Double => read_f64(cur)?.to_string(),
+            TypeCode::Char => {
+                let c = read_u16(cur)?;
+                match std::char::from_u32(c as u32) {
+                    Some(ch) => format!("{:?} ({})", ch, c),
+                    None => c.to_string(),
+                }
+            }
+            TypeCode::Bool => read_bool(cur)?.to_string(),
+            TypeCode::String => format!("{:?}", read_string(cur)?),
+            TypeCode::Uuid => {
+                let uuid = Uuid {
+                    most_sig_bits: read_i64(cur)?,
+                    least_sig_bits: read_i64(cur)?,
+                };
+                let s = format!("{:032x}", u128::from(uuid));
+                format!(
+                    "{}-{}-{}-{}-{}",
+                    &s[0..8],
+                    &s[8..12],
+                    &s[12..16],
+                    &s[16..20],
+                    &s[20..32]
+                )
+            }
+            TypeCode::Date => format!("{} ms", read_i64(cur)?),
+            TypeCode::Timestamp => {
+                let millis = read_i64(cur)?;
+                let nanos = read_i32(cur)?;
+                format!("{} ms + {} ns", millis, nanos)
+            }
+            TypeCode::Decimal => {
+                let scale = read_i32(cur)?;
+                let len = read_len(cur)?;
+                let magnitude = self.take(len)?;
+                format!("scale = {}, magnitude = [{}]", scale, hex(magnitude))
+            }
+            TypeCode::Enum => {
+                let type_id = read_i32(cur)?;
+                let ordinal = read_i32(cur)?;
+                format!("type id = {}, ordinal = {}", type_id, ordinal)
+            }
+            TypeCode::ArrByte => {
+                let len = read_len(cur)?;
+                format!("[{}]", hex(self.take(len)?))
+            }
+            TypeCode::ArrShort => format!("{:?}", read_primitive_arr(cur, read_i16)?),
+            TypeCode::ArrInt => format!("{:?}", read_primitive_arr(cur, read_i32)?),
+            TypeCode::ArrLong => format!("{:?}", read_primitive_arr(cur, read_i64)?),
+            TypeCode::ArrFloat => format!("{:?}", read_primitive_arr(cur, read_f32)?),
+            TypeCode::ArrDouble => format!("{:?}", read_primitive_arr(cur, read_f64)?),
+            TypeCode::ArrChar => format!("{:?}", read_primitive_arr(cur, read_u16)?),
+            TypeCode::ArrBool => format!("{:?}", read_primitive_arr(cur, read_bool)?),
+            TypeCode::Null => return Ok(Node::leaf(offset, name)),
+            TypeCode::ArrString
+            | TypeCode::ArrUuid
+            | TypeCode::ArrDate
+            | TypeCode::ArrDecimal
+            | TypeCode::TimestampArray => {
+                let len = read_i32(cur)?;
+                return self.items(offset, format!("{} len = {}", name, len), len);
+            }
+            TypeCode::ArrEnum | TypeCode::ArrObj => {
+                let type_id = read_i32(cur)?;
+                let len = read_i32(cur)?;
+                let label = format!("{} type id = {}, len = {}", name, type_id, len);
+                return self.items(offset, label, len);
+            }
+            TypeCode::Collection => {
+                let len = read_i32(cur)?;
+                let kind = CollectionKind::try_from(read_i8(cur)?)?;
+                let label = format!("{} {:?}, len = {}", name, kind, len);
+                return self.items(offset, label, len);
+            }
+            TypeCode::Map => {
+                let len = read_i32(cur)?;
+                let kind = MapKind::try_from(read_i8(cur)?)?;
+                let mut children = Vec::new();
+                for _ in 0..len {
+                    let entry_offset = self.pos();
+                    let key = self.data_object()?;
+                    let value = self.data_object()?;
+                    children.push(Node {
+                        offset: entry_offset,
+                        label: "entry".to_string(),
+                        children: vec![key, value],
+                    });
+                }
+                return Ok(Node {
+                    offset,
+                    label: format!("{} {:?}, len = {}", name, kind, len),
+                    children,
+                });
+            }
+            TypeCode::WrappedData => return self.wrapped_data(offset, name),
+            TypeCode::ComplexObj => return self.complex_obj(offset, name),
+        };
+        Ok(Node::leaf(offset, format!("{} = {}", name, value)))
+    }
+
+    fn items(&mut self, offset: usize, label: String, len: i32) -> Result<Node> {
+        let mut children = Vec::new();
+        for _ in 0..len {
+            children.push(self.data_object()?);
+        }
+        Ok(Node {
+            offset,
+            label,
+            children,
+        })
+    }
+
+    fn wrapped_data(&mut self, offset: usize, name: String) -> Result<Node> {
+        let len = read_len(&mut self.cur)?;
+        let payload_offset = self.pos();
+        let payload = self.take(len)?;
+        let start = read_i32(&mut self.cur)?;
+        let mut inner = Inspector::new(payload, payload_offset);
+        inner.cur.set_position(start.max(0) as u64);
+        Ok(Node {
+            offset,
+            label: format!("{} len = {}, offset = {}", name, len, start),
+            children: vec![inner.data_object()?],
+        })
+    }
+
+    fn complex_obj(&mut self, offset: usize, name: String) -> Result<Node> {
+        let err =
+            |at: usize, desc: String| decode_err(TypeCode::ComplexObj as u8, offset + at, desc);
+        let version = read_u8(&mut self.cur)?;
+        let flags = read_u16(&mut self.cur)?;
+        let type_id = read_i32(&mut self.cur)?;
+        let hash_code = read_i32(&mut self.cur)?;
+        let len = read_i32(&mut self.cur)?;
+        let schema_id = read_i32(&mut self.cur)?;
+        let schema_offset = read_i32(&mut self.cur)?;
+        if len < COMPLEX_OBJ_HEADER_LEN {
+            return Err(err(12, format!("Invalid object length: {}", len)));
+        }
+        if schema_offset < COMPLEX_OBJ_HEADER_LEN || schema_offset > len {
+            return Err(err(20, format!("Invalid schema offset: {}", schema_offset)));
+        }
+        let (len, schema_offset) = (len as usize, schema_offset as usize);
+
+        let flag_names: Vec<&str> = FLAG_NAMES
+            .iter()
+            .filter(|(flag, _)| flags & flag != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        let mut children = vec![
+            Node::leaf(offset + 1, format!("version = {}", version)),
+            Node::leaf(
+                offset + 2,
+                format!("flags = 0x{:04X} [{}]", flags, flag_names.join(", ")),
+            ),
+            Node::leaf(offset + 4, format!("type id = {}", type_id)),
+            Node::leaf(offset + 8, format!("hash code = {}", hash_code)),
+            Node::leaf(offset + 12, format!("length = {}", len)),
+            Node::leaf(offset + 16, format!("schema id = {}", schema_id)),
+            Node::leaf(offset + 20, format!("schema offset = {}", schema_offset)),
+        ];
+
+        // the whole object, header included
+        let body = self.take(len - COMPLEX_OBJ_HEADER_LEN as usize)?;
+        let mut object = vec![0u8; COMPLEX_OBJ_HEADER_LEN as usize];
+        object.extend_from_slice(body);
+
+        let has_schema = flags & FLAG_HAS_SCHEMA != 0;
+        let raw_offset = match (flags & HAS_RAW_DATA != 0, has_schema) {
+            (false, _) => None,
+            (true, false) => Some(schema_offset),
+            (true, true) => {
+                let at = len.checked_sub(4).filter(|at| *at >= schema_offset);
+                let at = at.ok_or_else(|| err(12, "No room for raw data offset".to_string()))?;
+                let raw = &object[at..];
+                Some(i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]).max(0) as usize)
+            }
+        };
+        let fields_end = match raw_offset {
+            Some(raw) => raw,
+            None if has_schema => schema_offset,
+            None => len,
+        };
+        let raw_end = if has_schema { schema_offset } else { len };
+        if fields_end < COMPLEX_OBJ_HEADER_LEN as usize || fields_end > raw_end {
+            return Err(err(20, format!("Invalid raw data offset: {}", fields_end)));
+        }
+
+        let mut fields = Inspector::new(&object[..fields_end], offset);
+        fields.cur.set_position(COMPLEX_OBJ_HEADER_LEN as u64);
+        let mut field_nodes = Vec::new();
+        while fields.remaining() > 0 {
+            field_nodes.push(fields.data_object()?);
+        }
+        children.push(Node {
+            offset: offset + COMPLEX_OBJ_HEADER_LEN as usize,
+            label: format!("fields ({})", field_nodes.len()),
+            children: field_nodes,
+        });
+
+        if let Some(raw) = raw_offset {
+            children.push(Node::leaf(
+                offset + raw,
+                format!("raw data = [{}]", hex(&object[raw..raw_end])),
+            ));
+        }
+
+        if has_schema {
+            let footer_end = match raw_offset {
+                Some(_) => len - 4,
+                None => len,
+            };
+            let offset_size = match (
+                flags & FLAG_OFFSET_ONE_BYTE != 0,
+                flags & FLAG_OFFSET_TWO_BYTES != 0,
+            ) {
+                (true, false) => 1,
+                (false, true) => 2,
+                (false, false) => 4,
+                (true, true) => return Err(err(2, format!("Invalid offset flags: {}", flags))),
+            };
+            let compact = flags & FLAG_COMPACT_FOOTER != 0;
+            let entry_size = if compact {
+                offset_size
+            } else {
+                4 + offset_size
+            };
+            let mut footer =
+                Inspector::new(&object[schema_offset..footer_end], offset + schema_offset);
+            let mut entries = Vec::new();
+            while footer.remaining() >= entry_size {
+                let entry_offset = footer.pos();
+                let field_id = if compact {
+                    None
+                } else {
+                    Some(read_i32(&mut footer.cur)?)
+                };
+                let field_offset = match offset_size {
+                    1 => read_u8(&mut footer.cur)? as u32,
+                    2 => read_u16(&mut footer.cur)? as u32,
+                    _ => read_i32(&mut footer.cur)? as u32,
+                };
+                let label = match field_id {
+                    Some(id) => format!("field id = {}, offset = {}", id, field_offset),
+                    None => format!("offset = {}", field_offset),
+                };
+                entries.push(Node::leaf(entry_offset, label));
+            }
+            if footer.remaining() > 0 {
+                return Err(err(schema_offset, "Truncated footer entry".to_string()));
+            }
+            children.push(Node {
+                offset: offset + schema_offset,
+                label: format!("footer ({} entries)", entries.len()),
+                children: entries,
+            });
+            if let Some(raw) = raw_offset {
+                children.push(Node::leaf(
+                    offset + footer_end,
+                    format!("raw data offset = {}", raw),
+                ));
+            }
+        }
+
+        Ok(Node {
+            offset,
+            label: name,
+            children,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::complex_obj::{
+        ComplexObject, ComplexObjectSchema, IgniteField, IgniteType, IgniteValue,
+    };
+    use crate::WritableType;
+    use std::sync::Arc;
+
+    fn flatten(node: &Node) -> Vec<String> {
+        let mut result = vec![node.label.clone()];
+        for child in &node.children {
+            result.extend(flatten(child));
+        }
+        result
+    }
+
+    #[test]
+    fn test_inspect_complex_object() {
+        let obj = ComplexObject {
+            schema: Arc::new(ComplexObjectSchema {
+                type_name: "Point".to_string(),
+                fields: vec![
+                    IgniteField {
+                        name: "X".to_string(),
+                        r#type: IgniteType::Int,
+                    },
+                    IgniteField {
+                        name: "NAME".to_string(),
+                        r#type: IgniteType::String,
+                    },
+                ],
+            }),
+            values: vec![IgniteValue::Int(7), IgniteValue::String("p".to_string())],
+        };
+        let mut bytes = Vec::new();
+        obj.write(&mut bytes).unwrap();
+
+        let node = inspect_data_object(&bytes).unwrap();
+        let labels = flatten(&node);
+        assert_eq!(labels[0], "ComplexObj (103)");
+        assert!(labels.contains(&"flags = 0x0003 [USER_TYPE, HAS_SCHEMA]".to_string()));
+        assert!(labels.contains(&"fields (2)".to_string()));
+        assert!(labels.contains(&"Int (3) = 7".to_string()));
+        assert!(labels.contains(&"String (9) = \"p\"".to_string()));
+        assert!(labels.contains(&"footer (2 entries)".to_string()));
+        let x_id = crate::utils::string_to_java_hashcode("x");
+        assert!(labels.contains(&format!("field id = {}, offset = 24", x_id)));
+
+        // fields start right after the header
+        let fields = &node.children[7];
+        assert_eq!(fields.children[0].offset, 24);
+        assert_eq!(fields.children[1].offset, 29);
+        assert!(node.to_string().contains("    24    fields (2)"));
+    }
+
+    #[test]
+    fn test_inspect_frames() {
+        // put(1, "a") into the cache 42
+        let req = hex_literal::hex!(
+            "1A 00 00 00" // length
+            "E9 03" // op code
+            "05 00 00 00 00 00 00 00" // request id
+            "2A 00 00 00 00" // cache id, flags
+            "03 01 00 00 00" // key
+            "09 01 00 00 00 61" // value
+        );
+        let labels = flatten(&inspect_request(&req).unwrap());
+        assert_eq!(
+            labels,
+            vec![
+                "request",
+                "length = 26",
+                "op code = 1001 (CachePut)",
+                "request id = 5",
+                "cache id = 42",
+                "flags = 0x00",
+                "Int (3) = 1",
+                "String (9) = \"a\"",
+            ]
+        );
+
+        // the same put with an expiry policy
+        let req = hex_literal::hex!(
+            "32 00 00 00" // length
+            "E9 03" // op code
+            "06 00 00 00 00 00 00 00" // request id
+            "2A 00 00 00 04" // cache id, flags
+            "E8 03 00 00 00 00 00 00" // create
+            "FE FF FF FF FF FF FF FF" // update
+            "FF FF FF FF FF FF FF FF" // access
+            "03 01 00 00 00" // key
+            "09 01 00 00 00 61" // value
+        );
+        let labels = flatten(&inspect_request(&req).unwrap());
+        assert_eq!(
+            labels[5..10],
+            [
+                "flags = 0x04",
+                "expiry for create = 1000",
+                "expiry for update = -2",
+                "expiry for access = -1",
+                "Int (3) = 1",
+            ]
+        );
+
+        let resp = hex_literal::hex!(
+            "1F 00 00 00" // length
+            "05 00 00 00 00 00 00 00" // request id
+            "03 00" // flags
+            "07 00 00 00 00 00 00 00 01 00 00 00" // topology version
+            "E8 03 00 00" // status
+            "09 00 00 00 00" // message
+        );
+        let labels = flatten(&inspect_response(&resp, None).unwrap());
+        assert_eq!(
+            labels[3..],
+            [
+                "flags = 0x0003 [ERROR, TOPOLOGY_CHANGED]",
+                "topology version = 7.1",
+                "status = 1000 (CacheDoesNotExist)",
+                "String (9) = \"\""
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inspect_events() {
+        let notification = hex_literal::hex!(
+            "29 00 00 00" // length
+            "03 00 00 00 00 00 00 00" // resource id
+            "06 00" // flags
+            "03 00 00 00 00 00 00 00 01 00 00 00" // topology version
+            "D7 07" // op code
+            "01 00 00 00" // event count
+            "03 01 00 00 00" // key
+            "65" // old value
+            "09 01 00 00 00 61" // new value
+            "00" // created
+        );
+        let labels = flatten(&inspect_notification(&notification).unwrap());
+        assert_eq!(
+            labels[2..],
+            [
+                "resource id = 3",
+                "flags = 0x0006 [TOPOLOGY_CHANGED, NOTIFICATION]",
+                "topology version = 3.1",
+                "op code = 2007 (QueryContinuousEventNotification)",
+                "event count = 1",
+                "Int (3) = 1",
+                "Null (101)",
+                "String (9) = \"a\"",
+                "event type = 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inspect_malformed() {
+        // string longer than the input
+        match inspect_data_object(&hex_literal::hex!("09 05 00 00 00 61")) {
+            Err(Error::Decode {
+                type_code, offset, ..
+            }) => assert_eq!((type_code, offset), (9, 0)),
+            _ => panic!("malformed string is decoded"),
+        }
+    }
+}
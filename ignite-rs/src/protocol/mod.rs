@@ -2,13 +2,19 @@ use std::io;
 use std::io::{ErrorKind, Read, Write};
 
 use crate::error::{Error, Result};
+use crate::protocol::complex_obj::decode_err;
 
-use crate::{Enum, ReadableType};
+use crate::{Date, Enum, ReadableType, Uuid};
 use std::convert::TryFrom;
 
+pub mod binary_object;
 pub(crate) mod cache_config;
 pub mod complex_obj;
 pub(crate) mod data_types;
+pub(crate) mod decimal;
+pub mod inspect;
+#[cfg(feature = "chrono")]
+pub(crate) mod timestamp;
 
 pub const FLAG_USER_TYPE: u16 = 0x0001;
 pub const FLAG_HAS_SCHEMA: u16 = 0x0002;
@@ -19,6 +25,17 @@ pub const FLAG_OFFSET_TWO_BYTES: u16 = 0x0010;
 
 pub const COMPLEX_OBJ_HEADER_LEN: i32 = 24;
 
+/// Flags of the response header since protocol 1.4.0. Notifications, the frames a server
+/// sends on its own rather than as a response, have the same header
+pub(crate) const RESPONSE_FLAG_ERROR: i16 = 0x0001;
+pub(crate) const RESPONSE_FLAG_TOPOLOGY_CHANGED: i16 = 0x0002;
+pub(crate) const RESPONSE_FLAG_NOTIFICATION: i16 = 0x0004;
+
+/// Max number of elements or bytes allocated upfront for a length prefix. Bigger data grows
+/// while being read, so a corrupted prefix fails at the end of the frame instead of
+/// allocating memory for data that doesn't exist
+const MAX_PREALLOC_LEN: usize = 1024;
+
 /// All Data types described in Binary Protocol
 /// https://apacheignite.readme.io/docs/binary-client-protocol-data-format
 #[derive(PartialOrd, PartialEq, Debug)]
@@ -32,6 +49,8 @@ pub enum TypeCode {
     Char = 7,
     Bool = 8,
     String = 9,
+    Uuid = 10,
+    Date = 11,
     ArrByte = 12,
     ArrShort = 13,
     ArrInt = 14,
@@ -40,12 +59,18 @@ pub enum TypeCode {
     ArrDouble = 17,
     ArrChar = 18,
     ArrBool = 19,
+    ArrString = 20,
+    ArrUuid = 21,
+    ArrDate = 22,
     ArrObj = 23,
     Collection = 24,
+    Map = 25,
     Decimal = 30,
     Timestamp = 33,
     WrappedData = 27,
     Enum = 28,
+    ArrEnum = 29,
+    ArrDecimal = 31,
     TimestampArray = 34,
     Null = 101,
     ComplexObj = 103,
@@ -65,6 +90,8 @@ impl TryFrom<u8> for TypeCode {
             7 => Ok(TypeCode::Char),
             8 => Ok(TypeCode::Bool),
             9 => Ok(TypeCode::String),
+            10 => Ok(TypeCode::Uuid),
+            11 => Ok(TypeCode::Date),
             28 => Ok(TypeCode::Enum),
             12 => Ok(TypeCode::ArrByte),
             13 => Ok(TypeCode::ArrShort),
@@ -74,10 +101,17 @@ impl TryFrom<u8> for TypeCode {
             17 => Ok(TypeCode::ArrDouble),
             18 => Ok(TypeCode::ArrChar),
             19 => Ok(TypeCode::ArrBool),
+            20 => Ok(TypeCode::ArrString),
+            21 => Ok(TypeCode::ArrUuid),
+            22 => Ok(TypeCode::ArrDate),
             23 => Ok(TypeCode::ArrObj),
+            29 => Ok(TypeCode::ArrEnum),
             30 => Ok(TypeCode::Decimal),
+            31 => Ok(TypeCode::ArrDecimal),
             33 => Ok(TypeCode::Timestamp),
+            34 => Ok(TypeCode::TimestampArray),
             24 => Ok(TypeCode::Collection),
+            25 => Ok(TypeCode::Map),
             27 => Ok(TypeCode::WrappedData),
             103 => Ok(TypeCode::ComplexObj),
             101 => Ok(TypeCode::Null),
@@ -88,10 +122,124 @@ impl TryFrom<u8> for TypeCode {
     }
 }
 
+/// Kind of the Map data object (type code = 25). Matches Java's map implementation
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MapKind {
+    UserMap = 0,
+    HashMap = 1,
+    LinkedHashMap = 2,
+}
+
+impl TryFrom<i8> for MapKind {
+    type Error = Error;
+
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MapKind::UserMap),
+            1 => Ok(MapKind::HashMap),
+            2 => Ok(MapKind::LinkedHashMap),
+            _ => Err(Error::from(
+                format!("Cannot read MapKind {}", value).as_str(),
+            )),
+        }
+    }
+}
+
+/// Kind of the Collection data object (type code = 24). Matches Java's collection implementation
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CollectionKind {
+    UserSet = -1,
+    UserCollection = 0,
+    ArrayList = 1,
+    LinkedList = 2,
+    HashSet = 3,
+    LinkedHashSet = 4,
+    SingletonList = 5,
+}
+
+impl TryFrom<i8> for CollectionKind {
+    type Error = Error;
+
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        match value {
+            -1 => Ok(CollectionKind::UserSet),
+            0 => Ok(CollectionKind::UserCollection),
+            1 => Ok(CollectionKind::ArrayList),
+            2 => Ok(CollectionKind::LinkedList),
+            3 => Ok(CollectionKind::HashSet),
+            4 => Ok(CollectionKind::LinkedHashSet),
+            5 => Ok(CollectionKind::SingletonList),
+            _ => Err(Error::from(
+                format!("Cannot read CollectionKind {}", value).as_str(),
+            )),
+        }
+    }
+}
+
 /// Flag of general Response header
 pub(crate) enum Flag {
     Success,
-    Failure { err_msg: String },
+    Failure { status: i32, err_msg: String },
+}
+
+/// Header of a response or a notification, up to the error status
+pub(crate) struct RespHeader {
+    /// Request id of a response, resource id of a notification
+    pub(crate) id: i64,
+    pub(crate) flags: i16,
+    /// Op code of a notification, none for a response
+    pub(crate) notification: Option<i16>,
+    /// Status of a header without the flags, it is read along with the request id
+    status: Option<i32>,
+}
+
+impl RespHeader {
+    /// Reads the header. The topology version is skipped.
+    /// Before 1.4.0 the status is sent instead of the flags, the error flag is set
+    /// for a failure status then
+    pub(crate) fn read(reader: &mut impl Read, has_flags: bool) -> io::Result<RespHeader> {
+        let id = read_i64(reader)?;
+        if !has_flags {
+            let status = read_i32(reader)?;
+            return Ok(RespHeader {
+                id,
+                flags: if status == 0 { 0 } else { RESPONSE_FLAG_ERROR },
+                notification: None,
+                status: Some(status),
+            });
+        }
+        let flags = read_i16(reader)?;
+        if flags & RESPONSE_FLAG_TOPOLOGY_CHANGED != 0 {
+            let _major_topology_version = read_i64(reader)?;
+            let _minor_topology_version = read_i32(reader)?;
+        }
+        let notification = match flags & RESPONSE_FLAG_NOTIFICATION {
+            0 => None,
+            _ => Some(read_i16(reader)?),
+        };
+        Ok(RespHeader {
+            id,
+            flags,
+            notification,
+            status: None,
+        })
+    }
+
+    /// Reads the error status and message that follow the header if the error flag is set
+    pub(crate) fn read_status(&self, reader: &mut impl Read) -> Result<Flag> {
+        if self.flags & RESPONSE_FLAG_ERROR == 0 {
+            return Ok(Flag::Success);
+        }
+        let status = match self.status {
+            Some(status) => status,
+            None => read_i32(reader)?,
+        };
+        let err_msg = String::read(reader)?;
+        Ok(Flag::Failure {
+            status,
+            err_msg: err_msg.unwrap_or_default(),
+        })
+    }
 }
 
 fn read_object(reader: &mut impl Read) -> Result<Option<()>> {
@@ -137,38 +285,57 @@ pub fn read_wrapped_data_dyn(
     }
 }
 
-/// Reads a complex object (type code = 103)
+/// Reads a complex object (type code = 103 is already consumed). The callback is called
+/// with the reader at the first field and the object length. It may read the fields partly,
+/// the rest of them and the footer are skipped by the schema offset and the object length
 pub fn read_complex_obj_dyn(
     reader: &mut dyn Read,
     cb: &mut dyn Fn(&mut dyn Read, i32) -> Result<()>,
 ) -> Result<()> {
-    let _ver = read_u8(reader)?; // 1
-    let flags = read_u16(reader)?; // 43
-    let _type_id = read_i32(reader)?;
-    let _hash_code = read_i32(reader)?;
-    let len = read_i32(reader)?; // 157
-    let _schema_id = read_i32(reader)?;
-
-    let value = cb(reader, len);
-
-    // Footer / schema
-    if flags & FLAG_HAS_SCHEMA != 0 {
-        if flags & FLAG_COMPACT_FOOTER != 0 {
-            if flags & FLAG_OFFSET_ONE_BYTE != 0 {
-                let field_count = 2;
-                for _ in 0..field_count {
-                    let _ = read_u8(reader)?;
-                }
-            } else {
-                todo!()
-            }
-        } else {
-            todo!()
-        }
-    } else {
-        // no schema, nothing to do
+    let _ver = read_u8(reader)?; // offset 1
+    let flags = read_u16(reader)?; // offset 2
+    let _type_id = read_i32(reader)?; // offset 4
+    let _hash_code = read_i32(reader)?; // offset 8
+    let len = read_i32(reader)?; // offset 12
+    let _schema_id = read_i32(reader)?; // offset 16
+    let schema_offset = read_i32(reader)?; // offset 20
+    if len < COMPLEX_OBJ_HEADER_LEN {
+        return Err(decode_err(
+            12,
+            format!("Invalid object length: {}, flags: 0x{:04X}", len, flags),
+        ));
+    }
+
+    // the fields and the raw data end where the footer starts, or at the raw data
+    // offset closing an object without a footer
+    let data_end = match (flags & FLAG_HAS_SCHEMA != 0, flags & HAS_RAW_DATA != 0) {
+        (true, _) => schema_offset,
+        (false, true) => len - 4,
+        (false, false) => len,
+    };
+    if data_end < COMPLEX_OBJ_HEADER_LEN || data_end > len {
+        return Err(decode_err(
+            20,
+            format!(
+                "Invalid schema offset: {} of an object of {} bytes, flags: 0x{:04X}",
+                schema_offset, len, flags
+            ),
+        ));
     }
-    value
+
+    let mut fields = reader.take((data_end - COMPLEX_OBJ_HEADER_LEN) as u64);
+    cb(&mut fields, len)?;
+    let unread = fields.limit();
+    skip(reader, unread + (len - data_end) as u64)
+}
+
+/// Reads and drops the bytes
+fn skip(reader: &mut dyn Read, len: u64) -> Result<()> {
+    let skipped = io::copy(&mut reader.take(len), &mut io::sink())?;
+    if skipped < len {
+        return Err(Error::from(io::Error::from(ErrorKind::UnexpectedEof)));
+    }
+    Ok(())
 }
 
 /// This function is basically a String's PackType implementation but for &str.
@@ -182,7 +349,7 @@ pub(crate) fn write_string_type_code(writer: &mut dyn Write, value: &str) -> io:
     Ok(())
 }
 
-//// Read functions. No TypeCode, no NULL checking
+// Read functions. No TypeCode, no NULL checking
 
 pub fn write_string(writer: &mut dyn Write, value: &str) -> io::Result<()> {
     let value_bytes = value.as_bytes();
@@ -191,16 +358,44 @@ pub fn write_string(writer: &mut dyn Write, value: &str) -> io::Result<()> {
     Ok(())
 }
 
+/// Reads a length prefix. Negative lengths are rejected
+pub fn read_len(reader: &mut (impl Read + ?Sized)) -> io::Result<usize> {
+    let len = read_i32(reader)?;
+    if len < 0 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Negative length: {}", len),
+        ));
+    }
+    Ok(len as usize)
+}
+
+/// Capacity to allocate upfront for the given length prefix
+pub(crate) fn prealloc_len(len: i32) -> usize {
+    (len.max(0) as usize).min(MAX_PREALLOC_LEN)
+}
+
+/// Reads exactly `len` bytes. Memory is allocated as the data arrives,
+/// so a length prefix exceeding the remaining data fails without allocating it
+pub fn read_bytes(reader: &mut (impl Read + ?Sized), len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(len.min(MAX_PREALLOC_LEN));
+    Read::take(&mut *reader, len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("Length {} exceeds the remaining {} bytes", len, buf.len()),
+        ));
+    }
+    Ok(buf)
+}
+
 pub fn read_string(reader: &mut (impl Read + ?Sized)) -> io::Result<String> {
-    let str_len = read_i32(reader)?;
-
-    let mut new_alloc = vec![0u8; str_len as usize];
-    match reader.read_exact(new_alloc.as_mut_slice()) {
-        Ok(_) => match String::from_utf8(new_alloc) {
-            Ok(s) => Ok(s),
-            Err(err) => Err(io::Error::new(ErrorKind::InvalidData, err)),
-        },
-        Err(err) => Err(err),
+    let str_len = read_len(reader)?;
+
+    let new_alloc = read_bytes(reader, str_len)?;
+    match String::from_utf8(new_alloc) {
+        Ok(s) => Ok(s),
+        Err(err) => Err(io::Error::new(ErrorKind::InvalidData, err)),
     }
 }
 
@@ -355,8 +550,8 @@ where
     R: Read,
     F: Fn(&mut R) -> io::Result<T>,
 {
-    let len = read_i32(reader)?;
-    let mut payload: Vec<T> = Vec::with_capacity(len as usize);
+    let len = read_len(reader)?;
+    let mut payload: Vec<T> = Vec::with_capacity(len.min(MAX_PREALLOC_LEN));
     for _ in 0..len {
         payload.push(read_fn(reader)?);
     }
@@ -375,6 +570,30 @@ pub fn write_enum(writer: &mut dyn Write, val: Enum) -> io::Result<()> {
     Ok(())
 }
 
+pub fn read_uuid(reader: &mut impl Read) -> io::Result<Uuid> {
+    let most_sig_bits = read_i64(reader)?;
+    let least_sig_bits = read_i64(reader)?;
+    Ok(Uuid {
+        most_sig_bits,
+        least_sig_bits,
+    })
+}
+
+pub fn write_uuid(writer: &mut dyn Write, val: Uuid) -> io::Result<()> {
+    write_i64(writer, val.most_sig_bits)?;
+    write_i64(writer, val.least_sig_bits)?;
+    Ok(())
+}
+
+pub fn read_date(reader: &mut impl Read) -> io::Result<Date> {
+    let millis = read_i64(reader)?;
+    Ok(Date { millis })
+}
+
+pub fn write_date(writer: &mut dyn Write, val: Date) -> io::Result<()> {
+    write_i64(writer, val.millis)
+}
+
 pub fn write_null(writer: &mut dyn Write) -> io::Result<()> {
     write_u8(writer, TypeCode::Null as u8)?;
     Ok(())
@@ -0,0 +1,233 @@
+use std::convert::TryFrom;
+use std::io;
+use std::io::{Read, Write};
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+use crate::error::{Error, Result};
+use crate::protocol::complex_obj::IgniteValue;
+use crate::protocol::data_types::read_typed_arr;
+use crate::protocol::{read_i32, read_i64, write_i32, write_i64, write_u8, TypeCode};
+use crate::{ReadableType, WritableType};
+
+// Ignite's Timestamp is a number of milliseconds elapsed since 00:00:00 1 Jan 1970 UTC
+// followed by a nanosecond fraction of a millisecond. It carries no time zone,
+// so NaiveDateTime values are treated as UTC.
+
+const NANOS_PER_MILLI: u32 = 1_000_000;
+const TIMESTAMP_SIZE: usize = 8 + 4 + 1; // millis, nanos, type code
+
+/// Splits a date-time into Ignite's (milliseconds, nanosecond fraction) pair
+fn to_timestamp(value: &DateTime<Utc>) -> (i64, i32) {
+    let millis = value.timestamp_millis();
+    let nanos = value.timestamp_subsec_nanos() % NANOS_PER_MILLI;
+    (millis, nanos as i32)
+}
+
+/// Builds a date-time from Ignite's (milliseconds, nanosecond fraction) pair
+fn from_timestamp(millis: i64, nanos: i32) -> Result<DateTime<Utc>> {
+    if nanos < 0 || nanos as u32 >= NANOS_PER_MILLI {
+        return Err(Error::from(
+            format!("Invalid Timestamp nanosecond fraction: {}", nanos).as_str(),
+        ));
+    }
+    DateTime::<Utc>::from_timestamp_millis(millis)
+        .and_then(|dt| dt.checked_add_signed(Duration::nanoseconds(nanos as i64)))
+        .ok_or_else(|| Error::from(format!("Timestamp is out of range: {}", millis).as_str()))
+}
+
+fn write_timestamp(writer: &mut dyn Write, value: &DateTime<Utc>) -> io::Result<()> {
+    let (millis, nanos) = to_timestamp(value);
+    write_u8(writer, TypeCode::Timestamp as u8)?;
+    write_i64(writer, millis)?;
+    write_i32(writer, nanos)?;
+    Ok(())
+}
+
+fn read_timestamp(reader: &mut impl Read) -> Result<DateTime<Utc>> {
+    let millis = read_i64(reader)?;
+    let nanos = read_i32(reader)?;
+    from_timestamp(millis, nanos)
+}
+
+impl WritableType for DateTime<Utc> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_timestamp(writer, self)
+    }
+
+    fn size(&self) -> usize {
+        TIMESTAMP_SIZE
+    }
+}
+
+impl ReadableType for DateTime<Utc> {
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        match type_code {
+            TypeCode::Null => Ok(None),
+            TypeCode::Timestamp => Ok(Some(read_timestamp(reader)?)),
+            _ => Err(Error::from("Expected Timestamp!")),
+        }
+    }
+}
+
+impl WritableType for NaiveDateTime {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_timestamp(writer, &self.and_utc())
+    }
+
+    fn size(&self) -> usize {
+        TIMESTAMP_SIZE
+    }
+}
+
+impl ReadableType for NaiveDateTime {
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        DateTime::<Utc>::read_unwrapped(type_code, reader).map(|dt| dt.map(|dt| dt.naive_utc()))
+    }
+}
+
+impl WritableType for Vec<DateTime<Utc>> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u8(writer, TypeCode::TimestampArray as u8)?;
+        write_i32(writer, self.len() as i32)?; // length of array
+        for el in self {
+            write_timestamp(writer, el)?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        TIMESTAMP_SIZE * self.len() + 4 + 1 // items, len, type code
+    }
+}
+
+impl ReadableType for Vec<DateTime<Utc>> {
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        match type_code {
+            TypeCode::Null => Ok(None),
+            TypeCode::TimestampArray => Ok(Some(read_typed_arr(reader)?)),
+            _ => Err(Error::from("Expected Timestamp array!")),
+        }
+    }
+}
+
+impl WritableType for Vec<NaiveDateTime> {
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u8(writer, TypeCode::TimestampArray as u8)?;
+        write_i32(writer, self.len() as i32)?; // length of array
+        for el in self {
+            write_timestamp(writer, &el.and_utc())?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        TIMESTAMP_SIZE * self.len() + 4 + 1 // items, len, type code
+    }
+}
+
+impl ReadableType for Vec<NaiveDateTime> {
+    fn read_unwrapped(type_code: TypeCode, reader: &mut impl Read) -> Result<Option<Self>> {
+        Vec::<DateTime<Utc>>::read_unwrapped(type_code, reader)
+            .map(|arr| arr.map(|arr| arr.iter().map(|dt| dt.naive_utc()).collect()))
+    }
+}
+
+impl From<DateTime<Utc>> for IgniteValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        let (millis, nanos) = to_timestamp(&value);
+        IgniteValue::Timestamp(millis, nanos)
+    }
+}
+
+impl From<NaiveDateTime> for IgniteValue {
+    fn from(value: NaiveDateTime) -> Self {
+        IgniteValue::from(value.and_utc())
+    }
+}
+
+impl TryFrom<&IgniteValue> for DateTime<Utc> {
+    type Error = Error;
+
+    fn try_from(value: &IgniteValue) -> Result<Self, Self::Error> {
+        match value {
+            IgniteValue::Timestamp(millis, nanos) => from_timestamp(*millis, *nanos),
+            _ => Err(Error::from("Expected Timestamp!")),
+        }
+    }
+}
+
+impl TryFrom<&IgniteValue> for NaiveDateTime {
+    type Error = Error;
+
+    fn try_from(value: &IgniteValue) -> Result<Self, Self::Error> {
+        DateTime::<Utc>::try_from(value).map(|dt| dt.naive_utc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::io::Cursor;
+
+    fn sample() -> DateTime<Utc> {
+        // 2023-06-21 12:34:56.789012345 UTC
+        NaiveDate::from_ymd_opt(2023, 6, 21)
+            .unwrap()
+            .and_hms_nano_opt(12, 34, 56, 789_012_345)
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let expected_bytes = hex_literal::hex!(
+            "21" // type code
+            "95 68 F3 DD 88 01 00 00" // milliseconds: 1687350896789
+            "39 30 00 00" // nanosecond fraction: 12345
+        );
+
+        let mut actual_bytes = vec![];
+        sample().write(&mut actual_bytes).unwrap();
+        assert_eq!(actual_bytes, expected_bytes.to_vec());
+        assert_eq!(sample().size(), expected_bytes.len());
+
+        let mut reader = Cursor::new(expected_bytes);
+        let actual = DateTime::<Utc>::read(&mut reader).unwrap();
+        assert_eq!(actual, Some(sample()));
+
+        let mut reader = Cursor::new(expected_bytes);
+        let actual = NaiveDateTime::read(&mut reader).unwrap();
+        assert_eq!(actual, Some(sample().naive_utc()));
+    }
+
+    #[test]
+    fn test_timestamp_before_epoch() {
+        let expected =
+            DateTime::<Utc>::from_timestamp_millis(-1_500).unwrap() + Duration::nanoseconds(250);
+        let value = IgniteValue::from(expected);
+        assert_eq!(value, IgniteValue::Timestamp(-1_500, 250));
+        assert_eq!(DateTime::<Utc>::try_from(&value).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_timestamp_arr_round_trip() {
+        let expected = vec![sample(), DateTime::<Utc>::from_timestamp_millis(0).unwrap()];
+
+        let mut bytes = vec![];
+        expected.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), expected.size());
+        assert_eq!(bytes[0], TypeCode::TimestampArray as u8);
+
+        let mut reader = Cursor::new(bytes);
+        let actual = Vec::<DateTime<Utc>>::read(&mut reader).unwrap();
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        assert!(DateTime::<Utc>::try_from(&IgniteValue::Long(1)).is_err());
+        assert!(DateTime::<Utc>::try_from(&IgniteValue::Timestamp(0, 1_000_000)).is_err());
+    }
+}
@@ -0,0 +1,493 @@
+//! Recording and replaying of protocol sessions.
+//!
+//! If `ClientConfig::session_record_path` is set, every frame the client sends and receives,
+//! the handshake included, is appended to that file. A session file is a sequence of records:
+//! the direction byte (0 for requests, 1 for responses) followed by the frame as it was
+//! sent over the wire, length prefix included. The only exception is the user name and
+//! password of the handshake request: they are replaced with a placeholder before being
+//! written, so the session files carry no credentials. A failure to write the file stops
+//! the recording, the connection itself is not affected.
+//!
+//! `ReplayServer` (available in tests and with the "fake-server" feature) serves a recorded
+//! session back, so traffic captured once against a real cluster becomes a deterministic test.
+
+use std::convert::TryFrom;
+use std::io;
+use std::io::{Cursor, ErrorKind, Read, Write};
+#[cfg(any(test, feature = "fake-server"))]
+use std::net::{SocketAddr, TcpListener, TcpStream};
+#[cfg(any(test, feature = "fake-server"))]
+use std::path::Path;
+#[cfg(any(test, feature = "fake-server"))]
+use std::thread;
+#[cfg(any(test, feature = "fake-server"))]
+use std::thread::JoinHandle;
+
+#[cfg(any(test, feature = "fake-server"))]
+use crate::error::StatusCode;
+use crate::protocol::complex_obj::{read_value, write_value, IgniteValue};
+use crate::protocol::{read_bytes, read_i16, read_len, read_u8, write_i32, write_u8, TypeCode};
+#[cfg(any(test, feature = "fake-server"))]
+use crate::protocol::{read_i32, write_i16, write_string_type_code};
+#[cfg(all(any(test, feature = "fake-server"), not(feature = "ssl")))]
+use crate::ClientConfig;
+
+/// Direction of a recorded frame
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent by the client
+    Request = 0,
+    /// Sent by the server
+    Response = 1,
+}
+
+impl TryFrom<u8> for Direction {
+    type Error = io::Error;
+
+    fn try_from(value: u8) -> io::Result<Self> {
+        match value {
+            0 => Ok(Direction::Request),
+            1 => Ok(Direction::Response),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Unknown frame direction {}", value),
+            )),
+        }
+    }
+}
+
+/// A frame of the recorded session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedFrame {
+    pub direction: Direction,
+    /// Frame bytes including the length prefix
+    pub bytes: Vec<u8>,
+}
+
+/// Written instead of the user name and password of the recorded handshake
+const REDACTED: &str = "<redacted>";
+
+/// Length prefix, op code, version and client code of the handshake request
+const HANDSHAKE_HEADER_LEN: usize = 12;
+
+/// Reads all the frames of a recorded session
+pub fn read_session(reader: &mut impl Read) -> io::Result<Vec<RecordedFrame>> {
+    let mut frames = Vec::new();
+    loop {
+        let direction = match read_u8(reader) {
+            Ok(code) => Direction::try_from(code)?,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(frames),
+            Err(err) => return Err(err),
+        };
+        frames.push(RecordedFrame {
+            direction,
+            bytes: read_frame(reader)?,
+        });
+    }
+}
+
+/// Reads a length-prefixed frame and returns it with the prefix
+fn read_frame(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_len(reader)?;
+    let mut bytes = (len as i32).to_le_bytes().to_vec();
+    bytes.append(&mut read_bytes(reader, len)?);
+    Ok(bytes)
+}
+
+/// Reads the protocol version of a handshake request frame, length prefix included
+pub(crate) fn handshake_version(frame: &[u8]) -> io::Result<(i16, i16, i16)> {
+    let mut reader = Cursor::new(frame.get(5..).unwrap_or_default());
+    Ok((
+        read_i16(&mut reader)?,
+        read_i16(&mut reader)?,
+        read_i16(&mut reader)?,
+    ))
+}
+
+/// Replaces the credentials of the handshake request (length prefix included).
+/// They are the last two values of the request and the only strings there
+fn redact_handshake(frame: &[u8]) -> io::Result<Vec<u8>> {
+    if frame.len() < HANDSHAKE_HEADER_LEN {
+        return Ok(frame.to_vec());
+    }
+    let mut reader = Cursor::new(&frame[HANDSHAKE_HEADER_LEN..]);
+    let mut values = Vec::new();
+    while (reader.position() as usize) < reader.get_ref().len() {
+        let value = TypeCode::try_from(read_u8(&mut reader)?)
+            .and_then(|type_code| read_value(type_code, &mut reader))
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        values.push(value);
+    }
+    if let [.., IgniteValue::String(user), IgniteValue::String(pass)] = values.as_mut_slice() {
+        *user = REDACTED.to_string();
+        *pass = REDACTED.to_string();
+    }
+
+    let mut body = frame[4..HANDSHAKE_HEADER_LEN].to_vec();
+    for value in values.iter() {
+        write_value(&mut body, value)?;
+    }
+    let mut redacted = Vec::with_capacity(4 + body.len());
+    write_i32(&mut redacted, body.len() as i32)?;
+    redacted.append(&mut body);
+    Ok(redacted)
+}
+
+/// Stream wrapper that writes every complete frame passing through it to the sink.
+/// Does nothing but forwarding if there is no sink
+pub(crate) struct RecordingStream<S: Read + Write> {
+    inner: S,
+    sink: Option<Box<dyn Write + Send>>,
+    written: Vec<u8>,
+    read: Vec<u8>,
+    /// The first request is the handshake, which is recorded without the credentials
+    handshake_recorded: bool,
+}
+
+impl<S: Read + Write> RecordingStream<S> {
+    pub(crate) fn new(inner: S, sink: Option<Box<dyn Write + Send>>) -> RecordingStream<S> {
+        RecordingStream {
+            inner,
+            sink,
+            written: Vec::new(),
+            read: Vec::new(),
+            handshake_recorded: false,
+        }
+    }
+
+    /// Records the complete request frames, the handshake without the credentials
+    fn record_requests(&mut self) -> io::Result<()> {
+        let sink = match self.sink.as_mut() {
+            Some(sink) => sink,
+            None => return Ok(()),
+        };
+        if !self.handshake_recorded {
+            if let Some(len) = complete_frame_len(&self.written) {
+                let redacted = redact_handshake(&self.written[..len])?;
+                self.written.splice(..len, redacted);
+                self.handshake_recorded = true;
+            }
+        }
+        record_frames(sink, &mut self.written, Direction::Request)
+    }
+
+    /// Drops the sink that failed. The recording is for debugging,
+    /// so its failure is reported without failing the client's I/O
+    fn stop_recording(&mut self, err: io::Error) {
+        eprintln!("Session recording stopped: {}", err);
+        self.sink = None;
+        self.written.clear();
+        self.read.clear();
+    }
+}
+
+/// Length of the first frame in the buffer if it is complete
+fn complete_frame_len(buf: &[u8]) -> Option<usize> {
+    if buf.len() < 4 {
+        return None;
+    }
+    let len = 4 + i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]).max(0) as usize;
+    Some(len).filter(|len| buf.len() >= *len)
+}
+
+/// Moves the complete frames from the buffer to the sink
+fn record_frames(sink: &mut dyn Write, buf: &mut Vec<u8>, direction: Direction) -> io::Result<()> {
+    while let Some(len) = complete_frame_len(buf) {
+        write_u8(sink, direction as u8)?;
+        sink.write_all(&buf[..len])?;
+        buf.drain(..len);
+    }
+    sink.flush()
+}
+
+impl<S: Read + Write> Read for RecordingStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(sink) = self.sink.as_mut() {
+            self.read.extend_from_slice(&buf[..n]);
+            if let Err(err) = record_frames(sink, &mut self.read, Direction::Response) {
+                self.stop_recording(err);
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl<S: Read + Write> Write for RecordingStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if self.sink.is_some() {
+            self.written.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if let Err(err) = self.record_requests() {
+            self.stop_recording(err);
+        }
+        Ok(())
+    }
+}
+
+/// Serves a recorded session to a single client.
+/// Requests are expected to be exactly the same as the recorded ones,
+/// except for the credentials of the handshake
+#[cfg(any(test, feature = "fake-server"))]
+pub struct ReplayServer {
+    addr: SocketAddr,
+    handle: JoinHandle<io::Result<()>>,
+}
+
+#[cfg(any(test, feature = "fake-server"))]
+impl ReplayServer {
+    /// Starts serving the recorded frames on a random local port
+    pub fn start(frames: Vec<RecordedFrame>) -> io::Result<ReplayServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let handle = thread::spawn(move || loop {
+            let (stream, _) = listener.accept()?;
+            if replay(stream, &frames)? {
+                return Ok(());
+            }
+        });
+        Ok(ReplayServer { addr, handle })
+    }
+
+    /// Starts serving the session recorded to the file
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<ReplayServer> {
+        let mut file = io::BufReader::new(std::fs::File::open(path)?);
+        ReplayServer::start(read_session(&mut file)?)
+    }
+
+    /// Address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Client configuration pointing to this server
+    #[cfg(not(feature = "ssl"))]
+    pub fn client_config(&self) -> ClientConfig {
+        ClientConfig::new(self.addr.to_string().as_str())
+    }
+
+    /// Waits until the client disconnects. Fails if the client diverged from the recording
+    /// or disconnected before the whole session was replayed
+    pub fn join(self) -> io::Result<()> {
+        self.handle
+            .join()
+            .map_err(|_| io::Error::other("Replay thread panicked"))?
+    }
+}
+
+/// Serves the session to the connected client. A handshake offering a newer version
+/// than the recorded one is rejected with that version, as the recorded server did.
+/// Returns false then, the client is expected to connect again
+#[cfg(any(test, feature = "fake-server"))]
+fn replay(stream: TcpStream, frames: &[RecordedFrame]) -> io::Result<bool> {
+    let mut reader = io::BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    for (i, frame) in frames.iter().enumerate() {
+        match frame.direction {
+            Direction::Request => {
+                let mut request = read_frame(&mut reader)?;
+                if i == 0 {
+                    request = redact_handshake(&request)?;
+                    let version = handshake_version(&frame.bytes)?;
+                    if handshake_version(&request)? > version {
+                        reject_handshake(&mut writer, version)?;
+                        return Ok(false);
+                    }
+                }
+                if request != frame.bytes {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Frame {} differs from the recorded request", i),
+                    ));
+                }
+            }
+            Direction::Response => {
+                writer.write_all(&frame.bytes)?;
+                writer.flush()?;
+            }
+        }
+    }
+    match read_i32(&mut reader) {
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(true),
+        Err(err) => Err(err),
+        Ok(_) => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Request after the end of the recorded session",
+        )),
+    }
+}
+
+/// Writes a handshake failure reporting the version the server supports
+#[cfg(any(test, feature = "fake-server"))]
+fn reject_handshake(writer: &mut impl Write, version: (i16, i16, i16)) -> io::Result<()> {
+    let mut resp = Vec::new();
+    write_u8(&mut resp, 0)?;
+    write_i16(&mut resp, version.0)?;
+    write_i16(&mut resp, version.1)?;
+    write_i16(&mut resp, version.2)?;
+    write_string_type_code(&mut resp, "Unsupported version")?;
+    write_i32(&mut resp, StatusCode::Failed.into())?;
+    write_i32(writer, resp.len() as i32)?;
+    writer.write_all(&resp)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheConfiguration;
+    use crate::fake_server::FakeServer;
+    use crate::{new_client, Ignite};
+    use std::path::PathBuf;
+
+    fn session_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ignite-rs-{}-{}.session", name, std::process::id()))
+    }
+
+    /// Puts and reads back the value. Returns the result of every operation
+    fn run_session(conf: crate::ClientConfig) -> crate::error::Result<(Option<String>, i64)> {
+        let mut client = new_client(conf)?;
+        let cache = client.get_cache::<i32, String>("recorded")?;
+        cache.put(&1, &"one".to_string())?;
+        Ok((cache.get(&1)?, cache.get_size()?))
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = session_path("replay");
+        let server = FakeServer::start().unwrap();
+        server.add_cache(CacheConfiguration::new("recorded"));
+        let mut conf = server.client_config();
+        conf.session_record_path = Some(path.clone());
+        let recorded = run_session(conf).unwrap();
+        assert_eq!(recorded, (Some("one".to_string()), 1));
+
+        let frames = read_session(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        // handshake, get config, put, get, get size
+        assert_eq!(frames.len(), 10);
+        assert_eq!(frames[0].direction, Direction::Request);
+        assert_eq!(frames[9].direction, Direction::Response);
+
+        let nodes = crate::protocol::inspect::inspect_session(&frames).unwrap();
+        assert_eq!(nodes[0].label, "handshake request");
+        assert_eq!(nodes[7].children[3].label, "String (9) = \"one\"");
+
+        let replay = ReplayServer::from_file(&path).unwrap();
+        assert_eq!(run_session(replay.client_config()).unwrap(), recorded);
+        replay.join().unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_record_old_version() {
+        let path = session_path("old-version");
+        let server = FakeServer::start_with_version((1, 2, 0)).unwrap();
+        server.add_cache(CacheConfiguration::new("recorded"));
+        let mut conf = server.client_config();
+        conf.session_record_path = Some(path.clone());
+        let recorded = run_session(conf).unwrap();
+
+        // the rejected handshake is overwritten by the one with the server's version
+        let frames = read_session(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(frames.len(), 10);
+        let nodes = crate::protocol::inspect::inspect_session(&frames).unwrap();
+        assert_eq!(nodes[0].children[2].label, "version = 1.2.0");
+        assert_eq!(nodes[7].children[2].label, "status = 0");
+        assert_eq!(nodes[7].children[3].label, "String (9) = \"one\"");
+
+        let replay = ReplayServer::from_file(&path).unwrap();
+        assert_eq!(run_session(replay.client_config()).unwrap(), recorded);
+        replay.join().unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_credentials_are_redacted() {
+        let path = session_path("credentials");
+        let server = FakeServer::start().unwrap();
+        server.add_cache(CacheConfiguration::new("recorded"));
+        let with_credentials = |mut conf: crate::ClientConfig| {
+            conf.username = Some("ignite".to_string());
+            conf.password = Some("s3cr3t-pa55".to_string());
+            conf
+        };
+        let mut conf = with_credentials(server.client_config());
+        conf.session_record_path = Some(path.clone());
+        let recorded = run_session(conf).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let contains = |part: &[u8]| bytes.windows(part.len()).any(|window| window == part);
+        assert!(!contains(b"s3cr3t-pa55"));
+        assert!(!contains(b"ignite"));
+        assert!(contains(REDACTED.as_bytes()));
+
+        let frames = read_session(&mut bytes.as_slice()).unwrap();
+        let nodes = crate::protocol::inspect::inspect_session(&frames).unwrap();
+        assert_eq!(nodes[0].label, "handshake request");
+
+        // the replayed handshake is compared without the credentials
+        let replay = ReplayServer::start(frames).unwrap();
+        let replayed = run_session(with_credentials(replay.client_config())).unwrap();
+        assert_eq!(replayed, recorded);
+        replay.join().unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_mismatch() {
+        let server = FakeServer::start().unwrap();
+        server.add_cache(CacheConfiguration::new("recorded"));
+        let path = session_path("mismatch");
+        let mut conf = server.client_config();
+        conf.session_record_path = Some(path.clone());
+        run_session(conf).unwrap();
+
+        let mut frames = read_session(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // the recorded put request has a different value
+        let last = frames[4].bytes.len() - 1;
+        frames[4].bytes[last] = b'0';
+
+        let replay = ReplayServer::start(frames).unwrap();
+        assert!(run_session(replay.client_config()).is_err());
+        assert_eq!(replay.join().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    /// Sink failing every write, like a file on a full disk
+    struct FailingSink;
+
+    impl Write for FailingSink {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("No space left on device"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sink_failure() {
+        let frame = hex_literal::hex!("02 00 00 00 AB CD");
+        let mut stream =
+            RecordingStream::new(Cursor::new(frame.to_vec()), Some(Box::new(FailingSink)));
+        let mut read = [0; 6];
+        stream.read_exact(&mut read).unwrap();
+        assert_eq!(read, frame);
+        assert!(stream.sink.is_none());
+
+        // the client's I/O goes on without the recording
+        stream.write_all(&frame).unwrap();
+        stream.flush().unwrap();
+        assert_eq!(stream.inner.get_ref()[6..], frame);
+    }
+}
@@ -0,0 +1,188 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::key_value::CacheDataObjectResp;
+use crate::api::service::{
+    ServiceGetDescriptorReq, ServiceGetDescriptorResp, ServiceGetDescriptorsReq,
+    ServiceGetDescriptorsResp, ServiceInvokeReq,
+};
+use crate::api::OpCode;
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::utils::duration_to_millis;
+use crate::{ReadableType, Uuid, WritableType};
+
+/// Platform a service is implemented on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ServicePlatform {
+    Java = 0,
+    DotNet = 1,
+}
+
+impl TryFrom<u8> for ServicePlatform {
+    type Error = Error;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ServicePlatform::Java),
+            1 => Ok(ServicePlatform::DotNet),
+            _ => Err(Error::from("Cannot read ServicePlatform")),
+        }
+    }
+}
+
+/// Deployment of a service in the cluster
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceDescriptor {
+    pub name: String,
+    /// Class name of the service implementation
+    pub service_class: String,
+    /// Max number of instances in the cluster, 0 for no limit
+    pub total_count: i32,
+    /// Max number of instances per node, 0 for no limit
+    pub max_per_node_count: i32,
+    /// Cache the service is collocated with
+    pub cache_name: Option<String>,
+    /// Node the service was deployed from
+    pub origin_node_id: Option<Uuid>,
+    pub platform: ServicePlatform,
+}
+
+/// Invokes methods of the services deployed in the cluster
+#[derive(Clone)]
+pub struct Services {
+    conn: Arc<Connection>,
+    timeout: Option<Duration>,
+}
+
+impl Services {
+    pub(crate) fn new(conn: Arc<Connection>) -> Services {
+        Services {
+            conn,
+            timeout: None,
+        }
+    }
+
+    /// Invocations fail if the service does not return in time. There is no timeout by default
+    pub fn with_timeout(&self, timeout: Duration) -> Services {
+        Services {
+            conn: self.conn.clone(),
+            timeout: Some(timeout),
+        }
+    }
+
+    /// Calls the method of the service and returns its result.
+    /// The method is found by its name and the number of arguments.
+    /// https://ignite.apache.org/docs/latest/binary-client-protocol/service-api#op_service_invoke
+    pub fn invoke<R: ReadableType>(
+        &self,
+        name: &str,
+        method: &str,
+        args: &[&dyn WritableType],
+    ) -> Result<Option<R>> {
+        let timeout = self.timeout.map_or(0, duration_to_millis);
+        self.conn
+            .send_and_read(
+                OpCode::ServiceInvoke,
+                ServiceInvokeReq {
+                    name,
+                    timeout,
+                    method,
+                    args,
+                },
+            )
+            .map(|resp: CacheDataObjectResp<R>| resp.val)
+    }
+
+    /// Returns the descriptors of all the deployed services
+    pub fn get_descriptors(&self) -> Result<Vec<ServiceDescriptor>> {
+        let resp: ServiceGetDescriptorsResp = self
+            .conn
+            .send_and_read(OpCode::ServiceGetDescriptors, ServiceGetDescriptorsReq {})?;
+        Ok(resp.descriptors)
+    }
+
+    /// Returns the descriptor of the service. Fails if there is no such service
+    pub fn get_descriptor(&self, name: &str) -> Result<ServiceDescriptor> {
+        let resp: ServiceGetDescriptorResp = self.conn.send_and_read(
+            OpCode::ServiceGetDescriptor,
+            ServiceGetDescriptorReq { name },
+        )?;
+        Ok(resp.descriptor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StatusCode;
+    use crate::fake_server::FakeServer;
+    use crate::protocol::complex_obj::IgniteValue;
+
+    fn descriptor(name: &str) -> ServiceDescriptor {
+        ServiceDescriptor {
+            name: name.to_string(),
+            service_class: "com.example.PricingService".to_string(),
+            total_count: 1,
+            max_per_node_count: 0,
+            cache_name: None,
+            origin_node_id: Some(Uuid::from(1)),
+            platform: ServicePlatform::Java,
+        }
+    }
+
+    fn start_server() -> FakeServer {
+        let server = FakeServer::start().unwrap();
+        server.add_service(descriptor("pricing"), |method, args| match method {
+            "price" => match args.as_slice() {
+                [IgniteValue::String(item), IgniteValue::Int(count)] => {
+                    Ok(IgniteValue::Long(item.len() as i64 * *count as i64))
+                }
+                _ => Err(Error::from("Wrong arguments")),
+            },
+            "reset" => Ok(IgniteValue::Null),
+            _ => Err(Error::from("Unknown method")),
+        });
+        server
+    }
+
+    #[test]
+    fn test_invoke() {
+        let server = start_server();
+        let mut client = server.connect().unwrap();
+        let services = client.services().with_timeout(Duration::from_secs(5));
+
+        let price: Option<i64> = services
+            .invoke("pricing", "price", &[&"apple".to_string(), &3i32])
+            .unwrap();
+        assert_eq!(price, Some(15));
+        let none: Option<i64> = services.invoke("pricing", "reset", &[]).unwrap();
+        assert_eq!(none, None);
+
+        assert!(services
+            .invoke::<i64>("pricing", "price", &[&1i32])
+            .is_err());
+        match services.invoke::<i64>("missing", "price", &[]) {
+            Err(Error::Server { code, .. }) => assert_eq!(code, StatusCode::Failed),
+            _ => panic!("missing service is invoked"),
+        }
+    }
+
+    #[test]
+    fn test_descriptors() {
+        let server = start_server();
+        let mut client = server.connect().unwrap();
+        let services = client.services();
+
+        assert_eq!(
+            services.get_descriptors().unwrap(),
+            vec![descriptor("pricing")]
+        );
+        assert_eq!(
+            services.get_descriptor("pricing").unwrap(),
+            descriptor("pricing")
+        );
+        assert!(services.get_descriptor("missing").is_err());
+    }
+}
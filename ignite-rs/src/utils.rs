@@ -1,3 +1,6 @@
+use std::convert::TryFrom;
+use std::time::Duration;
+
 use crate::protocol::complex_obj::IgniteField;
 
 /// Converts string into Java-like hash code
@@ -5,17 +8,16 @@ use crate::protocol::complex_obj::IgniteField;
 // because _sometimes_ it needs to be upper case, like in CacheGetConfigReq
 pub fn string_to_java_hashcode(value: &str) -> i32 {
     let mut hash: i32 = 0;
-    for char in value.chars().into_iter() {
+    for char in value.chars() {
         hash = 31i32.overflowing_mul(hash).0 + char as i32;
     }
     hash
 }
 
 pub fn bytes_to_java_hashcode(data: &[u8]) -> i32 {
-    let len = data.len();
     let mut h: i32 = 1;
-    for i in 0..len {
-        h = h.wrapping_mul(31).wrapping_add(i32::from(data[i] as i8));
+    for b in data {
+        h = h.wrapping_mul(31).wrapping_add(i32::from(*b as i8));
     }
     h
 }
@@ -44,9 +46,22 @@ pub fn get_schema_id(fields: &[IgniteField]) -> i32 {
         })
 }
 
+/// Converts the duration into milliseconds sent as a long. Longer durations are capped
+pub(crate) fn duration_to_millis(duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cache::ExpiryDuration;
+
+    #[test]
+    fn test_duration_to_millis() {
+        assert_eq!(duration_to_millis(Duration::from_micros(1500)), 1);
+        assert_eq!(duration_to_millis(Duration::MAX), i64::MAX);
+        assert_eq!(i64::from(ExpiryDuration::After(Duration::MAX)), i64::MAX);
+    }
 
     #[test]
     fn test_str_hash() {
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod derive_test {
+    use ignite_rs::protocol::{CollectionKind, TypeCode};
+    use ignite_rs::{ReadableType, WritableType};
+    use ignite_rs_derive::IgniteObj;
+    use std::collections::{BTreeMap, HashMap};
+    use std::io::Cursor;
+
+    /// Writes the value and reads it back
+    fn round_trip<T: WritableType + ReadableType>(value: &T) -> T {
+        let mut bytes = Vec::new();
+        value.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), value.size());
+        T::read(&mut Cursor::new(bytes)).unwrap().unwrap()
+    }
+
+    #[derive(IgniteObj, Debug, PartialEq)]
+    struct Inventory {
+        counts: HashMap<String, i32>,
+        prices: BTreeMap<i64, String>,
+    }
+
+    #[test]
+    fn should_round_trip_map_fields() {
+        let mut counts = HashMap::new();
+        counts.insert("apple".to_string(), 3);
+        counts.insert("pear".to_string(), 0);
+        let mut prices = BTreeMap::new();
+        prices.insert(10, "cheap".to_string());
+        prices.insert(99, "expensive".to_string());
+        let inventory = Inventory { counts, prices };
+
+        assert_eq!(round_trip(&inventory), inventory);
+
+        let empty = Inventory {
+            counts: HashMap::new(),
+            prices: BTreeMap::new(),
+        };
+        assert_eq!(round_trip(&empty), empty);
+    }
+
+    #[derive(IgniteObj, Debug, PartialEq)]
+    struct Contact {
+        name: String,
+        phone: Option<String>,
+    }
+
+    #[test]
+    fn should_read_null_option_field_as_none() {
+        let contact = Contact {
+            name: "a".to_string(),
+            phone: None,
+        };
+        assert_eq!(round_trip(&contact), contact);
+
+        let contact = Contact {
+            name: "b".to_string(),
+            phone: Some("123".to_string()),
+        };
+        assert_eq!(round_trip(&contact), contact);
+    }
+
+    #[derive(IgniteObj, Debug, PartialEq)]
+    #[ignite(collection)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(IgniteObj, Debug, PartialEq)]
+    struct Path {
+        points: Vec<Point>,
+    }
+
+    // without #[ignite(collection)] the marker could still be implemented by hand
+    impl ignite_rs::IgniteObj for Path {}
+
+    #[test]
+    fn should_pack_vec_as_array_list() {
+        let points = vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+        let mut bytes = Vec::new();
+        points.write(&mut bytes).unwrap();
+        assert_eq!(bytes[0], TypeCode::Collection as u8);
+        assert_eq!(&bytes[1..5], &2i32.to_le_bytes());
+        assert_eq!(bytes[5], CollectionKind::ArrayList as u8);
+
+        let path = Path { points };
+        assert_eq!(round_trip(&path), path);
+        let paths = vec![path];
+        assert_eq!(round_trip(&paths), paths);
+    }
+}
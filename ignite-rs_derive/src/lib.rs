@@ -1,9 +1,12 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::*;
 use syn::spanned::Spanned;
-use syn::{Data, DeriveInput, Fields, FieldsNamed};
+use syn::{Attribute, Data, DeriveInput, Fields, FieldsNamed, Meta, NestedMeta, Type};
 
-#[proc_macro_derive(IgniteObj)]
+/// Implements WritableType and ReadableType for a named struct.
+/// With `#[ignite(collection)]` also implements the IgniteObj marker, so `Vec<T>` of the struct
+/// is packed as Java's ArrayList
+#[proc_macro_derive(IgniteObj, attributes(ignite))]
 pub fn derive_ignite_obj(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(item as DeriveInput);
 
@@ -13,10 +16,16 @@ pub fn derive_ignite_obj(item: proc_macro::TokenStream) -> proc_macro::TokenStre
             Fields::Named(ref fields) => {
                 let write_tokens = impl_write_type(type_name, fields);
                 let read_tokens = impl_read_type(type_name, fields);
+                let marker_tokens = match is_collection_element(&input.attrs) {
+                    Ok(true) => quote! { impl ignite_rs::IgniteObj for #type_name {} },
+                    Ok(false) => quote! {},
+                    Err(err) => err.to_compile_error(),
+                };
 
                 quote! {
                     #write_tokens
                     #read_tokens
+                    #marker_tokens
                 }
             }
             _ => quote_spanned! { st.fields.span() => compile_error!("Named struct expected!");},
@@ -27,6 +36,37 @@ pub fn derive_ignite_obj(item: proc_macro::TokenStream) -> proc_macro::TokenStre
     proc_macro::TokenStream::from(output)
 }
 
+/// Checks the `#[ignite(...)]` attributes for the `collection` option
+fn is_collection_element(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut collection = false;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("ignite")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested.iter() {
+                    match nested {
+                        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("collection") => {
+                            collection = true
+                        }
+                        _ => {
+                            return Err(syn::Error::new(
+                                nested.span(),
+                                "Unknown ignite option, `collection` expected",
+                            ))
+                        }
+                    }
+                }
+            }
+            meta => {
+                return Err(syn::Error::new(
+                    meta.span(),
+                    "Expected #[ignite(collection)]",
+                ))
+            }
+        }
+    }
+    Ok(collection)
+}
+
 /// Implements ignite_rs::WritableType trait
 fn impl_write_type(type_name: &Ident, fields: &FieldsNamed) -> TokenStream {
     let type_id: i32 = get_type_id(type_name);
@@ -88,12 +128,31 @@ fn impl_read_type(type_name: &Ident, fields: &FieldsNamed) -> TokenStream {
     let exp_type_id: i32 = get_type_id(type_name);
     let fields_count = fields.named.len();
 
+    // read schema. an empty struct has no schema entries
+    let schema_read = if fields_count > 0 {
+        quote! {
+            for _ in 0..#fields_count {
+                ignite_rs::protocol::read_i64(reader)?; // read one field (id and offset)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let fields_read = fields.named.iter().map(|f| {
         let field_name = &f.ident;
         let ty = &f.ty;
         let formatted_name = format_ident!("_{}", field_name.as_ref().unwrap().to_string());
-        quote_spanned! { field_name.span() =>
-            let #formatted_name = <#ty>::read(reader)?.unwrap(); // get option value
+        if is_option(ty) {
+            quote_spanned! { field_name.span() =>
+                let #formatted_name = <#ty>::read(reader)?.unwrap_or(None); // NULL is read as None
+            }
+        } else {
+            quote_spanned! { field_name.span() =>
+                let #formatted_name = <#ty>::read(reader)?.ok_or_else(|| {
+                    ignite_rs::error::Error::from(concat!("NULL is not expected for field ", stringify!(#field_name)))
+                })?; // get option value
+            }
         }
     });
 
@@ -136,10 +195,7 @@ fn impl_read_type(type_name: &Ident, fields: &FieldsNamed) -> TokenStream {
 
                         #( #fields_read)*
 
-                        // read schema
-                        for _ in 0..#fields_count {
-                            ignite_rs::protocol::read_i64(reader)?; // read one field (id and offset)
-                        }
+                        #schema_read
 
                         Some(
                             #type_name{
@@ -154,6 +210,18 @@ fn impl_read_type(type_name: &Ident, fields: &FieldsNamed) -> TokenStream {
     }
 }
 
+/// Checks if the field type is written as Option<T>
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
 /// Schema ID based on field hashcodes
 fn get_schema_id(fields: &FieldsNamed) -> i32 {
     fields
@@ -189,7 +257,7 @@ const FNV1_PRIME: i32 = 0x0100_0193;
 /// Converts string into Java-like hash code
 fn string_to_java_hashcode(value: &str) -> i32 {
     let mut hash: i32 = 0;
-    for char in value.chars().into_iter() {
+    for char in value.chars() {
         hash = 31i32.overflowing_mul(hash).0 + char as i32;
     }
     hash